@@ -1,5 +1,5 @@
 use crate::{graphics::TextureConfig, quicksilver_compat::Color};
-use crate::{CanvasConfig, DisplayConfig, GpuConfig, Rectangle, Vector};
+use crate::{CanvasConfig, DisplayConfig, GpuConfig, Rectangle, ScalingConfig, Vector};
 use web_sys::HtmlCanvasElement;
 
 #[derive(Default)]
@@ -32,6 +32,11 @@ impl PaddleConfig {
         self.display.gpu_config = config;
         self
     }
+    /// Scale the display automatically when the browser window or the canvas container is resized.
+    pub fn with_scaling(mut self, scaling: impl Into<ScalingConfig>) -> Self {
+        self.display.scaling = Some(scaling.into());
+        self
+    }
     pub fn with_background_color(mut self, color: Color) -> Self {
         self.display.background = Some(color);
         self
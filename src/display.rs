@@ -7,30 +7,47 @@
 //! The display accepts pre-tessellated and raw objects, using either `draw_mesh()` or `draw()` (on DisplayArea).
 
 mod canvas;
+mod context_loss;
 mod display_area;
 mod display_paint;
 mod display_tessellate;
 mod fit_strategy;
 mod gpu;
+mod mask;
+mod particles;
 mod render;
+mod scaling;
+mod shader_reload;
 mod text;
 
 pub use canvas::*;
+pub use context_loss::{WebGlContextLost, WebGlContextRestored};
 pub use display_area::*;
-pub use display_paint::DisplayPaint;
+pub use display_paint::{Blended, DisplayPaint, Repeated};
 pub use display_tessellate::DisplayTessellate;
 pub use fit_strategy::FitStrategy;
 pub use gpu::{
-    CustomShader, GpuConfig, GpuMesh, GpuTriangle, GpuVertex, RenderPipelineHandle,
-    UniformDescriptor, UniformList, UniformValue, VertexDescriptor,
+    BlendMode, CustomShader, GpuConfig, GpuMesh, GpuTriangle, GpuVertex, PreprocessedShader,
+    RenderPipelineHandle, RenderStats, ShaderPreprocessor, UniformDescriptor, UniformList,
+    UniformValue, VertexAttributeLayout, VertexAttributeValues, VertexDescriptor,
 };
+pub use mask::MaskMode;
+pub use particles::EmitterHandle;
 pub use render::*;
+pub use scaling::{DisplayResized, ScalingConfig, ScalingMode, ScalingTarget};
 pub use text::*;
 
 use crate::*;
-use crate::{graphics::AbstractMesh, Vector};
+use crate::{
+    graphics::AbstractMesh, graphics::GlyphPaint, graphics::SdfGlyphPaint, graphics::TilePaint,
+    Vector,
+};
 use crate::{graphics::ImageLoader, graphics::TextureConfig, quicksilver_compat::Color};
+use context_loss::ContextLossActivity;
 use div::DivHandle;
+use particles::{ParticleActivity, ParticlePaint, ParticleSystems};
+use scaling::ScalingActivity;
+use shader_reload::{ShaderReloadActivity, UnwatchShaders, WatchShaders};
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Element, HtmlCanvasElement};
 
@@ -43,6 +60,12 @@ pub struct Display {
     browser_region: Rectangle,
     /// Game / World coordinates. Used to refer to game objects and UI elements independently of resolution or size in the browser.
     game_coordinates: Vector,
+    /// Game coordinates as configured initially. Only differs from `game_coordinates` after the game area has been expanded by scaling.
+    configured_game_coordinates: Vector,
+    /// Policy to apply when the browser area changes
+    scaling: Option<ScalingConfig>,
+    /// Offset of the canvas inside the available area, as applied by the last scaling (in browser coordinates)
+    scaling_offset: Vector,
     /// A canvas with WebGL capabilities (covering the full display area)
     canvas: WebGLCanvas,
     /// Screen background color. A clear to this color is invoked every frame.
@@ -53,6 +76,13 @@ pub struct Display {
     tessellation_buffer: AbstractMesh,
     /// Sprites for images and animations
     asset_library: AssetLibrary,
+    /// Particle emitters, updated on every `UpdateWorld`
+    particles: ParticleSystems,
+    /// Tessellated once and reused for every particle and glyph
+    quad: AbstractMesh,
+    /// Counters of the last complete frame
+    render_stats: RenderStats,
+    publish_render_stats: bool,
 }
 
 pub struct DisplayConfig {
@@ -63,6 +93,10 @@ pub struct DisplayConfig {
     pub update_delay_ms: i32,
     pub background: Option<Color>,
     pub capture_touch: bool,
+    /// If set, the display is scaled automatically whenever the browser area changes.
+    pub scaling: Option<ScalingConfig>,
+    /// Publish the `RenderStats` of each frame as message, right after `EndOfFrame`.
+    pub publish_render_stats: bool,
 }
 impl Default for DisplayConfig {
     fn default() -> Self {
@@ -74,6 +108,8 @@ impl Default for DisplayConfig {
             gpu_config: Default::default(),
             background: None,
             capture_touch: true,
+            scaling: None,
+            publish_render_stats: false,
         }
     }
 }
@@ -94,8 +130,7 @@ impl Display {
         let parent_element = canvas.parent_element().expect("Canvas has no parent");
         if config.capture_touch {
             let parent_html = parent_element
-                .clone()
-                .dyn_into::<web_sys::HtmlElement>()
+                .dyn_ref::<web_sys::HtmlElement>()
                 .expect("Canvas parent is not an HTML element");
             parent_html
                 .style()
@@ -112,6 +147,10 @@ impl Display {
 
         // Initialize with game coordinates, which allows using them again for later calls
         let size = (game_coordinates.x as u32, game_coordinates.y as u32);
+        if config.scaling.is_some() {
+            // Resize events are handled asynchronously, once the display exists
+            ScalingActivity::register(&parent_element);
+        }
         div::init_ex_with_element(parent_element, (0, 0), Some(size))
             .expect("Div initialization failed");
 
@@ -123,6 +162,9 @@ impl Display {
 
         // For binding textures as they arrive
         ImageLoader::register(canvas.clone_webgl(), config.texture_config);
+        ContextLossActivity::register(canvas.html_element());
+        ParticleActivity::register();
+        ShaderReloadActivity::register();
 
         let background_color = config.background;
 
@@ -137,20 +179,56 @@ impl Display {
         )?;
         div.set_css("z-index", &(-1).to_string())?;
 
-        Ok(Self {
+        let mut quad = AbstractMesh::new();
+        Tessellate::tessellate(&ABSTRACT_SPACE, &mut quad);
+
+        let mut display = Self {
             asset_library: AssetLibrary::default(),
             canvas,
             browser_region,
             game_coordinates,
+            configured_game_coordinates: game_coordinates,
+            scaling: config.scaling,
+            scaling_offset: Vector::ZERO,
             background_color,
             div,
             tessellation_buffer: AbstractMesh::new(),
-        })
+            particles: ParticleSystems::new(config.update_delay_ms as f32),
+            quad,
+            render_stats: RenderStats::default(),
+            publish_render_stats: config.publish_render_stats,
+        };
+        if display.scaling.is_some() {
+            display.apply_scaling()?;
+        }
+        Ok(display)
     }
     pub(crate) fn canvas_mut(&mut self) -> &mut WebGLCanvas {
         &mut self.canvas
     }
 
+    /// Draw calls, triangles and state changes sent to the GPU during the last complete frame
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+    /// Publish the `RenderStats` after every frame, see `DisplayConfig::publish_render_stats`
+    pub fn set_publish_render_stats(&mut self, publish: bool) {
+        self.publish_render_stats = publish;
+    }
+    /// Store the counters of the finished frame and start counting anew. Returns them if they should be published.
+    pub(crate) fn end_frame(&mut self) -> Option<RenderStats> {
+        self.render_stats = self.canvas.take_render_stats();
+        if self.publish_render_stats {
+            Some(self.render_stats)
+        } else {
+            None
+        }
+    }
+
+    /// True while the browser has taken away the WebGL context. Draw calls are discarded during that time.
+    pub fn is_context_lost(&self) -> bool {
+        self.canvas.is_context_lost()
+    }
     /// Position relative to browser page and size in browser pixels
     pub fn browser_region(&self) -> Rectangle {
         self.browser_region
@@ -159,6 +237,10 @@ impl Display {
     pub fn resolution(&self) -> Vector {
         self.canvas.resolution()
     }
+    /// Size of the full display in game coordinates
+    pub fn game_size(&self) -> Vector {
+        self.game_coordinates
+    }
     /// Transformation to go from display coordinates to WebGL coordinates. Useful for custom shaders.
     pub fn webgl_transform(&self) -> Transform {
         Transform::scale((1.0, -1.0))
@@ -167,6 +249,8 @@ impl Display {
     }
 
     pub fn clear(&mut self) {
+        // Masks are not carried over from one frame to the next
+        self.canvas.reset_masks();
         if let Some(col) = self.background_color {
             self.canvas.clear(col);
         }
@@ -192,9 +276,86 @@ impl Display {
     }
 
     /// Gives result for x axis (assuming y is the same)
+    #[deprecated(
+        note = "Only correct while x and y are scaled equally, use `browser_to_game_pixel_ratios` instead"
+    )]
     pub fn browser_to_game_pixel_ratio(&self) -> f32 {
         self.browser_region.width() / self.game_coordinates.x
     }
+    /// Gives the ratio for x and y axis separately, which differ when the display is stretched
+    pub fn browser_to_game_pixel_ratios(&self) -> Vector {
+        self.browser_region
+            .size
+            .times(self.game_coordinates.recip())
+    }
+
+    /// Apply the configured `ScalingMode` to the current browser area.
+    ///
+    /// Paddle calls this automatically when the browser area changes, there is usually no need to call it manually.
+    /// Returns whether the display has changed, in which case `DisplayResized` is shared with frames.
+    pub fn apply_scaling(&mut self) -> PaddleResult<bool> {
+        let config = match self.scaling {
+            Some(config) => config,
+            None => return Ok(false),
+        };
+        let margin = Vector::new(config.margin, config.margin);
+        let available = self.available_area(config.target)? - margin;
+        let layout = config
+            .mode
+            .layout(available, self.configured_game_coordinates);
+
+        let previous_region = self.browser_region;
+        let game_size_changed = layout.game_size != self.game_coordinates;
+        if game_size_changed {
+            self.game_coordinates = layout.game_size;
+            self.canvas.set_resolution(layout.game_size);
+        }
+        self.canvas.set_layout(
+            layout.canvas_size,
+            layout.offset,
+            config.mode == ScalingMode::PixelPerfect,
+        );
+        self.scaling_offset = layout.offset;
+        self.adjust_display()?;
+        Ok(game_size_changed || previous_region != self.browser_region)
+    }
+
+    /// Available space for the canvas as considered by the configured scaling, or `None` without automatic scaling
+    pub(crate) fn scaling_available_area(&self) -> PaddleResult<Option<Vector>> {
+        match self.scaling {
+            Some(config) => self.available_area(config.target).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Available space for the canvas, in browser coordinates
+    fn available_area(&self, target: ScalingTarget) -> PaddleResult<Vector> {
+        match target {
+            ScalingTarget::Window => {
+                let web_window = web_sys::window().unwrap();
+                let w = web_window
+                    .inner_width()
+                    .map_err(JsError::from_js_value)?
+                    .as_f64()
+                    .unwrap();
+                let h = web_window
+                    .inner_height()
+                    .map_err(JsError::from_js_value)?
+                    .as_f64()
+                    .unwrap();
+                // Position of the canvas without the offset added by scaling
+                let origin = self.browser_region.pos - self.scaling_offset;
+                Ok(Vector::new(w as f32, h as f32) - origin)
+            }
+            ScalingTarget::Container => {
+                let parent =
+                    self.canvas.html_element().parent_element().ok_or_else(|| {
+                        ErrorMessage::technical("canvas has no parent".to_owned())
+                    })?;
+                Ok(Vector::new(parent.client_width(), parent.client_height()))
+            }
+        }
+    }
 
     /// Scale the display to make it fully visible keeping the ratio true.
     pub fn fit_to_visible_area(&mut self, margin: f64) -> PaddleResult<()> {
@@ -262,10 +423,13 @@ impl Display {
             web_window.scroll_y().unwrap() as i32,
         );
         div::reposition(x as i32 - scroll_x, y as i32 - scroll_y)?;
-        div::resize(
-            self.browser_region.size.x as u32,
-            self.browser_region.size.y as u32,
-        )?;
+        // Divs have been initialized with the configured game coordinates, which may differ from the current game coordinates
+        let div_size = self
+            .browser_region
+            .size
+            .times(self.configured_game_coordinates)
+            .times(self.game_coordinates.recip());
+        div::resize(div_size.x as u32, div_size.y as u32)?;
         Ok(())
     }
 
@@ -310,6 +474,213 @@ impl Display {
             z,
         );
     }
+    /// Draw all parts of a loaded SVG drawing, with their colors from the file.
+    ///
+    /// Without a position, the drawing is placed according to its view box.
+    pub fn draw_svg(
+        &mut self,
+        position: Option<(&Rectangle, FitStrategy)>,
+        svg: SvgDesc,
+        trans: &Transform,
+        z: i16,
+    ) {
+        let (view_box, num_parts) = match self.asset_library.lookup_svg(svg).nuts_check() {
+            Some(drawing) => (drawing.view_box(), drawing.parts().len()),
+            None => return,
+        };
+        let target = match position {
+            Some((pos, fit_strat)) => view_box.fit_into_ex(pos, fit_strat, true),
+            None => view_box,
+        };
+        // Shapes are positioned within the view box, hence they are drawn without individual positioning
+        let trans = *trans * view_box.project(&target);
+        for i in 0..num_parts {
+            // Parts are copied one at a time, drawing needs mutable access to the display that owns the asset library
+            let part = match self.asset_library.lookup_svg(svg) {
+                Ok(drawing) => drawing.parts()[i],
+                Err(_) => return,
+            };
+            self.draw_ex(None, &part.shape, &part.color, &trans, z);
+        }
+    }
+    /// Draw the tiles of a loaded tilemap that are visible through the camera.
+    ///
+    /// The camera area (in world coordinates of the map) is projected onto the target area.
+    /// Only tiles overlapping the camera are drawn, but they are not clipped at the border of the target area.
+    pub fn draw_tilemap(
+        &mut self,
+        map: TilemapDesc,
+        camera: &Rectangle,
+        target: &Rectangle,
+        trans: &Transform,
+        z: i16,
+    ) {
+        let tiles = match self.asset_library.lookup_tilemap(map).nuts_check() {
+            Some(map) => map.visible_tiles(camera),
+            None => return,
+        };
+        let trans = *trans * camera.project(target);
+        for tile in tiles {
+            let paint = match DisplayPaint::image(&tile.image, &self.asset_library) {
+                Some(sheet) => TilePaint::new(sheet, &tile),
+                None => continue,
+            };
+            self.draw_ex(None, &tile.area, &paint, &trans, z);
+        }
+    }
+    /// Draw a nine-slice image into the given area, keeping its corners unscaled.
+    pub fn draw_nine_slice(
+        &mut self,
+        area: &Rectangle,
+        nine_slice: &NineSlice,
+        trans: &Transform,
+        z: i16,
+    ) {
+        let image = match nine_slice.image(&self.asset_library) {
+            Some(image) => image.clone(),
+            None => return,
+        };
+        let size = (image.natural_width(), image.natural_height());
+        for (target, source) in nine_slice.slices(area, size) {
+            self.draw_ex(None, &target, &image.subimage_texels(source), trans, z);
+        }
+    }
+    /// Draw text with a bitmap font into the given area, placed according to the fit strategy of the text.
+    pub fn draw_text(&mut self, area: &Rectangle, text: &BitmapText, trans: &Transform, z: i16) {
+        let font = match self.asset_library.lookup_font(text.font).nuts_check() {
+            Some(font) => font,
+            None => return,
+        };
+        let scale = text.size.map(|s| s / font.line_height()).unwrap_or(1.0);
+        let max_width = if text.wrap {
+            Some(area.width() / scale)
+        } else {
+            None
+        };
+        let assets = &self.asset_library;
+        let items = rich_items(&text.text, font.line_height(), |name| {
+            let icon = assets.text_icon(name)?;
+            let image = DisplayPaint::image(&icon, assets)?;
+            Some(image.natural_width() / image.natural_height())
+        });
+        let layout = font.layout(&items, max_width, text.fit.alignment().0);
+        let size = layout.size;
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+        let block = Rectangle::new_sized(size * scale).fit_into_ex(area, text.fit, false);
+        let trans = *trans * Rectangle::new_sized(size).project(&block);
+        let sdf_paint = text.sdf.clone().unwrap_or_default();
+        let field = sdf_paint.field.or(font.distance_field());
+        // Scale from atlas texels to canvas pixels, which defines the sharpness of distance field edges
+        let screen_px_per_texel = (trans * Vector::X - trans * Vector::ZERO).len()
+            * self.canvas.resolution().x
+            / self.game_coordinates.x;
+        // Bold spans are drawn a second time, shifted to the right
+        let bold_offset = Vector::new(font.line_height() / 24.0, 0.0);
+        let mut page_uniforms = vec![None; font.pages.len()];
+        for glyph in layout.glyphs {
+            let page = match DisplayPaint::image(&font.pages[glyph.page], &self.asset_library) {
+                Some(page) => page,
+                None => continue,
+            };
+            let style = match &text.text.spans()[glyph.span] {
+                RichSpan::Text { style, .. } => *style,
+                RichSpan::Icon(_) => SpanStyle::default(),
+            };
+            let color = style.color.unwrap_or(text.color);
+            let offsets: &[Vector] = if style.bold {
+                &[Vector::ZERO, bold_offset]
+            } else {
+                &[Vector::ZERO]
+            };
+            for offset in offsets {
+                let image = page.subimage_texels(glyph.region);
+                let glyph_area = Rectangle::new(glyph.area.pos + *offset, glyph.area.size);
+                let glyph_trans = trans * ABSTRACT_SPACE.project(&glyph_area);
+                if let Some(field) = field {
+                    let uniforms = page_uniforms[glyph.page]
+                        .get_or_insert_with(|| {
+                            sdf_paint.uniforms(field, screen_px_per_texel, page.natural_size())
+                        })
+                        .clone();
+                    let paint = SdfGlyphPaint {
+                        image,
+                        color,
+                        uniforms,
+                    };
+                    self.canvas
+                        .render(&self.quad, &glyph_trans, &(&paint, &self.asset_library), z);
+                } else {
+                    let paint = GlyphPaint { image, color };
+                    self.canvas
+                        .render(&self.quad, &glyph_trans, &(&paint, &self.asset_library), z);
+                }
+            }
+        }
+        for (icon_area, span) in layout.icons {
+            let icon = match &text.text.spans()[span] {
+                RichSpan::Icon(name) => self.asset_library.text_icon(name),
+                RichSpan::Text { .. } => None,
+            };
+            if let Some(icon) = icon {
+                let icon_trans = trans * ABSTRACT_SPACE.project(&icon_area);
+                self.canvas
+                    .render(&self.quad, &icon_trans, &(&icon, &self.asset_library), z);
+            }
+        }
+    }
+    /// Draw all particles of an emitter, each as a square with the color and size of its current life stage.
+    ///
+    /// Particles of one emitter share texture and blend mode and therefore end up in a single draw call.
+    pub fn draw_particles(&mut self, emitter: EmitterHandle, trans: &Transform, z: i16) {
+        let emitter = match self.particles.get(emitter) {
+            Some(emitter) => emitter,
+            None => return,
+        };
+        for particle in emitter.particles() {
+            let paint = ParticlePaint {
+                image: emitter.image.as_ref(),
+                color: emitter.color.sample(particle.life()),
+                blend_mode: emitter.blend_mode,
+            };
+            let trans = *trans * ABSTRACT_SPACE.project(&emitter.particle_area(particle));
+            self.canvas
+                .render(&self.quad, &trans, &(&paint, &self.asset_library), z);
+        }
+    }
+    /// Add a particle emitter to the display. It is updated on every `UpdateWorld` until it is removed.
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) -> EmitterHandle {
+        self.particles.add(emitter)
+    }
+    pub fn emitter(&self, emitter: EmitterHandle) -> Option<&ParticleEmitter> {
+        self.particles.get(emitter)
+    }
+    pub fn emitter_mut(&mut self, emitter: EmitterHandle) -> Option<&mut ParticleEmitter> {
+        self.particles.get_mut(emitter)
+    }
+    pub fn remove_emitter(&mut self, emitter: EmitterHandle) -> Option<ParticleEmitter> {
+        self.particles.remove(emitter)
+    }
+    /// Draw a shape as mask. Until the mask is popped again, only the part inside or outside of the mask will be visible.
+    ///
+    /// Only the geometry of the shape is used, transparent parts of textures are not considered.
+    /// Masks can be nested, in which case draws have to satisfy all masks to be visible.
+    pub fn push_mask(
+        &mut self,
+        position: Option<(&Rectangle, FitStrategy)>,
+        shape: &impl DisplayTessellate,
+        trans: &Transform,
+        mode: MaskMode,
+    ) -> PaddleResult<()> {
+        self.canvas.begin_mask()?;
+        self.draw_ex(position, shape, &Color::WHITE, trans, 0);
+        self.canvas.end_mask(mode)
+    }
+    /// Remove the mask that was pushed last.
+    pub fn pop_mask(&mut self) -> PaddleResult<()> {
+        self.canvas.pop_mask()
+    }
     // Insert triangles to buffer with a transform and z value
     pub fn draw_mesh_ex(
         &mut self,
@@ -346,6 +717,52 @@ impl Display {
     ) {
         self.canvas.update_uniform(rp, name, value)
     }
+    /// Snippets for `#include` and defines applied to shader sources of new render pipelines
+    pub fn shader_preprocessor(&self) -> &ShaderPreprocessor {
+        self.canvas.shader_preprocessor()
+    }
+    pub fn shader_preprocessor_mut(&mut self) -> &mut ShaderPreprocessor {
+        self.canvas.shader_preprocessor_mut()
+    }
+    /// Compile a render pipeline again from new shader sources, keeping its handle and uniform values.
+    ///
+    /// If the sources fail to compile, the previous program stays in use and the error log is returned.
+    pub fn recompile_render_pipeline(
+        &mut self,
+        rp: RenderPipelineHandle,
+        vertex_shader_text: &str,
+        fragment_shader_text: &str,
+    ) -> PaddleResult<()> {
+        self.canvas
+            .recompile_render_pipeline(rp, vertex_shader_text, fragment_shader_text)
+    }
+    /// Development helper: Load the shader sources of a render pipeline from URLs and recompile it whenever they change.
+    ///
+    /// The URLs are polled every second. Compile errors are reported as technical errors, while the previous program stays in use.
+    pub fn watch_render_pipeline(
+        &mut self,
+        rp: RenderPipelineHandle,
+        vertex_url: impl Into<String>,
+        fragment_url: impl Into<String>,
+    ) {
+        nuts::send_to::<ShaderReloadActivity, _>(WatchShaders {
+            rp,
+            vertex_url: vertex_url.into(),
+            fragment_url: fragment_url.into(),
+        });
+    }
+    /// Stop polling the shader sources of a render pipeline
+    pub fn unwatch_render_pipeline(&mut self, rp: RenderPipelineHandle) {
+        nuts::send_to::<ShaderReloadActivity, _>(UnwatchShaders(rp));
+    }
+    /// Assets available for drawing
+    pub fn assets(&self) -> &AssetLibrary {
+        &self.asset_library
+    }
+    /// Mutable access to the assets, for example to register text icons
+    pub fn assets_mut(&mut self) -> &mut AssetLibrary {
+        &mut self.asset_library
+    }
     pub(super) fn asset_library(&mut self) -> &mut AssetLibrary {
         &mut self.asset_library
     }
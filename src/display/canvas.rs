@@ -7,9 +7,11 @@ pub(crate) const ABSTRACT_SPACE: Rectangle = Rectangle {
 };
 
 use super::gpu::{
-    new_fragment_shader, new_vertex_shader, Gpu, GpuConfig, GpuMesh, RenderPipelineHandle,
-    UniformValue, VertexDescriptor, WasmHeapBuffer,
+    Gpu, GpuConfig, GpuMesh, RenderPipelineHandle, RenderStats, ShaderPreprocessor, UniformValue,
+    VertexDescriptor, WasmHeapBuffer,
 };
+use super::mask::{mask_bit, stencil_test, MaskMode, MAX_MASKS};
+use crate::graphics::delete_released_textures;
 use crate::{
     quicksilver_compat::Color, ErrorMessage, JsError, NutsCheck, PaddleResult, Paint, Rectangle,
     Render, Transform, Vector,
@@ -25,6 +27,10 @@ pub(crate) struct WebGLCanvas {
     gl: WebGlRenderingContext,
     buffer: WasmHeapBuffer,
     gpu: Gpu,
+    /// While the WebGL context is lost, nothing is sent to the GPU
+    context_lost: bool,
+    /// Stack of active masks, each occupying one bit in the stencil buffer
+    masks: Vec<MaskMode>,
 }
 impl WebGLCanvas {
     /// Create a new webgl area in the given canvas.
@@ -46,8 +52,16 @@ impl WebGLCanvas {
             )
             .map_err(|_| ErrorMessage::technical("Failed setting canvas style".to_owned()))?;
 
+        // The stencil buffer is not part of the default context attributes
+        let context_options = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &context_options,
+            &"stencil".into(),
+            &gpu_config.stencil.into(),
+        )
+        .map_err(JsError::from_js_value)?;
         let gl = canvas
-            .get_context("webgl")
+            .get_context_with_context_options("webgl", &context_options)
             .map_err(|_| ErrorMessage::technical("Failed loading WebGL".to_owned()))?
             .unwrap()
             .dyn_into::<WebGlRenderingContext>()
@@ -55,12 +69,7 @@ impl WebGLCanvas {
 
         let buffer = WasmHeapBuffer::new();
 
-        // project screen space coordinates with origin at top left and y pointing down,
-        // to WebGL's [-1,-1] to [1,1] space with y pointing up
-        let projection = Transform::scale((1.0, -1.0))
-            * Transform::translate((-1.0, -1.0))
-            * Transform::scale(pixels.recip() * 2.0);
-        let gpu = Gpu::new(&gl, projection, gpu_config)?;
+        let gpu = Gpu::new(&gl, projection(pixels), gpu_config)?;
 
         let window = WebGLCanvas {
             pixels,
@@ -69,6 +78,8 @@ impl WebGLCanvas {
             gl,
             buffer,
             gpu,
+            context_lost: false,
+            masks: Vec::new(),
         };
         Ok(window)
     }
@@ -106,6 +117,55 @@ impl WebGLCanvas {
             .nuts_check();
     }
 
+    /// Resize the area the canvas takes in the browser and move it by an offset within its container. (In browser coordinates)
+    ///
+    /// With `pixelated`, the browser is told to not smooth the canvas when scaling it.
+    pub(crate) fn set_layout(&mut self, size: Vector, offset: Vector, pixelated: bool) {
+        // Only the properties owned by the scaling are changed, other styles of the canvas are kept
+        let style = self.canvas.style();
+        let mut result = [
+            ("width", size.x),
+            ("height", size.y),
+            ("margin-left", offset.x),
+            ("margin-top", offset.y),
+        ]
+        .iter()
+        .try_for_each(|(property, px)| style.set_property(property, &format!("{}px", px)));
+        if result.is_ok() {
+            result = if pixelated {
+                // Browsers ignore values they do not support, `crisp-edges` is the fallback for `pixelated`
+                style
+                    .set_property("image-rendering", "crisp-edges")
+                    .and_then(|_| style.set_property("image-rendering", "pixelated"))
+            } else {
+                style.remove_property("image-rendering").map(|_| ())
+            };
+        }
+        result
+            .map_err(JsError::from_js_value)
+            .map_err(ErrorMessage::from)
+            .nuts_check();
+    }
+
+    /// Change how many pixels are rendered in the canvas.
+    ///
+    /// The projection of the built-in render pipelines is updated accordingly, custom pipelines have to be updated by their owner.
+    pub(crate) fn set_resolution(&mut self, pixels: Vector) {
+        self.pixels = pixels;
+        self.canvas.set_width(pixels.x as u32);
+        self.canvas.set_height(pixels.y as u32);
+        self.gl.viewport(0, 0, pixels.x as i32, pixels.y as i32);
+        let builtin = self.gpu.builtin_pipelines;
+        for rp in [
+            RenderPipelineHandle::default(),
+            builtin.gradient,
+            builtin.sdf_text,
+        ] {
+            self.gpu
+                .update_uniform(&self.gl, rp, "Projection", &projection(pixels).into());
+        }
+    }
+
     /// Flush the current buffered draw calls
     ///
     /// Attributes like z-ordering will be reset: all items drawn after a flush will *always* draw
@@ -113,6 +173,11 @@ impl WebGLCanvas {
     ///
     /// Note that calling this can be an expensive operation
     pub fn flush(&mut self) -> PaddleResult<()> {
+        if self.context_lost {
+            self.mesh.clear();
+            delete_released_textures(&self.gl);
+            return Ok(());
+        }
         if self.gpu.depth_tests_enabled {
             // If depth tests are enabled, overdrawing can be avoided (for performance) by drawing closer sprites first
             self.mesh.triangles.sort_by(|a, b| b.cmp(a));
@@ -121,13 +186,45 @@ impl WebGLCanvas {
             // If depth tests are disabled, overdrawing has to be forced for correctness
             self.mesh.triangles.sort();
         }
-        self.gpu.perform_draw_calls(
+        let result = self.gpu.perform_draw_calls(
             &mut self.buffer,
             &self.gl,
             &self.mesh.vertices,
             self.mesh.triangles.as_slice(),
-        )?;
+        );
+        // Invalid draws are discarded, rather than failing again on every following flush
         self.mesh.clear();
+        delete_released_textures(&self.gl);
+        result
+    }
+
+    /// Counters collected since the last call, which resets them
+    pub(crate) fn take_render_stats(&mut self) -> RenderStats {
+        std::mem::take(&mut self.gpu.stats)
+    }
+
+    pub fn shader_preprocessor(&self) -> &ShaderPreprocessor {
+        &self.gpu.preprocessor
+    }
+    pub fn shader_preprocessor_mut(&mut self) -> &mut ShaderPreprocessor {
+        &mut self.gpu.preprocessor
+    }
+
+    pub fn is_context_lost(&self) -> bool {
+        self.context_lost
+    }
+    /// Stop drawing until the context is restored. Already queued draws are discarded.
+    pub(crate) fn on_context_lost(&mut self) {
+        self.context_lost = true;
+        self.mesh.clear();
+    }
+    /// Recreate all GPU resources owned by the canvas. Textures are restored separately by the `ImageLoader`.
+    pub(crate) fn on_context_restored(&mut self) -> PaddleResult<()> {
+        self.gpu.restore(&self.gl)?;
+        self.masks.clear();
+        self.gl
+            .viewport(0, 0, self.pixels.x as i32, self.pixels.y as i32);
+        self.context_lost = false;
         Ok(())
     }
 
@@ -137,8 +234,97 @@ impl WebGLCanvas {
             WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT,
         );
     }
+
+    /// Flushes previous draws and prepares the stencil buffer, such that the next draws define a new mask.
+    ///
+    /// Must be followed by `end_mask` after the mask shape has been rendered.
+    pub(crate) fn begin_mask(&mut self) -> PaddleResult<()> {
+        if !self.gpu.stencil_enabled {
+            return Err(ErrorMessage::technical(
+                "Masks require a stencil buffer, enable it with GpuConfig::with_stencil".to_owned(),
+            ));
+        }
+        if self.masks.len() >= MAX_MASKS {
+            return Err(ErrorMessage::technical(format!(
+                "Cannot push more than {} masks",
+                MAX_MASKS
+            )));
+        }
+        self.flush()?;
+        let bit = mask_bit(self.masks.len());
+        self.gl.enable(WebGlRenderingContext::STENCIL_TEST);
+        self.gl.stencil_mask(bit);
+        self.gl
+            .stencil_func(WebGlRenderingContext::ALWAYS, bit as i32, bit);
+        self.gl.stencil_op(
+            WebGlRenderingContext::KEEP,
+            WebGlRenderingContext::KEEP,
+            WebGlRenderingContext::REPLACE,
+        );
+        // The mask shape only goes to the stencil buffer, regardless of the depth of content drawn before
+        self.gl.color_mask(false, false, false, false);
+        self.gl.depth_mask(false);
+        if self.gpu.depth_tests_enabled {
+            self.gl.disable(WebGlRenderingContext::DEPTH_TEST);
+        }
+        Ok(())
+    }
+    /// Writes the mask rendered since `begin_mask` to the stencil buffer and activates it.
+    pub(crate) fn end_mask(&mut self, mode: MaskMode) -> PaddleResult<()> {
+        let result = self.flush();
+        // Restore the context state even if drawing the mask failed
+        self.gl.color_mask(true, true, true, true);
+        self.gl.depth_mask(true);
+        if self.gpu.depth_tests_enabled {
+            self.gl.enable(WebGlRenderingContext::DEPTH_TEST);
+        }
+        result?;
+        self.masks.push(mode);
+        self.apply_stencil_test();
+        Ok(())
+    }
+    /// Flushes draws that used the mask and removes the last pushed mask.
+    pub(crate) fn pop_mask(&mut self) -> PaddleResult<()> {
+        if self.masks.is_empty() {
+            return Err(ErrorMessage::technical(
+                "Cannot pop mask, no mask is active".to_owned(),
+            ));
+        }
+        self.flush()?;
+        self.masks.pop();
+        // Clear only the bit of the removed mask
+        self.gl.stencil_mask(mask_bit(self.masks.len()));
+        self.gl.clear(WebGlRenderingContext::STENCIL_BUFFER_BIT);
+        self.apply_stencil_test();
+        Ok(())
+    }
+    /// Removes all masks without flushing.
+    pub(crate) fn reset_masks(&mut self) {
+        if !self.masks.is_empty() {
+            self.masks.clear();
+            self.gl.stencil_mask(0xFF);
+            self.gl.clear(WebGlRenderingContext::STENCIL_BUFFER_BIT);
+            self.apply_stencil_test();
+        }
+    }
+    fn apply_stencil_test(&mut self) {
+        if self.masks.is_empty() {
+            self.gl.disable(WebGlRenderingContext::STENCIL_TEST);
+            return;
+        }
+        let (reference, bits) = stencil_test(&self.masks);
+        self.gl.stencil_mask(0);
+        self.gl
+            .stencil_func(WebGlRenderingContext::EQUAL, reference, bits);
+        self.gl.stencil_op(
+            WebGlRenderingContext::KEEP,
+            WebGlRenderingContext::KEEP,
+            WebGlRenderingContext::KEEP,
+        );
+    }
     /// If this RP is not already active, buffers will be flushed and RP is set
     pub fn ensure_render_pipeline(&mut self, rp: RenderPipelineHandle) -> PaddleResult<()> {
+        let rp = self.gpu.builtin_pipelines.resolve(rp);
         if rp != self.gpu.active_render_pipeline() {
             self.flush()?;
             self.gpu.use_render_pipeline(&self.gl, rp);
@@ -153,17 +339,25 @@ impl WebGLCanvas {
         vertex_descriptor: VertexDescriptor,
         uniform_values: &[(&'static str, UniformValue)],
     ) -> PaddleResult<RenderPipelineHandle> {
-        let vertex_shader = new_vertex_shader(&self.gl, vertex_shader_text)?;
-        let fragment_shader = new_fragment_shader(&self.gl, fragment_shader_text)?;
-
         self.gpu.new_render_pipeline(
             &self.gl,
-            vertex_shader,
-            fragment_shader,
+            vertex_shader_text,
+            fragment_shader_text,
             vertex_descriptor,
             uniform_values,
         )
     }
+    /// Compile a render pipeline again from new sources, after drawing everything queued with the previous program.
+    pub fn recompile_render_pipeline(
+        &mut self,
+        rp: RenderPipelineHandle,
+        vertex_shader_text: &str,
+        fragment_shader_text: &str,
+    ) -> PaddleResult<()> {
+        self.flush()?;
+        self.gpu
+            .recompile_render_pipeline(&self.gl, rp, vertex_shader_text, fragment_shader_text)
+    }
     pub fn update_uniform(
         &mut self,
         rp: RenderPipelineHandle,
@@ -179,3 +373,11 @@ impl Drop for WebGLCanvas {
         self.gpu.custom_drop(&self.gl);
     }
 }
+
+/// Project screen space coordinates with origin at top left and y pointing down,
+/// to WebGL's [-1,-1] to [1,1] space with y pointing up
+fn projection(pixels: Vector) -> Transform {
+    Transform::scale((1.0, -1.0))
+        * Transform::translate((-1.0, -1.0))
+        * Transform::scale(pixels.recip() * 2.0)
+}
@@ -0,0 +1,64 @@
+//! Recovery from a lost WebGL context.
+//!
+//! Browsers may drop the WebGL context at any time, for example when the GPU driver resets or the tab is in the background on mobile.
+//! Paddle tells the browser that it wants the context back and, once restored, recompiles all render pipelines,
+//! recreates the vertex and index buffers and uploads all textures again.
+//! Frames can observe both transitions with `FrameHandle::listen`.
+
+use crate::{graphics::RestoreTextures, js, Domain, NutsCheck};
+use wasm_bindgen::closure::Closure;
+use web_sys::Element;
+
+/// Shared with all frames when the WebGL context has been lost. Nothing is drawn until it is restored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebGlContextLost;
+
+/// Shared with all frames after the WebGL context has been restored and all GPU resources have been recreated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebGlContextRestored;
+
+/// Internal activity that keeps the browser listeners alive and forwards context events to the display.
+pub(crate) struct ContextLossActivity {
+    _lost_guard: Closure<dyn FnMut()>,
+    _restored_guard: Closure<dyn FnMut()>,
+}
+
+/// Published by the browser listeners, handled by `ContextLossActivity`.
+struct BrowserContextLost;
+/// Published by the browser listeners, handled by `ContextLossActivity`.
+struct BrowserContextRestored;
+
+impl ContextLossActivity {
+    pub(crate) fn register(canvas: &Element) {
+        let lost =
+            Closure::wrap(Box::new(|| nuts::publish(BrowserContextLost)) as Box<dyn FnMut()>);
+        let restored =
+            Closure::wrap(Box::new(|| nuts::publish(BrowserContextRestored)) as Box<dyn FnMut()>);
+        js::register_webgl_context_listeners(canvas, &lost, &restored);
+        let activity = nuts::new_domained_activity(
+            ContextLossActivity {
+                _lost_guard: lost,
+                _restored_guard: restored,
+            },
+            &Domain::Frame,
+        );
+        activity.subscribe_domained(|_, domain, _: &BrowserContextLost| {
+            let display = crate::Display::from_domain(domain);
+            display.canvas_mut().on_context_lost();
+            crate::share(WebGlContextLost);
+        });
+        activity.subscribe_domained(|_, domain, _: &BrowserContextRestored| {
+            let display = crate::Display::from_domain(domain);
+            if display
+                .canvas_mut()
+                .on_context_restored()
+                .nuts_check()
+                .is_some()
+            {
+                // Messages are delivered in order, hence textures are ready before frames are notified
+                nuts::publish(RestoreTextures);
+                crate::share(WebGlContextRestored);
+            }
+        });
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    error::NutsCheck, graphics::AbstractMesh, quicksilver_compat::Shape, Display, DisplayPaint,
-    DisplayTessellate, ErrorMessage, FitStrategy, Rectangle, RenderPipelineHandle, Transform,
-    Vector,
+    error::NutsCheck, graphics::AbstractMesh, quicksilver_compat::Shape, BitmapText, Display,
+    DisplayPaint, DisplayTessellate, EmitterHandle, ErrorMessage, FitStrategy, MaskMode, NineSlice,
+    ParticleEmitter, Rectangle, RenderPipelineHandle, SvgDesc, TilemapDesc, Transform, Vector,
 };
 use div::DivHandle;
 use web_sys::Element;
@@ -78,6 +78,51 @@ impl DisplayArea {
         self.display
             .draw_ex(Some((position, fit_strat)), shape, bkg, &trans, z)
     }
+    /// Draw a loaded SVG file, fitted into the given position.
+    pub fn draw_svg(&mut self, position: &Rectangle, svg: SvgDesc, fit_strat: FitStrategy, z: i16) {
+        let trans = self.frame_to_display_coordinates();
+        self.display
+            .draw_svg(Some((position, fit_strat)), svg, &trans, z)
+    }
+    /// Draw the part of a tilemap that is visible through the camera (in map coordinates) into the given position.
+    pub fn draw_tilemap(
+        &mut self,
+        map: TilemapDesc,
+        camera: &Rectangle,
+        position: &Rectangle,
+        z: i16,
+    ) {
+        let trans = self.frame_to_display_coordinates();
+        self.display.draw_tilemap(map, camera, position, &trans, z)
+    }
+    /// Draw a nine-slice image stretched (or tiled) over the given position, with unscaled corners.
+    pub fn draw_nine_slice(&mut self, position: &Rectangle, nine_slice: &NineSlice, z: i16) {
+        let trans = self.frame_to_display_coordinates();
+        self.display
+            .draw_nine_slice(position, nine_slice, &trans, z)
+    }
+    /// Draw text with a bitmap font, rendered in WebGL together with other sprites.
+    ///
+    /// The text is wrapped at the width of the position and placed inside it according to `BitmapText::with_fit`.
+    pub fn draw_text(&mut self, position: &Rectangle, text: &BitmapText, z: i16) {
+        let trans = self.frame_to_display_coordinates();
+        self.display.draw_text(position, text, &trans, z)
+    }
+    /// Draw all particles of an emitter. Emitter positions are in coordinates of this area.
+    pub fn draw_particles(&mut self, emitter: EmitterHandle, z: i16) {
+        let trans = self.frame_to_display_coordinates();
+        self.display.draw_particles(emitter, &trans, z)
+    }
+    /// Add a particle emitter, which is updated on every `UpdateWorld` until it is removed.
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) -> EmitterHandle {
+        self.display.add_emitter(emitter)
+    }
+    pub fn emitter_mut(&mut self, emitter: EmitterHandle) -> Option<&mut ParticleEmitter> {
+        self.display.emitter_mut(emitter)
+    }
+    pub fn remove_emitter(&mut self, emitter: EmitterHandle) -> Option<ParticleEmitter> {
+        self.display.remove_emitter(emitter)
+    }
     /// Draw a Drawable to the window with exhaustive flexibility.
     ///
     /// With this method, the drawn object has to be sized and positioned through transformations.
@@ -92,6 +137,38 @@ impl DisplayArea {
         let trans = self.frame_to_display_coordinates() * trans;
         self.display.draw_ex(None, draw, bkg, &trans, z)
     }
+    /// Draw a shape as a mask, stretched to the given position.
+    ///
+    /// Until `pop_mask` is called, everything drawn afterwards will only be visible inside (or outside) of the mask.
+    /// This requires a stencil buffer, see `GpuConfig::with_stencil`.
+    /// Masks are also removed at the start of every frame.
+    pub fn push_mask(
+        &mut self,
+        position: &Rectangle,
+        shape: &impl DisplayTessellate,
+        mode: MaskMode,
+    ) {
+        let trans = self.frame_to_display_coordinates();
+        self.display
+            .push_mask(Some((position, FitStrategy::Stretch)), shape, &trans, mode)
+            .nuts_check();
+    }
+    /// Like `push_mask` but the mask shape has to be sized and positioned through a transformation.
+    pub fn push_mask_ex(
+        &mut self,
+        shape: &impl DisplayTessellate,
+        trans: Transform,
+        mode: MaskMode,
+    ) {
+        let trans = self.frame_to_display_coordinates() * trans;
+        self.display
+            .push_mask(None, shape, &trans, mode)
+            .nuts_check();
+    }
+    /// Remove the last pushed mask. Draws before this call are still masked.
+    pub fn pop_mask(&mut self) {
+        self.display.pop_mask().nuts_check();
+    }
     /// Fills selected area with the given color (or image)
     pub fn fill(&mut self, bkg: &impl DisplayPaint) {
         let region = Rectangle::new_sized(self.region.size);
@@ -175,6 +252,27 @@ impl DisplayArea {
     ) {
         self.full_mut().update_uniform(rp, name, value)
     }
+    pub fn recompile_render_pipeline(
+        &mut self,
+        rp: RenderPipelineHandle,
+        vertex_shader_text: &str,
+        fragment_shader_text: &str,
+    ) -> crate::PaddleResult<()> {
+        self.full_mut()
+            .recompile_render_pipeline(rp, vertex_shader_text, fragment_shader_text)
+    }
+    pub fn watch_render_pipeline(
+        &mut self,
+        rp: RenderPipelineHandle,
+        vertex_url: impl Into<String>,
+        fragment_url: impl Into<String>,
+    ) {
+        self.full_mut()
+            .watch_render_pipeline(rp, vertex_url, fragment_url)
+    }
+    pub fn unwatch_render_pipeline(&mut self, rp: RenderPipelineHandle) {
+        self.full_mut().unwatch_render_pipeline(rp)
+    }
 }
 
 impl Into<DisplayArea> for Display {
@@ -1,6 +1,7 @@
 use crate::UniformList;
 use crate::{
-    quicksilver_compat::Color, AbstractVertex, AssetLibrary, Image, Paint, RenderPipelineHandle,
+    quicksilver_compat::Color, AbstractVertex, AssetLibrary, BlendMode, Image, Paint,
+    RenderPipelineHandle, Vector, VertexAttributeLayout,
 };
 
 /// Implementor of this trait can be used on `Display` and `DisplayArea` to fill geometric shapes.
@@ -21,12 +22,134 @@ pub trait DisplayPaint {
     ) -> Option<Vec<f32>> {
         None
     }
+    fn extra_vertex_attribute_layout(
+        &self,
+        _assets: &AssetLibrary,
+    ) -> Option<VertexAttributeLayout> {
+        None
+    }
     fn paint_uniforms(&self) -> UniformList {
         UniformList::default()
     }
     fn render_pipeline(&self, _assets: &AssetLibrary) -> RenderPipelineHandle {
         RenderPipelineHandle::default()
     }
+    fn blend_mode(&self, _assets: &AssetLibrary) -> BlendMode {
+        BlendMode::default()
+    }
+    fn texture_repeat(&self, _assets: &AssetLibrary) -> Vector {
+        Vector::ONE
+    }
+    /// Wrap the paint to repeat its image N times over the painted shape, along each axis.
+    ///
+    /// Works for images and image descriptors alike.
+    /// The image must use a repeating `WrapMode` in its `TextureConfig` and cover its full texture (no subimage).
+    /// Otherwise, the texture is stretched at its edges instead.
+    fn with_texture_repeat(self, repeat: impl Into<Vector>) -> Repeated<Self>
+    where
+        Self: Sized,
+    {
+        Repeated {
+            paint: self,
+            repeat: repeat.into(),
+        }
+    }
+    /// Wrap the paint to draw it with the given blend mode.
+    ///
+    /// Works for anything drawable, including image descriptors and other assets.
+    fn with_blend_mode(self, blend_mode: BlendMode) -> Blended<Self>
+    where
+        Self: Sized,
+    {
+        Blended {
+            paint: self,
+            blend_mode,
+        }
+    }
+}
+
+/// A paint drawn with a specific blend mode, created with `DisplayPaint::with_blend_mode`.
+#[derive(Clone, Debug)]
+pub struct Blended<P> {
+    paint: P,
+    blend_mode: BlendMode,
+}
+
+impl<P: DisplayPaint> DisplayPaint for Blended<P> {
+    fn image<'a>(&'a self, assets: &'a AssetLibrary) -> Option<&'a Image> {
+        self.paint.image(assets)
+    }
+    fn color(&self, assets: &AssetLibrary) -> Option<Color> {
+        self.paint.color(assets)
+    }
+    fn extra_vertex_attributes(
+        &self,
+        assets: &AssetLibrary,
+        index: usize,
+        vertex: &AbstractVertex,
+    ) -> Option<Vec<f32>> {
+        self.paint.extra_vertex_attributes(assets, index, vertex)
+    }
+    fn extra_vertex_attribute_layout(
+        &self,
+        assets: &AssetLibrary,
+    ) -> Option<VertexAttributeLayout> {
+        self.paint.extra_vertex_attribute_layout(assets)
+    }
+    fn paint_uniforms(&self) -> UniformList {
+        self.paint.paint_uniforms()
+    }
+    fn render_pipeline(&self, assets: &AssetLibrary) -> RenderPipelineHandle {
+        self.paint.render_pipeline(assets)
+    }
+    fn blend_mode(&self, _assets: &AssetLibrary) -> BlendMode {
+        self.blend_mode
+    }
+    fn texture_repeat(&self, assets: &AssetLibrary) -> Vector {
+        self.paint.texture_repeat(assets)
+    }
+}
+
+/// A paint with a repeated image, created with `DisplayPaint::with_texture_repeat`.
+#[derive(Clone, Debug)]
+pub struct Repeated<P> {
+    paint: P,
+    repeat: Vector,
+}
+
+impl<P: DisplayPaint> DisplayPaint for Repeated<P> {
+    fn image<'a>(&'a self, assets: &'a AssetLibrary) -> Option<&'a Image> {
+        self.paint.image(assets)
+    }
+    fn color(&self, assets: &AssetLibrary) -> Option<Color> {
+        self.paint.color(assets)
+    }
+    fn extra_vertex_attributes(
+        &self,
+        assets: &AssetLibrary,
+        index: usize,
+        vertex: &AbstractVertex,
+    ) -> Option<Vec<f32>> {
+        self.paint.extra_vertex_attributes(assets, index, vertex)
+    }
+    fn extra_vertex_attribute_layout(
+        &self,
+        assets: &AssetLibrary,
+    ) -> Option<VertexAttributeLayout> {
+        self.paint.extra_vertex_attribute_layout(assets)
+    }
+    fn paint_uniforms(&self) -> UniformList {
+        self.paint.paint_uniforms()
+    }
+    fn render_pipeline(&self, assets: &AssetLibrary) -> RenderPipelineHandle {
+        self.paint.render_pipeline(assets)
+    }
+    fn blend_mode(&self, assets: &AssetLibrary) -> BlendMode {
+        self.paint.blend_mode(assets)
+    }
+    fn texture_repeat(&self, _assets: &AssetLibrary) -> Vector {
+        self.repeat
+    }
 }
 
 /// A tuple of an asset library with anything that implements `DisplayPaint` also implements `Paint`.
@@ -45,12 +168,21 @@ impl<DP: DisplayPaint> Paint for (&DP, &AssetLibrary) {
     ) -> Option<Vec<f32>> {
         DisplayPaint::extra_vertex_attributes(self.0, &self.1, index, vertex)
     }
+    fn paint_extra_vertex_attribute_layout(&self) -> Option<VertexAttributeLayout> {
+        DisplayPaint::extra_vertex_attribute_layout(self.0, self.1)
+    }
     fn paint_uniforms(&self) -> UniformList {
         DisplayPaint::paint_uniforms(self.0)
     }
     fn paint_render_pipeline(&self) -> RenderPipelineHandle {
         DisplayPaint::render_pipeline(self.0, &self.1)
     }
+    fn paint_blend_mode(&self) -> BlendMode {
+        DisplayPaint::blend_mode(self.0, self.1)
+    }
+    fn paint_texture_repeat(&self) -> Vector {
+        DisplayPaint::texture_repeat(self.0, self.1)
+    }
 }
 
 // Anything that implements `Paint` also implements `DisplayPaint`.
@@ -70,12 +202,24 @@ impl<P: Paint> DisplayPaint for P {
     ) -> Option<Vec<f32>> {
         Paint::paint_extra_vertex_attributes(self, index, vertex)
     }
+    fn extra_vertex_attribute_layout(
+        &self,
+        _assets: &AssetLibrary,
+    ) -> Option<VertexAttributeLayout> {
+        Paint::paint_extra_vertex_attribute_layout(self)
+    }
     fn paint_uniforms(&self) -> UniformList {
         Paint::paint_uniforms(self)
     }
     fn render_pipeline(&self, _assets: &AssetLibrary) -> RenderPipelineHandle {
         Paint::paint_render_pipeline(self)
     }
+    fn blend_mode(&self, _assets: &AssetLibrary) -> BlendMode {
+        Paint::paint_blend_mode(self)
+    }
+    fn texture_repeat(&self, _assets: &AssetLibrary) -> Vector {
+        Paint::paint_texture_repeat(self)
+    }
 }
 
 // Enable `Box<DisplayPaint>`` to be used as a generic store for something that can be drawn.
@@ -97,10 +241,22 @@ impl DisplayPaint for Box<dyn DisplayPaint> {
     ) -> Option<Vec<f32>> {
         self.as_ref().extra_vertex_attributes(assets, index, vertex)
     }
+    fn extra_vertex_attribute_layout(
+        &self,
+        assets: &AssetLibrary,
+    ) -> Option<VertexAttributeLayout> {
+        self.as_ref().extra_vertex_attribute_layout(assets)
+    }
     fn paint_uniforms(&self) -> UniformList {
         self.as_ref().paint_uniforms()
     }
     fn render_pipeline(&self, assets: &AssetLibrary) -> RenderPipelineHandle {
         self.as_ref().render_pipeline(assets)
     }
+    fn blend_mode(&self, assets: &AssetLibrary) -> BlendMode {
+        self.as_ref().blend_mode(assets)
+    }
+    fn texture_repeat(&self, assets: &AssetLibrary) -> Vector {
+        self.as_ref().texture_repeat(assets)
+    }
 }
@@ -1,6 +1,109 @@
+use crate::{Rectangle, Vector};
+
+/// Defines how an object is placed (and potentially scaled) inside a frame.
+///
+/// The nine anchor variants keep the proportions of the object and align it to the named position in the frame.
+/// `Contain`, `Cover` and `Stretch` follow the semantics of the CSS `object-fit` property with the same name.
+/// (`FloatingText` implements them with CSS transforms, as `object-fit` has no effect on text.)
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum FitStrategy {
-    Center,
-    LeftCenter,
     TopLeft,
+    TopCenter,
+    TopRight,
+    LeftCenter,
+    Center,
+    RightCenter,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+    /// Fill the frame entirely, ignoring the proportions of the object.
+    Stretch,
+    /// Scale the object as large as possible while staying fully visible inside the frame and center it. Always grows if necessary.
+    Contain,
+    /// Scale the object such that it covers the frame entirely and center it. Parts of the object may be outside the frame.
+    Cover,
+}
+
+impl FitStrategy {
+    /// Relative alignment on x and y axis, where 0.0 is left/top and 1.0 is right/bottom.
+    pub fn alignment(&self) -> (f32, f32) {
+        match self {
+            FitStrategy::TopLeft => (0.0, 0.0),
+            FitStrategy::TopCenter => (0.5, 0.0),
+            FitStrategy::TopRight => (1.0, 0.0),
+            FitStrategy::LeftCenter => (0.0, 0.5),
+            FitStrategy::Center => (0.5, 0.5),
+            FitStrategy::RightCenter => (1.0, 0.5),
+            FitStrategy::BottomLeft => (0.0, 1.0),
+            FitStrategy::BottomCenter => (0.5, 1.0),
+            FitStrategy::BottomRight => (1.0, 1.0),
+            FitStrategy::Stretch | FitStrategy::Contain | FitStrategy::Cover => (0.5, 0.5),
+        }
+    }
+}
+
+/// Placement of centered content inside an HTML element, for the strategies that scale it.
+///
+/// Elements other than images and videos ignore `object-fit`, hence the element is scaled with a CSS transform instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CssFit {
+    /// Where the content ends up, relative to the element
+    pub area: Rectangle,
+    /// Scale of the element around its center
+    pub scale: Vector,
+    /// Horizontal and vertical inset that clips the scaled element to its original bounds, in unscaled pixels
+    pub clip: Vector,
+}
+
+impl FitStrategy {
+    /// True for `Stretch`, `Contain` and `Cover`, which change the size of the object
+    pub(crate) fn scales_content(&self) -> bool {
+        matches!(
+            self,
+            FitStrategy::Stretch | FitStrategy::Contain | FitStrategy::Cover
+        )
+    }
+    /// Transform of an element of size `frame` with centered content of size `content`, or `None` if the strategy does not scale content.
+    pub(crate) fn css_fit(&self, content: Vector, frame: Vector) -> Option<CssFit> {
+        if !self.scales_content() || content.x <= 0.0 || content.y <= 0.0 {
+            return None;
+        }
+        let area = Rectangle::new_sized(content).fit_into(&Rectangle::new_sized(frame), *self);
+        let scale = Vector::new(area.width() / content.x, area.height() / content.y);
+        let clip = Vector::new(
+            ((frame.x - frame.x / scale.x) / 2.0).max(0.0),
+            ((frame.y - frame.y / scale.y) / 2.0).max(0.0),
+        );
+        Some(CssFit { area, scale, clip })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_fit_contain_and_cover() {
+        let content = Vector::new(20, 20);
+        let frame = Vector::new(100, 50);
+
+        let contain = FitStrategy::Contain.css_fit(content, frame).unwrap();
+        assert_eq!(contain.area, Rectangle::new((25, 0), (50, 50)));
+        assert_eq!(contain.scale, Vector::new(2.5, 2.5));
+        // The grown element is clipped to its bounds, which the content stays within
+        assert_eq!(contain.clip, Vector::new(30, 15));
+
+        let cover = FitStrategy::Cover.css_fit(content, frame).unwrap();
+        assert_eq!(cover.area, Rectangle::new((0, -25), (100, 100)));
+        assert_eq!(cover.scale, Vector::new(5, 5));
+        // Only the middle 20x10 of the element remain visible after scaling
+        assert_eq!(cover.clip, Vector::new(40, 20));
+
+        let stretch = FitStrategy::Stretch.css_fit(content, frame).unwrap();
+        assert_eq!(stretch.area, Rectangle::new((0, 0), (100, 50)));
+        assert_eq!(stretch.scale, Vector::new(5, 2.5));
+
+        assert_eq!(FitStrategy::Center.css_fit(content, frame), None);
+        assert_eq!(FitStrategy::Cover.css_fit(Vector::ZERO, frame), None);
+    }
 }
@@ -1,3 +1,5 @@
+mod batch_split;
+mod blend_mode;
 mod gpu_buffers;
 mod gpu_config;
 mod gpu_mesh;
@@ -6,8 +8,11 @@ mod gpu_triangle;
 mod gpu_vertex;
 mod heap_buffer;
 mod render_pipeline;
+mod render_stats;
 mod shader;
 
+pub use blend_mode::BlendMode;
+use blend_mode::PREMULTIPLY_ALPHA_UNIFORM;
 pub use gpu_config::*;
 pub use gpu_mesh::*;
 pub(crate) use gpu_texture::sample;
@@ -15,21 +20,31 @@ pub use gpu_triangle::*;
 pub use gpu_vertex::*;
 pub(super) use heap_buffer::*;
 pub use render_pipeline::*;
+pub use render_stats::RenderStats;
 pub use shader::*;
 
+use self::batch_split::{split_batches, MAX_BATCH_VERTICES};
 use self::gpu_buffers::GpuBuffers;
-use crate::{PaddleResult, Transform};
+use crate::{NutsCheck, PaddleResult, Transform};
 use js_sys::Uint16Array;
-use web_sys::{WebGlRenderingContext, WebGlShader, WebGlTexture};
+use web_sys::{WebGlRenderingContext, WebGlTexture};
 
 pub(super) struct Gpu {
     gpu_buffers: GpuBuffers,
-    default_fragment_shader: WebGlShader,
-    default_vertex_shader: WebGlShader,
     active_render_pipeline: RenderPipelineHandle,
     render_pipelines: RenderPipelineContainer,
     // texture_location: Option<WebGlUniformLocation>,
     pub(crate) depth_tests_enabled: bool,
+    /// True if the context has a stencil buffer, which is only created when requested in the `GpuConfig`
+    pub(crate) stencil_enabled: bool,
+    /// As configured, which is necessary to set up a restored WebGL context in the same way
+    depth_tests_requested: bool,
+    /// Counters of the current frame
+    pub(crate) stats: RenderStats,
+    /// Applied to all shader sources before they are compiled
+    pub(crate) preprocessor: ShaderPreprocessor,
+    /// Handles of the built-in pipelines registered on this GPU, which paints refer to by placeholders
+    pub(crate) builtin_pipelines: BuiltinPipelines,
 }
 
 impl Gpu {
@@ -38,27 +53,7 @@ impl Gpu {
         projection: Transform,
         config: &GpuConfig,
     ) -> PaddleResult<Self> {
-        gl.blend_func_separate(
-            WebGlRenderingContext::SRC_ALPHA,
-            WebGlRenderingContext::ONE_MINUS_SRC_ALPHA,
-            WebGlRenderingContext::ONE,
-            WebGlRenderingContext::ONE_MINUS_SRC_ALPHA,
-        );
-        gl.enable(WebGlRenderingContext::BLEND);
-
-        let mut depth_tests_enabled = false;
-        if config.depth_test {
-            // If we can, we want to use the depth buffer for z ordering
-            gl.enable(WebGlRenderingContext::DEPTH_TEST);
-            depth_tests_enabled = gl.is_enabled(WebGlRenderingContext::DEPTH_TEST);
-            if depth_tests_enabled {
-                gl.clear_depth(0.0);
-                gl.depth_func(WebGlRenderingContext::GEQUAL);
-            }
-        }
-
-        let default_vertex_shader = new_vertex_shader(&gl, DEFAULT_VERTEX_SHADER)?;
-        let default_fragment_shader = new_fragment_shader(&gl, DEFAULT_FRAGMENT_SHADER)?;
+        let depth_tests_enabled = configure_context(gl, config.depth_test);
 
         let render_pipelines = RenderPipelineContainer::new();
 
@@ -66,26 +61,66 @@ impl Gpu {
 
         let mut gpu = Self {
             gpu_buffers,
-            default_vertex_shader,
-            default_fragment_shader,
             render_pipelines,
             depth_tests_enabled,
+            stencil_enabled: has_stencil_buffer(gl),
+            depth_tests_requested: config.depth_test,
             active_render_pipeline: Default::default(),
+            stats: RenderStats::default(),
+            preprocessor: ShaderPreprocessor::new(),
+            builtin_pipelines: BuiltinPipelines {
+                gradient: RenderPipelineHandle::default(),
+                sdf_text: RenderPipelineHandle::default(),
+            },
         };
 
         // Register default pipeline (Necessary to make `active_render_pipeline: Default::default()` valid)
         gpu.new_render_pipeline(
             gl,
-            gpu.default_vertex_shader.clone(),
-            gpu.default_fragment_shader.clone(),
+            DEFAULT_VERTEX_SHADER,
+            DEFAULT_FRAGMENT_SHADER,
+            VertexDescriptor::default(),
+            &[("Projection", projection.into())],
+        )?;
+        // Register built-in gradient pipeline (`RenderPipelineHandle::GRADIENT`)
+        let gradient = gpu.new_render_pipeline(
+            gl,
+            DEFAULT_VERTEX_SHADER,
+            GRADIENT_FRAGMENT_SHADER,
+            VertexDescriptor::default(),
+            &[("Projection", projection.into())],
+        )?;
+        // Register built-in distance field text pipeline (`RenderPipelineHandle::SDF_TEXT`)
+        let sdf_text = gpu.new_render_pipeline(
+            gl,
+            DEFAULT_VERTEX_SHADER,
+            SDF_TEXT_FRAGMENT_SHADER,
             VertexDescriptor::default(),
             &[("Projection", projection.into())],
         )?;
+        gpu.builtin_pipelines = BuiltinPipelines { gradient, sdf_text };
 
         Ok(gpu)
     }
 
+    /// Recreates all GPU resources after the WebGL context has been lost and restored.
+    ///
+    /// Render pipelines are compiled again from their sources and receive the last known uniform values.
+    /// Failing pipelines are reported individually and do not stop the others from being restored.
+    pub(super) fn restore(&mut self, gl: &WebGlRenderingContext) -> PaddleResult<()> {
+        self.depth_tests_enabled = configure_context(gl, self.depth_tests_requested);
+        self.gpu_buffers.restore(gl)?;
+        // Pipelines that failed to restore are reported, the others remain usable
+        self.render_pipelines.restore(gl).nuts_check();
+        gl.use_program(Some(
+            self.render_pipelines[self.active_render_pipeline].program(),
+        ));
+        Ok(())
+    }
+
     /// Takes the provided mesh and perform one or more draw calls (depending on number of textures & uniform values)
+    ///
+    /// Meshes with more vertices than 16-bit indices can address are drawn in several batches.
     pub(super) fn perform_draw_calls(
         &mut self,
         buffer: &mut WasmHeapBuffer,
@@ -93,16 +128,41 @@ impl Gpu {
         vertices: &[GpuVertex],
         triangles: &[GpuTriangle],
     ) -> PaddleResult<()> {
-        buffer.prepare_vertices(vertices, self.active_vertex_descriptor());
+        if !self.stats.record_flush(triangles.len()) {
+            return Ok(());
+        }
+        if vertices.len() <= MAX_BATCH_VERTICES {
+            return self.draw_batch(buffer, gl, vertices, triangles);
+        }
+        for (batch_vertices, batch_triangles) in
+            split_batches(vertices, triangles, MAX_BATCH_VERTICES)
+        {
+            self.draw_batch(buffer, gl, &batch_vertices, &batch_triangles)?;
+        }
+        Ok(())
+    }
+
+    /// Upload the vertices and draw the triangles, which must only reference vertices addressable with `u16` indices
+    fn draw_batch(
+        &mut self,
+        buffer: &mut WasmHeapBuffer,
+        gl: &WebGlRenderingContext,
+        vertices: &[GpuVertex],
+        triangles: &[GpuTriangle],
+    ) -> PaddleResult<()> {
+        self.stats.vertices += vertices.len() as u32;
+        buffer.prepare_vertices(vertices, self.active_vertex_descriptor())?;
         self.upload_vertices(gl, &buffer.vertex_data);
 
         // Scan through the triangles, adding the indices to the index buffer.
-        // Every time the texture or uniform values switch, flush and switch.
+        // Every time the texture, uniform values or blend mode switch, flush and switch.
         let mut current_texture: Option<&WebGlTexture> = None;
         let mut current_uniforms: &UniformList = &UniformList::default();
+        let mut current_blend_mode = BlendMode::default();
         for triangle in triangles.iter() {
             let tex = vertices[triangle.indices[0] as usize].tex();
             let uniform_changed = triangle.uniforms != *current_uniforms;
+            let blend_mode_changed = triangle.blend_mode != current_blend_mode;
             let texture_changed = if let Some(img) = tex {
                 match current_texture {
                     Some(val) => img != val,
@@ -112,7 +172,7 @@ impl Gpu {
                 false
             };
 
-            if texture_changed || uniform_changed {
+            if texture_changed || uniform_changed || blend_mode_changed {
                 self.draw_single_texture(
                     gl,
                     current_texture,
@@ -124,10 +184,22 @@ impl Gpu {
                 if let Some(img) = tex {
                     current_texture = Some(img);
                 }
-                current_uniforms = &triangle.uniforms;
-                self.render_pipelines[self.active_render_pipeline]
-                    .prepare_uniforms(gl, current_uniforms);
+                if uniform_changed {
+                    current_uniforms = &triangle.uniforms;
+                    self.stats.uniform_uploads += current_uniforms.len() as u32;
+                    self.render_pipelines[self.active_render_pipeline]
+                        .prepare_uniforms(gl, current_uniforms);
+                }
+                if blend_mode_changed {
+                    let premultiply = triangle.blend_mode.needs_premultiplied_output();
+                    if premultiply != current_blend_mode.needs_premultiplied_output() {
+                        self.set_premultiply_alpha(gl, premultiply);
+                    }
+                    current_blend_mode = triangle.blend_mode;
+                    current_blend_mode.apply(gl);
+                }
             }
+            debug_assert!(triangle.indices.iter().all(|n| *n <= u16::MAX as u32));
             buffer
                 .triangle_indices
                 .extend(triangle.indices.iter().map(|n| *n as u16));
@@ -137,9 +209,27 @@ impl Gpu {
             self.draw_single_texture(gl, current_texture, &buffer.triangle_indices);
             buffer.triangle_indices.clear();
         }
+        // Leave the context in the default state for the next flush
+        if current_blend_mode != BlendMode::default() {
+            BlendMode::default().apply(gl);
+        }
+        if current_blend_mode.needs_premultiplied_output() {
+            self.set_premultiply_alpha(gl, false);
+        }
         Ok(())
     }
 
+    /// Tell the shaders of the active pipeline whether to output premultiplied alpha, see `BlendMode`
+    fn set_premultiply_alpha(&mut self, gl: &WebGlRenderingContext, premultiply: bool) {
+        let value = UniformValue::F32(if premultiply { 1.0 } else { 0.0 });
+        self.stats.uniform_uploads += 1;
+        self.render_pipelines[self.active_render_pipeline].prepare_uniform(
+            gl,
+            PREMULTIPLY_ALPHA_UNIFORM,
+            &value,
+        );
+    }
+
     // Assumes that vertices area already uploaded, hence only the indices are needed as parameter
     fn draw_single_texture(
         &mut self,
@@ -163,7 +253,10 @@ impl Gpu {
                 WebGlRenderingContext::STREAM_DRAW,
             );
         }
+        self.stats.draw_calls += 1;
+        self.stats.triangles += indices.len() as u32 / 3;
         if texture.is_some() {
+            self.stats.texture_binds += 1;
             gl.active_texture(WebGlRenderingContext::TEXTURE0);
             gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, texture);
         }
@@ -197,17 +290,42 @@ impl Gpu {
         value: &super::gpu::UniformValue,
     ) {
         let stashed_rp = self.active_render_pipeline;
-        self.use_render_pipeline(gl, rp);
-        self.render_pipelines[rp].prepare_uniform(gl, name, value);
-        self.use_render_pipeline(gl, stashed_rp);
+        self.set_program(gl, rp);
+        self.render_pipelines[rp].set_uniform(gl, name, value);
+        self.stats.uniform_uploads += 1;
+        self.set_program(gl, stashed_rp);
+    }
+}
+
+fn has_stencil_buffer(gl: &WebGlRenderingContext) -> bool {
+    gl.get_parameter(WebGlRenderingContext::STENCIL_BITS)
+        .ok()
+        .and_then(|bits| bits.as_f64())
+        .unwrap_or(0.0)
+        > 0.0
+}
+
+/// Sets global state of the WebGL context. Returns whether depth tests are enabled.
+fn configure_context(gl: &WebGlRenderingContext, depth_test: bool) -> bool {
+    BlendMode::default().apply(gl);
+    gl.enable(WebGlRenderingContext::BLEND);
+
+    let mut depth_tests_enabled = false;
+    if depth_test {
+        // If we can, we want to use the depth buffer for z ordering
+        gl.enable(WebGlRenderingContext::DEPTH_TEST);
+        depth_tests_enabled = gl.is_enabled(WebGlRenderingContext::DEPTH_TEST);
+        if depth_tests_enabled {
+            gl.clear_depth(0.0);
+            gl.depth_func(WebGlRenderingContext::GEQUAL);
+        }
     }
+    depth_tests_enabled
 }
 
 impl Gpu {
     pub(super) fn custom_drop(&mut self, gl: &WebGlRenderingContext) {
         self.render_pipelines.drop_programs(gl);
         self.gpu_buffers.custom_drop(gl);
-        gl.delete_shader(Some(&self.default_fragment_shader));
-        gl.delete_shader(Some(&self.default_vertex_shader));
     }
 }
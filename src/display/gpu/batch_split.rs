@@ -0,0 +1,120 @@
+//! WebGL 1.0 draws indexed triangles with 16-bit indices, which limits how many vertices a single draw call can reference.
+//! Meshes above the limit are split into several batches, each with its own vertex list and re-numbered indices.
+
+use crate::{GpuTriangle, GpuVertex};
+
+/// Number of vertices addressable with `u16` indices
+pub(super) const MAX_BATCH_VERTICES: usize = u16::MAX as usize + 1;
+
+/// Index of a vertex not yet part of the current batch
+const NOT_IN_BATCH: u32 = u32::MAX;
+
+/// Split a mesh into batches that reference at most `max_vertices` vertices each.
+///
+/// Triangles keep their order, so drawing the batches one after another gives the same result as drawing the full mesh.
+pub(super) fn split_batches(
+    vertices: &[GpuVertex],
+    triangles: &[GpuTriangle],
+    max_vertices: usize,
+) -> Vec<(Vec<GpuVertex>, Vec<GpuTriangle>)> {
+    debug_assert!(max_vertices >= 3);
+    let mut batches = Vec::new();
+    // Index of each vertex inside the current batch
+    let mut local_index = vec![NOT_IN_BATCH; vertices.len()];
+    // Original indices of the vertices in the current batch
+    let mut used = Vec::new();
+    let mut batch_vertices = Vec::new();
+    let mut batch_triangles = Vec::new();
+    for triangle in triangles {
+        let new_vertices = triangle
+            .indices
+            .iter()
+            .filter(|i| local_index[**i as usize] == NOT_IN_BATCH)
+            .count();
+        if batch_vertices.len() + new_vertices > max_vertices {
+            for i in used.drain(..) {
+                local_index[i] = NOT_IN_BATCH;
+            }
+            batches.push((
+                std::mem::take(&mut batch_vertices),
+                std::mem::take(&mut batch_triangles),
+            ));
+        }
+        let mut triangle = triangle.clone();
+        for index in &mut triangle.indices {
+            let i = *index as usize;
+            if local_index[i] == NOT_IN_BATCH {
+                local_index[i] = batch_vertices.len() as u32;
+                batch_vertices.push(vertices[i].clone());
+                used.push(i);
+            }
+            *index = local_index[i];
+        }
+        batch_triangles.push(triangle);
+    }
+    if !batch_triangles.is_empty() {
+        batches.push((batch_vertices, batch_triangles));
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quicksilver_compat::Color;
+    use crate::Vector;
+
+    fn vertices(n: usize) -> Vec<GpuVertex> {
+        (0..n)
+            .map(|i| {
+                GpuVertex::new(
+                    Vector::new(i as f32, 0),
+                    None,
+                    Vector::ZERO,
+                    Color::WHITE,
+                    0.0,
+                    None,
+                )
+            })
+            .collect()
+    }
+
+    fn x(vertices: &[GpuVertex], t: &GpuTriangle) -> Vec<f32> {
+        t.indices
+            .iter()
+            .map(|i| vertices[*i as usize].pos.x)
+            .collect()
+    }
+
+    #[test]
+    fn batches_stay_within_limit() {
+        let vertices = vertices(10);
+        let triangles: Vec<_> = (0..8)
+            .map(|i| GpuTriangle::new(0, [i, i + 1, i + 2], 0))
+            .collect();
+        let batches = split_batches(&vertices, &triangles, 4);
+        assert_eq!(batches.len(), 4);
+        let mut drawn = Vec::new();
+        for (batch_vertices, batch_triangles) in &batches {
+            assert!(batch_vertices.len() <= 4);
+            for t in batch_triangles {
+                drawn.push(x(batch_vertices, t));
+            }
+        }
+        let expected: Vec<_> = triangles.iter().map(|t| x(&vertices, t)).collect();
+        assert_eq!(drawn, expected);
+    }
+
+    #[test]
+    fn shared_vertices_not_duplicated() {
+        let vertices = vertices(4);
+        let triangles = vec![
+            GpuTriangle::new(0, [0, 1, 2], 0),
+            GpuTriangle::new(0, [2, 1, 3], 0),
+        ];
+        let batches = split_batches(&vertices, &triangles, 4);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].0.len(), 4);
+        assert_eq!(batches[0].1[1].indices, [2, 1, 3]);
+    }
+}
@@ -0,0 +1,178 @@
+use web_sys::WebGlRenderingContext as Gl;
+
+/// Defines how the color of drawn triangles is combined with what is already on the canvas.
+///
+/// Each paint can declare its own blend mode. Triangles with different blend modes are drawn in separate draw calls.
+///
+/// Paddle's shaders output straight alpha. `Multiply` and `Screen` can only weight the source by alpha with premultiplied colors,
+/// hence the built-in shaders premultiply their output while these modes are active.
+/// Custom fragment shaders should do the same, by passing their output through `paddle_blend_output` of the `paddle/fragment` snippet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Standard transparency for straight (non-premultiplied) alpha. (default)
+    #[default]
+    Alpha,
+    /// Colors are added to the background, weighted by alpha. Useful for glow, fire and other light effects.
+    Additive,
+    /// Colors are multiplied with the background, weighted by alpha, which can only darken it. Useful for shadows.
+    Multiply,
+    /// The inverse colors are multiplied, weighted by alpha, which can only lighten the background.
+    Screen,
+    /// Standard transparency for sources where the color channels are already multiplied with alpha.
+    PremultipliedAlpha,
+}
+
+/// Float uniform set to 1.0 while a blend mode is active that needs the shader to output premultiplied alpha, 0.0 otherwise
+pub(crate) const PREMULTIPLY_ALPHA_UNIFORM: &str = "PremultiplyAlpha";
+
+impl BlendMode {
+    /// Whether the factors expect a color multiplied with its alpha from a shader that otherwise outputs straight alpha
+    pub(crate) fn needs_premultiplied_output(&self) -> bool {
+        matches!(self, BlendMode::Multiply | BlendMode::Screen)
+    }
+    /// Source and destination factors for color and alpha, as passed to `blendFuncSeparate`
+    ///
+    /// For `Multiply`: `src * a * dst + dst * (1 - a)`, for `Screen`: `src * a + dst * (1 - src * a)`, with `src` premultiplied by the shader.
+    fn factors(&self) -> (u32, u32, u32, u32) {
+        match self {
+            BlendMode::Alpha => (
+                Gl::SRC_ALPHA,
+                Gl::ONE_MINUS_SRC_ALPHA,
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Additive => (Gl::SRC_ALPHA, Gl::ONE, Gl::ONE, Gl::ONE),
+            BlendMode::Multiply => (
+                Gl::DST_COLOR,
+                Gl::ONE_MINUS_SRC_ALPHA,
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::Screen => (
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_COLOR,
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_ALPHA,
+            ),
+            BlendMode::PremultipliedAlpha => (
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_ALPHA,
+                Gl::ONE,
+                Gl::ONE_MINUS_SRC_ALPHA,
+            ),
+        }
+    }
+    pub(crate) fn apply(&self, gl: &Gl) {
+        let (src_rgb, dst_rgb, src_alpha, dst_alpha) = self.factors();
+        gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [BlendMode; 5] = [
+        BlendMode::Alpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::PremultipliedAlpha,
+    ];
+
+    /// Value of a blend factor for one color channel
+    fn factor(factor: u32, src: [f32; 2], dst: [f32; 2]) -> f32 {
+        match factor {
+            Gl::ZERO => 0.0,
+            Gl::ONE => 1.0,
+            Gl::SRC_COLOR => src[0],
+            Gl::ONE_MINUS_SRC_COLOR => 1.0 - src[0],
+            Gl::DST_COLOR => dst[0],
+            Gl::SRC_ALPHA => src[1],
+            Gl::ONE_MINUS_SRC_ALPHA => 1.0 - src[1],
+            _ => panic!("Unexpected blend factor {}", factor),
+        }
+    }
+
+    /// Blend one straight-alpha channel value with alpha `a` onto an opaque destination channel, as WebGL would
+    fn blend(mode: BlendMode, src: f32, a: f32, dst: f32) -> f32 {
+        let shader_out = if mode.needs_premultiplied_output() {
+            src * a
+        } else {
+            src
+        };
+        let (src_rgb, dst_rgb, _, _) = mode.factors();
+        let s = [shader_out, a];
+        let d = [dst, 1.0];
+        shader_out * factor(src_rgb, s, d) + dst * factor(dst_rgb, s, d)
+    }
+
+    #[test]
+    fn factor_table() {
+        let table = ALL.map(|mode| mode.factors());
+        assert_eq!(
+            table,
+            [
+                (
+                    Gl::SRC_ALPHA,
+                    Gl::ONE_MINUS_SRC_ALPHA,
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_ALPHA
+                ),
+                (Gl::SRC_ALPHA, Gl::ONE, Gl::ONE, Gl::ONE),
+                (
+                    Gl::DST_COLOR,
+                    Gl::ONE_MINUS_SRC_ALPHA,
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_ALPHA
+                ),
+                (
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_COLOR,
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_ALPHA
+                ),
+                (
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_ALPHA,
+                    Gl::ONE,
+                    Gl::ONE_MINUS_SRC_ALPHA
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn straight_alpha_results() {
+        let (src, a, dst) = (0.2, 0.5, 0.8);
+        let close = |x: f32, y: f32| (x - y).abs() < 1e-6;
+        assert!(close(
+            blend(BlendMode::Alpha, src, a, dst),
+            src * a + dst * (1.0 - a)
+        ));
+        assert!(close(
+            blend(BlendMode::Additive, src, a, dst),
+            dst + src * a
+        ));
+        // Multiply and screen are interpolated with the destination by alpha
+        let multiplied = src * dst;
+        assert!(close(
+            blend(BlendMode::Multiply, src, a, dst),
+            multiplied * a + dst * (1.0 - a)
+        ));
+        let screened = 1.0 - (1.0 - src) * (1.0 - dst);
+        assert!(close(
+            blend(BlendMode::Screen, src, a, dst),
+            screened * a + dst * (1.0 - a)
+        ));
+        // Fully transparent texels leave the background untouched
+        for mode in [
+            BlendMode::Alpha,
+            BlendMode::Additive,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+        ] {
+            assert!(close(blend(mode, 1.0, 0.0, dst), dst), "{:?}", mode);
+        }
+    }
+}
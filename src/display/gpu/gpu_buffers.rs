@@ -75,6 +75,14 @@ impl GpuBuffers {
     pub(super) fn ensure_index_buffer_size(&mut self, gl: &WebGlRenderingContext, size: usize) {
         self.index_buffer.ensure_size(gl, size);
     }
+    /// Create new buffers on the GPU, with the same layout as before. Used after the WebGL context has been restored.
+    pub(super) fn restore(&mut self, gl: &WebGlRenderingContext) -> PaddleResult<()> {
+        self.index_buffer = IndexBuffer::new(gl)?;
+        for vertex_buffer in &mut self.vertex_buffers {
+            *vertex_buffer = VertexBuffer::new(gl, vertex_buffer.v_desc.clone())?;
+        }
+        Ok(())
+    }
     pub(super) fn custom_drop(&mut self, gl: &WebGlRenderingContext) {
         gl.delete_buffer(Some(&self.index_buffer.buffer));
         for buffer in &self.vertex_buffers {
@@ -1,9 +1,13 @@
 pub struct GpuConfig {
     pub(crate) depth_test: bool,
+    pub(crate) stencil: bool,
 }
 impl Default for GpuConfig {
     fn default() -> Self {
-        Self { depth_test: false }
+        Self {
+            depth_test: false,
+            stencil: false,
+        }
     }
 }
 impl GpuConfig {
@@ -17,4 +21,15 @@ impl GpuConfig {
         self.depth_test = false;
         self
     }
+    /// Request a stencil buffer, which is required for masks.
+    #[inline(always)]
+    pub fn with_stencil(mut self) -> Self {
+        self.stencil = true;
+        self
+    }
+    #[inline(always)]
+    pub fn without_stencil(mut self) -> Self {
+        self.stencil = false;
+        self
+    }
 }
@@ -1,7 +1,7 @@
 //! Triangles ready to be drawn by GPU, after tesselation and all CPU-side transformations have finished
 
 use crate::graphics::AbstractTriangle;
-use crate::{Scalar, UniformList};
+use crate::{BlendMode, Scalar, UniformList};
 use std::cmp::Ordering;
 
 #[derive(Clone)]
@@ -13,6 +13,8 @@ pub struct GpuTriangle {
     pub indices: [u32; 3],
     /// Uniforms the triangles needs to be set.
     pub uniforms: UniformList,
+    /// How the triangle is blended with the background
+    pub blend_mode: BlendMode,
 }
 
 impl GpuTriangle {
@@ -26,12 +28,20 @@ impl GpuTriangle {
                 indices[2] + offset,
             ],
             uniforms: UniformList::default(),
+            blend_mode: BlendMode::default(),
         }
     }
-    pub fn from_abstract(t: &AbstractTriangle, offset: u32, z: f32, uniforms: UniformList) -> Self {
+    pub fn from_abstract(
+        t: &AbstractTriangle,
+        offset: u32,
+        z: f32,
+        uniforms: UniformList,
+        blend_mode: BlendMode,
+    ) -> Self {
         Self {
             z,
             uniforms,
+            blend_mode,
             indices: [
                 t.indices[0] + offset,
                 t.indices[1] + offset,
@@ -1,6 +1,10 @@
 use crate::{quicksilver_compat::graphics::Color, Vector};
+use std::rc::Rc;
 use web_sys::WebGlTexture;
 
+/// Names and sizes (in floats) of extra vertex attributes, in the order their values are stored in `GpuVertex::extra`
+pub type VertexAttributeLayout = Rc<[(&'static str, usize)]>;
+
 #[derive(Clone, Debug)]
 /// A vertex for drawing items to the GPU
 pub struct GpuVertex {
@@ -18,6 +22,8 @@ pub struct GpuVertex {
     pub z: f32,
     /// Additional (custom) attribute values to upload to the GPU. The mesh maintainer has to ensure these values are aligned with the associated `ExtraVertexAttributeDescriptor`.
     pub extra: Option<Vec<f32>>,
+    /// Which attributes the values in `extra` belong to. Without a layout, they must be in the order declared in the `VertexDescriptor`.
+    pub extra_layout: Option<VertexAttributeLayout>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +49,7 @@ pub enum VertexSource {
     Texture,
     Color,
     HasTexture,
+    /// Offset of the attribute values in `GpuVertex::extra`
     ExtraVertexAttribute(usize),
 }
 
@@ -62,8 +69,14 @@ impl GpuVertex {
             col,
             z,
             extra,
+            extra_layout: None,
         }
     }
+    /// Match the extra values to attributes of the `VertexDescriptor` by name, rather than by order
+    pub fn with_extra_layout(mut self, layout: Option<VertexAttributeLayout>) -> Self {
+        self.extra_layout = layout;
+        self
+    }
     pub fn has_texture(&self) -> bool {
         self.image.is_some()
     }
@@ -125,11 +138,14 @@ impl VertexDescriptor {
         self
     }
 
+    /// Declare an extra attribute with `size` floats per vertex, e.g. 2 for an `attribute vec2`.
+    ///
+    /// Values of extra attributes are stored in `GpuVertex::extra`, in the order of declaration.
     pub fn with(mut self, attribute: &'static str, size: usize) -> Self {
         self.attributes.push(VertexAttributeDescriptor::new(
             attribute,
             size as i32,
-            VertexSource::ExtraVertexAttribute(self.attributes.len()),
+            VertexSource::ExtraVertexAttribute(self.extra_size()),
         ));
         self.size += size as u32;
         self
@@ -140,6 +156,14 @@ impl VertexDescriptor {
     pub fn attributes(&self) -> &[VertexAttributeDescriptor] {
         &self.attributes
     }
+    /// Number of floats per vertex in all extra attributes together
+    pub fn extra_size(&self) -> usize {
+        self.attributes
+            .iter()
+            .filter(|attr| matches!(attr.source, VertexSource::ExtraVertexAttribute(_)))
+            .map(|attr| attr.size as usize)
+            .sum()
+    }
 }
 impl Default for VertexDescriptor {
     fn default() -> Self {
@@ -1,5 +1,6 @@
 use super::VertexSource;
-use crate::{GpuVertex, VertexDescriptor};
+use crate::{ErrorMessage, GpuVertex, PaddleResult, VertexAttributeLayout, VertexDescriptor};
+use std::rc::Rc;
 
 /// Manages memory regions in the WASM heap that is used
 /// to prepare data in GPU readable format for a frame.
@@ -22,10 +23,51 @@ impl WasmHeapBuffer {
     }
 
     /// Prepare vertex attributes in a heap-backed buffer to allow memory copy into GPU buffers
-    pub(super) fn prepare_vertices(&mut self, vertices: &[GpuVertex], v_desc: &VertexDescriptor) {
+    ///
+    /// Extra attribute values with a layout are matched to the descriptor by name, others are expected in declaration order.
+    /// Fails if the extra attribute values of a vertex do not match the attributes declared in the descriptor.
+    pub(super) fn prepare_vertices(
+        &mut self,
+        vertices: &[GpuVertex],
+        v_desc: &VertexDescriptor,
+    ) -> PaddleResult<()> {
         self.vertex_data.clear();
-        vertices.iter().for_each(|vertex| {
-            for attr in v_desc.attributes() {
+        let result = self.push_vertices(vertices, v_desc);
+        if result.is_err() {
+            self.vertex_data.clear();
+        }
+        result
+    }
+
+    fn push_vertices(
+        &mut self,
+        vertices: &[GpuVertex],
+        v_desc: &VertexDescriptor,
+    ) -> PaddleResult<()> {
+        let extra_size = v_desc.extra_size();
+        // Vertices of the same paint share their layout, so the offsets are only looked up again when it changes
+        let mut cached: Option<(VertexAttributeLayout, Vec<usize>)> = None;
+        for (index, vertex) in vertices.iter().enumerate() {
+            let offsets = match &vertex.extra_layout {
+                Some(layout) => {
+                    if !matches!(&cached, Some((l, _)) if Rc::ptr_eq(l, layout)) {
+                        cached = Some((layout.clone(), layout_offsets(layout, v_desc)?));
+                    }
+                    cached.as_ref().map(|(_, offsets)| offsets.as_slice())
+                }
+                None => None,
+            };
+            let extra = vertex.extra.as_deref().unwrap_or(&[]);
+            if extra.len() != extra_size {
+                self.vertex_data.clear();
+                return Err(ErrorMessage::technical(format!(
+                    "Vertex {} has {} extra attribute values, but the vertex descriptor of the render pipeline declares {}",
+                    index,
+                    extra.len(),
+                    extra_size
+                )));
+            }
+            for (i, attr) in v_desc.attributes().iter().enumerate() {
                 match attr.source {
                     VertexSource::Pos => {
                         // attribute vec3 position;
@@ -53,11 +95,168 @@ impl WasmHeapBuffer {
                         self.vertex_data
                             .push(if vertex.has_texture() { 1.0 } else { 0.0 });
                     }
-                    VertexSource::ExtraVertexAttribute(i) => {
-                        self.vertex_data.push(vertex.extra.as_ref().unwrap()[i]);
+                    VertexSource::ExtraVertexAttribute(declared_offset) => {
+                        let offset = offsets.map_or(declared_offset, |offsets| offsets[i]);
+                        self.vertex_data
+                            .extend_from_slice(&extra[offset..offset + attr.size as usize]);
                     }
                 }
             }
-        });
+        }
+        Ok(())
+    }
+}
+
+/// For each attribute of the descriptor, the offset of its values in `GpuVertex::extra` when they are stored as described by `layout`.
+///
+/// Fails if the layout names an attribute that is not declared, names one twice, or misses one, or if sizes differ.
+fn layout_offsets(
+    layout: &[(&'static str, usize)],
+    v_desc: &VertexDescriptor,
+) -> PaddleResult<Vec<usize>> {
+    let mut starts: Vec<(&str, usize)> = Vec::with_capacity(layout.len());
+    let mut offset = 0;
+    for (name, size) in layout {
+        let attr = v_desc
+            .attributes()
+            .iter()
+            .find(|attr| {
+                attr.name == *name
+                    && matches!(attr.source, VertexSource::ExtraVertexAttribute(_))
+            })
+            .ok_or_else(|| {
+                ErrorMessage::technical(format!(
+                    "Vertex attribute {} is not declared in the vertex descriptor of the render pipeline",
+                    name
+                ))
+            })?;
+        if attr.size as usize != *size {
+            return Err(ErrorMessage::technical(format!(
+                "Vertex attribute {} has {} values per vertex, but the vertex descriptor of the render pipeline declares {}",
+                name, size, attr.size
+            )));
+        }
+        if starts.iter().any(|(n, _)| n == name) {
+            return Err(ErrorMessage::technical(format!(
+                "Values for vertex attribute {} are given twice",
+                name
+            )));
+        }
+        starts.push((name, offset));
+        offset += size;
+    }
+    v_desc
+        .attributes()
+        .iter()
+        .map(|attr| match attr.source {
+            VertexSource::ExtraVertexAttribute(_) => starts
+                .iter()
+                .find(|(n, _)| *n == attr.name)
+                .map(|(_, offset)| *offset)
+                .ok_or_else(|| {
+                    ErrorMessage::technical(format!(
+                        "No values given for vertex attribute {}",
+                        attr.name
+                    ))
+                }),
+            _ => Ok(0),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quicksilver_compat::Color;
+    use crate::Vector;
+
+    fn vertex(extra: Option<Vec<f32>>) -> GpuVertex {
+        GpuVertex::new(
+            Vector::new(1, 2),
+            None,
+            Vector::ZERO,
+            Color::WHITE,
+            0.5,
+            extra,
+        )
+    }
+
+    #[test]
+    fn extra_attributes_in_declared_order() {
+        let v_desc = VertexDescriptor::new()
+            .with_pos()
+            .with("glow", 1)
+            .with("offset", 2);
+        let mut buffer = WasmHeapBuffer::new();
+        buffer
+            .prepare_vertices(&[vertex(Some(vec![0.25, 3.0, 4.0]))], &v_desc)
+            .unwrap();
+        assert_eq!(buffer.vertex_data, vec![1.0, 2.0, 0.5, 0.25, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn extra_attribute_size_mismatch() {
+        let v_desc = VertexDescriptor::new().with_pos().with("offset", 2);
+        let mut buffer = WasmHeapBuffer::new();
+        assert!(buffer
+            .prepare_vertices(&[vertex(Some(vec![1.0]))], &v_desc)
+            .is_err());
+        assert!(buffer.prepare_vertices(&[vertex(None)], &v_desc).is_err());
+        assert!(buffer.vertex_data.is_empty());
+        assert!(buffer
+            .prepare_vertices(&[vertex(None)], &VertexDescriptor::default())
+            .is_ok());
+    }
+
+    fn layout(attributes: &[(&'static str, usize)]) -> Option<VertexAttributeLayout> {
+        Some(attributes.into())
+    }
+
+    #[test]
+    fn extra_attributes_matched_by_name() {
+        let v_desc = VertexDescriptor::new()
+            .with_pos()
+            .with("glow", 1)
+            .with("offset", 2);
+        let reordered = layout(&[("offset", 2), ("glow", 1)]);
+        let mut buffer = WasmHeapBuffer::new();
+        buffer
+            .prepare_vertices(
+                &[
+                    vertex(Some(vec![3.0, 4.0, 0.25])).with_extra_layout(reordered.clone()),
+                    vertex(Some(vec![5.0, 6.0, 0.75])).with_extra_layout(reordered),
+                ],
+                &v_desc,
+            )
+            .unwrap();
+        assert_eq!(
+            buffer.vertex_data,
+            vec![1.0, 2.0, 0.5, 0.25, 3.0, 4.0, 1.0, 2.0, 0.5, 0.75, 5.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn extra_attribute_layout_mismatch() {
+        let v_desc = VertexDescriptor::new()
+            .with_pos()
+            .with("glow", 1)
+            .with("offset", 2);
+        let mut buffer = WasmHeapBuffer::new();
+        let mut prepare = |attributes: &[(&'static str, usize)], extra: Vec<f32>| {
+            buffer.prepare_vertices(
+                &[vertex(Some(extra)).with_extra_layout(layout(attributes))],
+                &v_desc,
+            )
+        };
+        // Wrong size
+        assert!(prepare(&[("glow", 2), ("offset", 1)], vec![1.0, 2.0, 3.0]).is_err());
+        // Unknown name
+        assert!(prepare(&[("glow", 1), ("shift", 2)], vec![1.0, 2.0, 3.0]).is_err());
+        // Missing name
+        assert!(prepare(&[("offset", 2)], vec![1.0, 2.0]).is_err());
+        // Duplicate name
+        assert!(prepare(&[("glow", 1), ("glow", 1), ("offset", 2)], vec![1.0; 4]).is_err());
+        assert!(prepare(&[("offset", 2), ("glow", 1)], vec![1.0, 2.0, 3.0]).is_ok());
+        assert_eq!(buffer.vertex_data, vec![1.0, 2.0, 0.5, 3.0, 1.0, 2.0]);
     }
 }
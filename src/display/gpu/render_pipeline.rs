@@ -1,12 +1,22 @@
 use web_sys::{WebGlProgram, WebGlRenderingContext, WebGlShader};
 
-use crate::display::gpu::{Gpu, UniformValue, VertexDescriptor};
+use crate::display::gpu::{
+    bind_sampler, new_fragment_shader, new_vertex_shader, Gpu, PreprocessedShader, UniformValue,
+    VertexDescriptor,
+};
 use crate::{ErrorMessage, PaddleResult};
 
 /// A handle to a registered shader program (essentially a pair of fragment + vertex shaders).
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct RenderPipelineHandle {
-    index: usize,
+    pub(super) index: usize,
+}
+
+/// Handles of the render pipelines that Paddle registers itself, as returned by their registration
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BuiltinPipelines {
+    pub gradient: RenderPipelineHandle,
+    pub sdf_text: RenderPipelineHandle,
 }
 
 pub(crate) struct RenderPipelineContainer {
@@ -16,22 +26,41 @@ pub(crate) struct RenderPipelineContainer {
 pub(crate) struct RenderPipeline {
     v_desc: VertexDescriptor,
     program: WebGlProgram,
+    vertex_shader: WebGlShader,
+    fragment_shader: WebGlShader,
+    /// Preprocessed shader sources are kept around to compile the program again when the WebGL context is restored
+    vertex_source: PreprocessedShader,
+    fragment_source: PreprocessedShader,
+    /// Last value set for each uniform that is independent of triangles
+    uniform_values: Vec<(&'static str, UniformValue)>,
+    /// Names of sampler uniforms, the index plus one is the texture unit of the sampler
+    pub(super) sampler_names: Vec<&'static str>,
 }
 
 impl Gpu {
     pub fn new_render_pipeline(
         &mut self,
         gl: &WebGlRenderingContext,
-        vertex_shader: WebGlShader,
-        fragment_shader: WebGlShader,
+        vertex_shader_text: &str,
+        fragment_shader_text: &str,
         vertex_descriptor: VertexDescriptor,
         uniform_values: &[(&'static str, UniformValue)],
     ) -> PaddleResult<RenderPipelineHandle> {
         let current_render_pipeline = self.active_render_pipeline;
-        let program = link_program(&gl, &vertex_shader, &fragment_shader)?;
-        let pipeline = RenderPipeline::new(program, vertex_descriptor.clone());
+        let vertex_source = self
+            .preprocessor
+            .process("vertex shader", vertex_shader_text)?;
+        let fragment_source = self
+            .preprocessor
+            .process("fragment shader", fragment_shader_text)?;
+        let mut pipeline = RenderPipeline::new(
+            gl,
+            vertex_source,
+            fragment_source,
+            vertex_descriptor.clone(),
+        )?;
         for (name, v) in uniform_values {
-            pipeline.prepare_uniform(gl, name, v);
+            pipeline.set_uniform(gl, name, v);
         }
         let handle = self.render_pipelines.store(pipeline);
 
@@ -39,21 +68,89 @@ impl Gpu {
 
         // program "used" after linking
         self.active_render_pipeline = handle;
-        self.use_render_pipeline(gl, current_render_pipeline);
+        self.set_program(gl, current_render_pipeline);
 
         Ok(handle)
     }
+    /// Compile a render pipeline again from new shader sources. The previous program is kept on errors.
+    pub fn recompile_render_pipeline(
+        &mut self,
+        gl: &WebGlRenderingContext,
+        rp: RenderPipelineHandle,
+        vertex_shader_text: &str,
+        fragment_shader_text: &str,
+    ) -> PaddleResult<()> {
+        let vertex_source = self
+            .preprocessor
+            .process("vertex shader", vertex_shader_text)?;
+        let fragment_source = self
+            .preprocessor
+            .process("fragment shader", fragment_shader_text)?;
+        let result = self.render_pipelines[rp].recompile(gl, vertex_source, fragment_source);
+        // Linking "uses" the new program, switch back to the active one
+        gl.use_program(Some(
+            &self.render_pipelines[self.active_render_pipeline].program,
+        ));
+        result
+    }
+    /// Switch to the render pipeline for the following draws
     pub fn use_render_pipeline(&mut self, gl: &WebGlRenderingContext, rp: RenderPipelineHandle) {
+        if self
+            .stats
+            .record_pipeline_switch(self.active_render_pipeline, rp)
+        {
+            self.set_program(gl, rp);
+        }
+    }
+    /// Switch the program without counting it, for changes that are not related to drawing
+    ///
+    /// Texture units are not part of the program, hence the samplers of the pipeline are bound again.
+    pub(super) fn set_program(&mut self, gl: &WebGlRenderingContext, rp: RenderPipelineHandle) {
         if self.active_render_pipeline != rp {
-            gl.use_program(Some(&self.render_pipelines[rp].program));
+            let pipeline = &self.render_pipelines[rp];
+            gl.use_program(Some(&pipeline.program));
+            pipeline.bind_samplers(gl);
             self.active_render_pipeline = rp;
         }
     }
 }
 
 impl RenderPipeline {
-    pub(crate) fn new(program: WebGlProgram, v_desc: VertexDescriptor) -> Self {
-        Self { v_desc, program }
+    /// Compiles and links the shaders. The new program is "used" afterwards.
+    pub(crate) fn new(
+        gl: &WebGlRenderingContext,
+        vertex_source: PreprocessedShader,
+        fragment_source: PreprocessedShader,
+        v_desc: VertexDescriptor,
+    ) -> PaddleResult<Self> {
+        // Shader objects are deleted again on errors, which matters when recompiling broken sources repeatedly
+        let vertex_shader =
+            new_vertex_shader(gl, vertex_source.code()).map_err(|e| vertex_source.map_error(e))?;
+        let fragment_shader = match new_fragment_shader(gl, fragment_source.code()) {
+            Ok(shader) => shader,
+            Err(e) => {
+                gl.delete_shader(Some(&vertex_shader));
+                return Err(fragment_source.map_error(e));
+            }
+        };
+        let program = match link_program(gl, &vertex_shader, &fragment_shader) {
+            Ok(program) => program,
+            Err(e) => {
+                gl.delete_shader(Some(&vertex_shader));
+                gl.delete_shader(Some(&fragment_shader));
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            v_desc,
+            program,
+            vertex_shader,
+            fragment_shader,
+            vertex_source,
+            fragment_source,
+            uniform_values: Vec::new(),
+            sampler_names: Vec::new(),
+        })
     }
 
     pub fn vertex_descriptor(&self) -> &VertexDescriptor {
@@ -62,9 +159,98 @@ impl RenderPipeline {
     pub fn program(&self) -> &WebGlProgram {
         &self.program
     }
+    /// Set a uniform value on the GPU and remember it. The pipeline must be in use.
+    pub(crate) fn set_uniform(
+        &mut self,
+        gl: &WebGlRenderingContext,
+        name: &'static str,
+        value: &UniformValue,
+    ) {
+        self.prepare_uniform(gl, name, value);
+        remember_uniform(&mut self.uniform_values, name, value);
+    }
+    /// Bind the images of remembered sampler uniforms to their texture units
+    fn bind_samplers(&self, gl: &WebGlRenderingContext) {
+        for (unit, value) in sampler_bindings(&self.sampler_names, &self.uniform_values) {
+            if let UniformValue::Sampler(image) = value {
+                bind_sampler(gl, unit, image);
+            }
+        }
+    }
+    /// Replace the shaders with new sources, keeping the handle and uniform values.
+    ///
+    /// On compile or link errors, the previous program stays in place and the error log is returned.
+    /// The new program is "used" afterwards, if there is one.
+    fn recompile(
+        &mut self,
+        gl: &WebGlRenderingContext,
+        vertex_source: PreprocessedShader,
+        fragment_source: PreprocessedShader,
+    ) -> PaddleResult<()> {
+        if vertex_source == self.vertex_source && fragment_source == self.fragment_source {
+            return Ok(());
+        }
+        let mut new = RenderPipeline::new(gl, vertex_source, fragment_source, self.v_desc.clone())
+            .map_err(|e| {
+                ErrorMessage::technical(format!(
+                    "Recompiling shaders failed, the previous program is kept: {}",
+                    e.text
+                ))
+            })?;
+        std::mem::swap(&mut self.program, &mut new.program);
+        std::mem::swap(&mut self.vertex_shader, &mut new.vertex_shader);
+        std::mem::swap(&mut self.fragment_shader, &mut new.fragment_shader);
+        new.custom_drop(gl);
+        self.vertex_source = new.vertex_source;
+        self.fragment_source = new.fragment_source;
+        let uniform_values = std::mem::take(&mut self.uniform_values);
+        for (name, value) in &uniform_values {
+            self.prepare_uniform(gl, name, value);
+        }
+        self.uniform_values = uniform_values;
+        Ok(())
+    }
+    /// Compile and link the program again from the stored sources, then re-apply uniform values.
+    ///
+    /// Compile errors point at the original files, like when the pipeline was created.
+    fn restore(&mut self, gl: &WebGlRenderingContext) -> PaddleResult<()> {
+        let restored = RenderPipeline::new(
+            gl,
+            self.vertex_source.clone(),
+            self.fragment_source.clone(),
+            self.v_desc.clone(),
+        )?;
+        self.program = restored.program;
+        self.vertex_shader = restored.vertex_shader;
+        self.fragment_shader = restored.fragment_shader;
+        let uniform_values = std::mem::take(&mut self.uniform_values);
+        for (name, value) in &uniform_values {
+            self.prepare_uniform(gl, name, value);
+        }
+        self.uniform_values = uniform_values;
+        Ok(())
+    }
+}
+
+impl BuiltinPipelines {
+    /// Replace placeholders of built-in pipelines, as returned by paints, with the handles registered on this GPU
+    pub(crate) fn resolve(&self, rp: RenderPipelineHandle) -> RenderPipelineHandle {
+        match rp {
+            RenderPipelineHandle::GRADIENT => self.gradient,
+            RenderPipelineHandle::SDF_TEXT => self.sdf_text,
+            rp => rp,
+        }
+    }
 }
 
 impl RenderPipelineHandle {
+    /// Placeholder for the built-in gradient pipeline, see `BuiltinPipelines::resolve`
+    pub(crate) const GRADIENT: RenderPipelineHandle = RenderPipelineHandle { index: usize::MAX };
+    /// Placeholder for the built-in distance field text pipeline, see `BuiltinPipelines::resolve`
+    pub(crate) const SDF_TEXT: RenderPipelineHandle = RenderPipelineHandle {
+        index: usize::MAX - 1,
+    };
+
     pub fn num(&self) -> usize {
         self.index
     }
@@ -89,6 +275,10 @@ impl RenderPipelineContainer {
         self.pipelines.push(p);
         handle
     }
+    /// Restore all pipelines, even if some of them fail. The errors of all failed pipelines are returned together.
+    pub(super) fn restore(&mut self, gl: &WebGlRenderingContext) -> PaddleResult<()> {
+        restore_each(&mut self.pipelines, |rp| rp.restore(gl))
+    }
 }
 impl std::ops::Index<RenderPipelineHandle> for RenderPipelineContainer {
     type Output = RenderPipeline;
@@ -96,6 +286,11 @@ impl std::ops::Index<RenderPipelineHandle> for RenderPipelineContainer {
         &self.pipelines[handle.index]
     }
 }
+impl std::ops::IndexMut<RenderPipelineHandle> for RenderPipelineContainer {
+    fn index_mut(&mut self, handle: RenderPipelineHandle) -> &mut Self::Output {
+        &mut self.pipelines[handle.index]
+    }
+}
 
 impl RenderPipelineContainer {
     pub(super) fn drop_programs(&mut self, gl: &WebGlRenderingContext) {
@@ -107,6 +302,55 @@ impl RenderPipelineContainer {
 impl RenderPipeline {
     pub(super) fn custom_drop(&mut self, gl: &WebGlRenderingContext) {
         gl.delete_program(Some(&self.program));
+        gl.delete_shader(Some(&self.vertex_shader));
+        gl.delete_shader(Some(&self.fragment_shader));
+    }
+}
+
+/// Store the last value of a uniform, which is applied again after recompiling or restoring the program
+fn remember_uniform(
+    values: &mut Vec<(&'static str, UniformValue)>,
+    name: &'static str,
+    value: &UniformValue,
+) {
+    match values.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, v)) => *v = value.clone(),
+        None => values.push((name, value.clone())),
+    }
+}
+
+/// Texture unit of each remembered value whose name was assigned a sampler unit, see `RenderPipeline::sampler_unit`
+fn sampler_bindings<'a, V>(
+    sampler_names: &[&'static str],
+    values: &'a [(&'static str, V)],
+) -> Vec<(u32, &'a V)> {
+    values
+        .iter()
+        .filter_map(|(name, value)| {
+            let index = sampler_names.iter().position(|n| n == name)?;
+            Some((index as u32 + 1, value))
+        })
+        .collect()
+}
+
+/// Call `restore` on every item and combine all errors into one
+fn restore_each<T>(
+    items: &mut [T],
+    mut restore: impl FnMut(&mut T) -> PaddleResult<()>,
+) -> PaddleResult<()> {
+    let errors: Vec<String> = items
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            restore(item)
+                .err()
+                .map(|e| format!("Restoring render pipeline {} failed: {}", index, e.text))
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorMessage::technical(errors.join("\n")))
     }
 }
 
@@ -131,10 +375,89 @@ fn link_program(
     {
         Ok(program)
     } else {
-        Err(ErrorMessage::technical(
-            context
-                .get_program_info_log(&program)
-                .unwrap_or_else(|| "Unknown error creating program object".to_owned()),
-        ))
+        let log = context
+            .get_program_info_log(&program)
+            .unwrap_or_else(|| "Unknown error creating program object".to_owned());
+        context.delete_program(Some(&program));
+        Err(ErrorMessage::technical(log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_continues_after_failures() {
+        let mut restored = vec![false; 4];
+        let result = restore_each(&mut restored, |done| {
+            *done = true;
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert!(restored.iter().all(|done| *done));
+
+        let mut attempts = vec![0, 1, 2, 3];
+        let mut visited = Vec::new();
+        let err = restore_each(&mut attempts, |n| {
+            visited.push(*n);
+            if *n % 2 == 1 {
+                Err(ErrorMessage::technical(format!("shader {}", n)))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap_err();
+        assert_eq!(visited, vec![0, 1, 2, 3]);
+        assert_eq!(
+            err.text,
+            "Restoring render pipeline 1 failed: shader 1\nRestoring render pipeline 3 failed: shader 3"
+        );
+    }
+
+    #[test]
+    fn samplers_rebound_on_pipeline_switch() {
+        // Both pipelines use texture unit 1 for different maps
+        let normal_maps = (
+            vec!["NormalMap"],
+            vec![("Projection", "p"), ("NormalMap", "bricks")],
+        );
+        let noise = (vec!["Noise"], vec![("Noise", "clouds")]);
+        let mut unit_1 = None;
+        let mut activate =
+            |(names, values): &(Vec<&'static str>, Vec<(&'static str, &'static str)>)| {
+                for (unit, texture) in sampler_bindings(names, values) {
+                    assert_eq!(unit, 1);
+                    unit_1 = Some(*texture);
+                }
+                unit_1
+            };
+        assert_eq!(activate(&normal_maps), Some("bricks"));
+        assert_eq!(activate(&noise), Some("clouds"));
+        assert_eq!(activate(&normal_maps), Some("bricks"));
+    }
+
+    #[test]
+    fn builtin_placeholders_resolved() {
+        let handle = |index| RenderPipelineHandle { index };
+        let builtin = BuiltinPipelines {
+            gradient: handle(1),
+            sdf_text: handle(2),
+        };
+        assert_eq!(builtin.resolve(RenderPipelineHandle::GRADIENT), handle(1));
+        assert_eq!(builtin.resolve(RenderPipelineHandle::SDF_TEXT), handle(2));
+        assert_eq!(builtin.resolve(handle(3)), handle(3));
+    }
+
+    #[test]
+    fn last_uniform_values_reapplied() {
+        let mut values = Vec::new();
+        remember_uniform(&mut values, "Projection", &UniformValue::F32(1.0));
+        remember_uniform(&mut values, "Time", &UniformValue::F32(2.0));
+        remember_uniform(&mut values, "Projection", &UniformValue::F32(3.0));
+        let names: Vec<_> = values.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["Projection", "Time"]);
+        assert!(values[0].1 == UniformValue::F32(3.0));
+        assert!(values[1].1 == UniformValue::F32(2.0));
     }
 }
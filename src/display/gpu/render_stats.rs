@@ -0,0 +1,76 @@
+use super::RenderPipelineHandle;
+
+/// Counters of the work sent to the GPU during one frame.
+///
+/// Retrieve the numbers of the last complete frame with `Display::render_stats`.
+/// With `DisplayConfig::publish_render_stats`, they are also published as message after each `EndOfFrame`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Calls to `drawElements`, one per batch of triangles sharing texture, uniforms and blend mode
+    pub draw_calls: u32,
+    /// Vertices uploaded to the GPU
+    pub vertices: u32,
+    pub triangles: u32,
+    pub texture_binds: u32,
+    /// Single uniform values uploaded, for paints with uniforms and for pipeline uniforms
+    pub uniform_uploads: u32,
+    /// Changes of the active shader program for drawing. Temporary switches to set uniforms or create pipelines are not counted.
+    pub pipeline_switches: u32,
+    /// Flushes of the buffered draws with at least one triangle, caused by the end of a frame, a pipeline switch or a mask
+    pub flushes: u32,
+}
+
+impl RenderStats {
+    /// Count a flush, unless there is nothing to draw. Returns whether it was counted.
+    pub(crate) fn record_flush(&mut self, triangles: usize) -> bool {
+        if triangles == 0 {
+            return false;
+        }
+        self.flushes += 1;
+        true
+    }
+    /// Count a switch of the render pipeline used for drawing, if it actually changes
+    pub(crate) fn record_pipeline_switch(
+        &mut self,
+        from: RenderPipelineHandle,
+        to: RenderPipelineHandle,
+    ) -> bool {
+        if from == to {
+            return false;
+        }
+        self.pipeline_switches += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_flushes_not_counted() {
+        let mut stats = RenderStats::default();
+        assert!(!stats.record_flush(0));
+        assert!(stats.record_flush(2));
+        assert!(!stats.record_flush(0));
+        assert_eq!(stats.flushes, 1);
+    }
+
+    #[test]
+    fn pipeline_switches_counted_once() {
+        let mut stats = RenderStats::default();
+        let default = RenderPipelineHandle::default();
+        let other = RenderPipelineHandle { index: 1 };
+        assert!(!stats.record_pipeline_switch(default, default));
+        assert!(stats.record_pipeline_switch(default, other));
+        assert!(stats.record_pipeline_switch(other, default));
+        assert_eq!(stats.pipeline_switches, 2);
+        assert_eq!(
+            stats,
+            RenderStats {
+                pipeline_switches: 2,
+                ..RenderStats::default()
+            }
+        );
+    }
+}
@@ -5,9 +5,11 @@
 
 mod custom_shader;
 mod default_shaders;
+mod preprocessor;
 mod uniform;
 pub use custom_shader::*;
 pub use default_shaders::*;
+pub use preprocessor::{PreprocessedShader, ShaderPreprocessor};
 pub use uniform::*;
 
 use crate::{ErrorMessage, PaddleResult};
@@ -39,9 +41,10 @@ fn compile_shader(
     {
         Ok(shader)
     } else {
-        Err(ErrorMessage::technical(
-            gl.get_shader_info_log(&shader)
-                .unwrap_or_else(|| "Unknown error creating shader".to_owned()),
-        ))
+        let log = gl
+            .get_shader_info_log(&shader)
+            .unwrap_or_else(|| "Unknown error creating shader".to_owned());
+        gl.delete_shader(Some(&shader));
+        Err(ErrorMessage::technical(log))
     }
 }
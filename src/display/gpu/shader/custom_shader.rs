@@ -1,10 +1,80 @@
-use crate::{quicksilver_compat::Color, AbstractVertex, Image, Paint, RenderPipelineHandle};
+use std::rc::Rc;
 
+use crate::{
+    quicksilver_compat::Color, AbstractVertex, Image, Paint, RenderPipelineHandle,
+    UniformDescriptor, UniformList, UniformValue, VertexAttributeLayout,
+};
+
+/// Paint that draws with a custom render pipeline.
+///
+/// # Example
+/// ```no_run
+/// # use paddle::{CustomShader, Image, RenderPipelineHandle, quicksilver_compat::Color};
+/// # fn example(pipeline: RenderPipelineHandle, diffuse: Image, normal_map: Image) {
+/// // The shader declares `uniform sampler2D NormalMap;` and `uniform vec4 LightColor;`
+/// let paint = CustomShader::new(pipeline)
+///     .with_image(diffuse)
+///     .with_uniform("NormalMap", normal_map)
+///     .with_uniform("LightColor", Color::WHITE);
+/// # }
+/// ```
 #[derive(Clone)]
 pub struct CustomShader {
     pipe: RenderPipelineHandle,
     color: Option<Color>,
     image: Option<Image>,
+    uniform_values: Vec<UniformDescriptor>,
+    uniforms: UniformList,
+    vertex_attributes: Vec<(&'static str, VertexAttributeValues)>,
+    /// Names and sizes of `vertex_attributes`, shared by all vertices drawn with this paint
+    vertex_attribute_layout: Option<VertexAttributeLayout>,
+}
+
+type VertexAttributeFn = dyn Fn(usize, &AbstractVertex) -> Vec<f32>;
+
+/// Values of an extra vertex attribute, declared with `VertexDescriptor::with`.
+#[derive(Clone)]
+pub enum VertexAttributeValues {
+    /// Computed for each vertex, from its index in the mesh and the vertex itself, `size` values per vertex
+    PerVertex {
+        size: usize,
+        f: Rc<VertexAttributeFn>,
+    },
+    /// Precomputed values of all vertices in the order of the mesh, `size` values per vertex
+    Buffer { size: usize, values: Rc<[f32]> },
+}
+
+impl VertexAttributeValues {
+    pub fn per_vertex(
+        size: usize,
+        f: impl Fn(usize, &AbstractVertex) -> Vec<f32> + 'static,
+    ) -> Self {
+        Self::PerVertex {
+            size,
+            f: Rc::new(f),
+        }
+    }
+    pub fn buffer(size: usize, values: impl Into<Rc<[f32]>>) -> Self {
+        Self::Buffer {
+            size,
+            values: values.into(),
+        }
+    }
+    /// Values of a single vertex. Missing values of a buffer result in an empty list, which fails validation when drawn.
+    fn values(&self, index: usize, vertex: &AbstractVertex) -> Vec<f32> {
+        match self {
+            Self::PerVertex { f, .. } => f(index, vertex),
+            Self::Buffer { size, values } => values
+                .get(index * size..(index + 1) * size)
+                .map(<[f32]>::to_vec)
+                .unwrap_or_default(),
+        }
+    }
+    fn size(&self) -> usize {
+        match self {
+            Self::PerVertex { size, .. } | Self::Buffer { size, .. } => *size,
+        }
+    }
 }
 
 impl CustomShader {
@@ -13,6 +83,10 @@ impl CustomShader {
             pipe,
             color: None,
             image: None,
+            uniform_values: Vec::new(),
+            uniforms: UniformList::default(),
+            vertex_attributes: Vec::new(),
+            vertex_attribute_layout: None,
         }
     }
     pub fn with_color(mut self, color: Color) -> Self {
@@ -23,16 +97,53 @@ impl CustomShader {
         self.image = Some(image);
         self
     }
+    /// Set a uniform for everything drawn with this paint, for example an additional texture as `UniformValue::Sampler`.
+    ///
+    /// Shapes with different uniform values are drawn in separate draw calls.
+    pub fn with_uniform(mut self, name: &'static str, value: impl Into<UniformValue>) -> Self {
+        self.uniform_values
+            .push(UniformDescriptor::new(name, value.into()));
+        self.uniforms = UniformList::new(&self.uniform_values);
+        self
+    }
+    /// Provide the values of an extra vertex attribute.
+    ///
+    /// Attributes are matched by name to those declared in the `VertexDescriptor` of the render pipeline, so they can be added in any order.
+    /// Drawing fails with an error if a name is not declared, a declared attribute has no values, or the sizes do not match.
+    pub fn with_vertex_attribute(
+        mut self,
+        name: &'static str,
+        values: VertexAttributeValues,
+    ) -> Self {
+        self.vertex_attributes.push((name, values));
+        self.vertex_attribute_layout = Some(
+            self.vertex_attributes
+                .iter()
+                .map(|(name, values)| (*name, values.size()))
+                .collect(),
+        );
+        self
+    }
 }
 
 impl Paint for CustomShader {
     fn paint_extra_vertex_attributes(
         &self,
-        _index: usize,
-        _vertex: &AbstractVertex,
+        index: usize,
+        vertex: &AbstractVertex,
     ) -> Option<Vec<f32>> {
-        None
-        // TODO?
+        if self.vertex_attributes.is_empty() {
+            return None;
+        }
+        Some(
+            self.vertex_attributes
+                .iter()
+                .flat_map(|(_, values)| values.values(index, vertex))
+                .collect(),
+        )
+    }
+    fn paint_extra_vertex_attribute_layout(&self) -> Option<VertexAttributeLayout> {
+        self.vertex_attribute_layout.clone()
     }
     fn paint_render_pipeline(&self) -> RenderPipelineHandle {
         self.pipe
@@ -45,4 +156,8 @@ impl Paint for CustomShader {
     fn paint_color(&self) -> Option<Color> {
         self.color
     }
+
+    fn paint_uniforms(&self) -> UniformList {
+        self.uniforms.clone()
+    }
 }
@@ -18,10 +18,126 @@ pub const DEFAULT_FRAGMENT_SHADER: &str = r#"varying highp vec4 Color;
 varying highp vec2 Tex_coord;
 varying lowp float Uses_texture;
 uniform sampler2D sampler;
+uniform lowp float PremultiplyAlpha;
 void main() {
     highp vec4 tex_color = (int(Uses_texture) != 0) ? texture2D(sampler, Tex_coord) : vec4(1, 1, 1, 1);
     if (tex_color.a < 0.1) 
         discard;
-    else
+    else {
         gl_FragColor = Color * tex_color;
+        gl_FragColor.rgb *= mix(1.0, gl_FragColor.a, PremultiplyAlpha);
+    }
+}"#;
+
+/// Fragment shader of the built-in gradient pipeline, used by `Gradient` paints.
+///
+/// The gradient position `t` is computed from the texture coordinates, which span the bounding box of the drawn shape.
+/// Colors between stops are interpolated linearly, outside the first and last stop the nearest color is used.
+pub const GRADIENT_FRAGMENT_SHADER: &str = r#"precision mediump float;
+varying highp vec4 Color;
+varying highp vec2 Tex_coord;
+uniform float GradientKind;
+uniform vec2 GradientStart;
+uniform vec2 GradientEnd;
+uniform float StopCount;
+uniform vec4 StopColors[8];
+uniform float StopOffsets[8];
+uniform float PremultiplyAlpha;
+void main() {
+    float t;
+    if (GradientKind < 0.5) {
+        vec2 dir = GradientEnd - GradientStart;
+        t = dot(Tex_coord - GradientStart, dir) / max(dot(dir, dir), 0.000001);
+    } else {
+        t = length((Tex_coord - GradientStart) / max(GradientEnd, vec2(0.000001)));
+    }
+    vec4 color = StopColors[0];
+    for (int i = 1; i < 8; i++) {
+        if (float(i) >= StopCount)
+            break;
+        float range = StopOffsets[i] - StopOffsets[i - 1];
+        float f = range > 0.0 ? clamp((t - StopOffsets[i - 1]) / range, 0.0, 1.0) : step(StopOffsets[i], t);
+        color = mix(color, StopColors[i], f);
+    }
+    gl_FragColor = Color * color;
+    gl_FragColor.rgb *= mix(1.0, gl_FragColor.a, PremultiplyAlpha);
+}"#;
+
+/// Fragment shader of the built-in distance field text pipeline, used for fonts with SDF or MSDF atlases.
+///
+/// Distances are sampled from the alpha channel (SDF) or as median of the color channels (MSDF), where 0.5 is the glyph edge.
+/// `ScreenPxRange` converts distances to screen pixels, it is computed on the CPU for each text draw.
+/// Outline and shadow widths are in units of the distance range of the atlas.
+pub const SDF_TEXT_FRAGMENT_SHADER: &str = r#"precision mediump float;
+varying highp vec4 Color;
+varying highp vec2 Tex_coord;
+uniform sampler2D sampler;
+uniform float FieldKind;
+uniform float ScreenPxRange;
+uniform float Softness;
+uniform float OutlineWidth;
+uniform vec4 OutlineColor;
+uniform vec2 ShadowOffset;
+uniform vec4 ShadowColor;
+uniform float ShadowSoftness;
+uniform float PremultiplyAlpha;
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+float signed_distance(vec2 uv) {
+    vec4 s = texture2D(sampler, uv);
+    return (FieldKind > 0.5 ? median(s.r, s.g, s.b) : s.a) - 0.5;
+}
+float coverage(float d, float softness) {
+    return clamp(d * ScreenPxRange / max(softness, 0.001) + 0.5, 0.0, 1.0);
+}
+vec4 over(vec4 top, vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    vec3 rgb = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / max(a, 0.0001);
+    return vec4(rgb, a);
+}
+void main() {
+    float d = signed_distance(Tex_coord);
+    vec4 fill = vec4(Color.rgb, Color.a * coverage(d, Softness));
+    vec4 outline = vec4(OutlineColor.rgb, OutlineColor.a * coverage(d + OutlineWidth, Softness));
+    float shadow_d = signed_distance(Tex_coord - ShadowOffset) + OutlineWidth;
+    vec4 shadow = vec4(ShadowColor.rgb, ShadowColor.a * coverage(shadow_d, ShadowSoftness));
+    vec4 result = over(over(fill, OutlineWidth > 0.0 ? outline : vec4(0.0)), shadow);
+    if (result.a < 0.01)
+        discard;
+    else
+        gl_FragColor = vec4(result.rgb * mix(1.0, result.a, PremultiplyAlpha), result.a);
+}"#;
+
+/// Shader snippet `paddle/vertex`, to write vertex shaders for the default vertex layout.
+pub const DEFAULT_VERTEX_CHUNK: &str = r#"attribute vec3 position;
+attribute vec2 tex_coord;
+attribute vec4 color;
+attribute lowp float uses_texture;
+varying vec2 Tex_coord;
+varying vec4 Color;
+varying lowp float Uses_texture;
+uniform mat3 Projection;
+void paddle_vertex() {
+    vec3 projected = vec3(position.xy, 1.0) * Projection;
+    gl_Position = vec4(projected.x / projected.z, projected.y / projected.z, position.z, 1.0);
+    Tex_coord = tex_coord;
+    Color = color;
+    Uses_texture = uses_texture;
+}"#;
+
+/// Shader snippet `paddle/fragment`, to write fragment shaders that receive the outputs of `paddle/vertex`.
+///
+/// Pass the final color through `paddle_blend_output` to support `BlendMode::Multiply` and `BlendMode::Screen`.
+pub const DEFAULT_FRAGMENT_CHUNK: &str = r#"varying highp vec4 Color;
+varying highp vec2 Tex_coord;
+varying lowp float Uses_texture;
+uniform sampler2D sampler;
+uniform lowp float PremultiplyAlpha;
+highp vec4 paddle_paint_color() {
+    highp vec4 tex_color = (int(Uses_texture) != 0) ? texture2D(sampler, Tex_coord) : vec4(1, 1, 1, 1);
+    return Color * tex_color;
+}
+highp vec4 paddle_blend_output(highp vec4 color) {
+    return vec4(color.rgb * mix(1.0, color.a, PremultiplyAlpha), color.a);
 }"#;
@@ -0,0 +1,251 @@
+//! GLSL ES 1.0 has no way to share code between shaders, so Paddle resolves includes itself before compiling.
+//!
+//! Includes are written as `#include "name"` (or `#include <name>`) and refer to snippets registered on the `ShaderPreprocessor`.
+//! Each snippet is included at most once per shader, which also makes cyclic includes harmless.
+//! Defines registered on the preprocessor are injected at the top of every shader compiled afterwards.
+//!
+//! The expanded code remembers where each of its lines came from, which is used to translate line numbers in compile errors.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use super::{DEFAULT_FRAGMENT_CHUNK, DEFAULT_VERTEX_CHUNK};
+use crate::{ErrorMessage, PaddleResult};
+
+/// Resolves `#include` directives and injects `#define`s into shader sources before they are compiled.
+///
+/// Access it with `Display::shader_preprocessor_mut`.
+/// Paddle registers these snippets by default:
+///  * `paddle/vertex`: Attributes, varyings and projection uniform of the default vertex layout, plus `void paddle_vertex()` which does what the default vertex shader does.
+///  * `paddle/fragment`: Varyings and sampler matching `paddle/vertex`, plus `vec4 paddle_paint_color()` which returns the color of the paint, including its texture, and `vec4 paddle_blend_output(vec4)` to apply to the final color for blend modes that need premultiplied alpha.
+pub struct ShaderPreprocessor {
+    snippets: Vec<(String, String)>,
+    defines: Vec<(String, String)>,
+}
+
+/// Shader code after preprocessing, with the origin of each line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreprocessedShader {
+    code: String,
+    /// File name and line number (starting at 1) for each line of `code`
+    origins: Vec<(Rc<str>, usize)>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        let mut preprocessor = Self {
+            snippets: Vec::new(),
+            defines: Vec::new(),
+        };
+        preprocessor.add_snippet("paddle/vertex", DEFAULT_VERTEX_CHUNK);
+        preprocessor.add_snippet("paddle/fragment", DEFAULT_FRAGMENT_CHUNK);
+        preprocessor
+    }
+    /// Register code that shaders can include with `#include "name"`. Replaces a previous snippet of the same name.
+    pub fn add_snippet(&mut self, name: impl Into<String>, source: impl Into<String>) {
+        set(&mut self.snippets, name.into(), source.into());
+    }
+    pub fn snippet(&self, name: &str) -> Option<&str> {
+        get(&self.snippets, name)
+    }
+    /// Inject `#define name value` into all shaders compiled afterwards. Replaces a previous value.
+    ///
+    /// Already compiled render pipelines are not affected, unless they are recompiled.
+    pub fn define(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        set(&mut self.defines, name.into(), value.into());
+    }
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.retain(|(n, _)| n != name);
+    }
+    /// Expand includes and add defines. `file_name` is used for the lines of `source` in error messages.
+    pub fn process(&self, file_name: &str, source: &str) -> PaddleResult<PreprocessedShader> {
+        let mut out = PreprocessedShader::default();
+        let file: Rc<str> = file_name.into();
+        // `#version` must remain the very first line of a shader
+        let mut skip = 0;
+        if let Some(first) = source.lines().next() {
+            if first.trim_start().starts_with("#version") {
+                out.push(first, &file, 1);
+                skip = 1;
+            }
+        }
+        if !self.defines.is_empty() {
+            let defines_file: Rc<str> = "defines".into();
+            for (i, (name, value)) in self.defines.iter().enumerate() {
+                out.push(&format!("#define {} {}", name, value), &defines_file, i + 1);
+            }
+        }
+        let mut included = HashSet::new();
+        self.expand(&file, source, skip, &mut out, &mut included)?;
+        Ok(out)
+    }
+    fn expand<'a>(
+        &'a self,
+        file: &Rc<str>,
+        source: &'a str,
+        skip: usize,
+        out: &mut PreprocessedShader,
+        included: &mut HashSet<&'a str>,
+    ) -> PaddleResult<()> {
+        for (i, line) in source.lines().enumerate().skip(skip) {
+            let number = i + 1;
+            if let Some(rest) = line.trim_start().strip_prefix("#include") {
+                let name = include_name(rest).ok_or_else(|| {
+                    ErrorMessage::technical(format!("{}:{}: malformed #include", file, number))
+                })?;
+                let (name, snippet) =
+                    self.snippets
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .ok_or_else(|| {
+                            ErrorMessage::technical(format!(
+                                "{}:{}: unknown shader snippet \"{}\"",
+                                file, number, name
+                            ))
+                        })?;
+                if included.insert(name.as_str()) {
+                    self.expand(&name.as_str().into(), snippet, 0, out, included)?;
+                }
+            } else {
+                out.push(line, file, number);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ShaderPreprocessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PreprocessedShader {
+    /// The code as handed to WebGL
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+    /// File name and line number in that file for a line (starting at 1) of the preprocessed code
+    pub fn origin(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = self.origins.get(line.checked_sub(1)?)?;
+        Some((file, *line))
+    }
+    /// Rewrite `ERROR: 0:12: ...` lines of a WebGL info log to point at the original files.
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.map_log_line(line).unwrap_or_else(|| line.to_owned()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn map_log_line(&self, line: &str) -> Option<String> {
+        let (prefix, rest) = ["ERROR: ", "WARNING: "]
+            .iter()
+            .find_map(|prefix| Some((*prefix, line.strip_prefix(prefix)?)))?;
+        let mut parts = rest.splitn(3, ':');
+        let _source_string = parts.next()?;
+        let line_number = parts.next()?.trim().parse().ok()?;
+        let message = parts.next()?;
+        let (file, original_line) = self.origin(line_number)?;
+        Some(format!("{}{}:{}:{}", prefix, file, original_line, message))
+    }
+    pub(crate) fn map_error(&self, e: ErrorMessage) -> ErrorMessage {
+        ErrorMessage::technical(self.map_log(&e.text))
+    }
+    fn push(&mut self, line: &str, file: &Rc<str>, number: usize) {
+        if !self.origins.is_empty() {
+            self.code.push('\n');
+        }
+        self.code.push_str(line);
+        self.origins.push((file.clone(), number));
+    }
+}
+
+fn include_name(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+    rest.strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')))
+        .filter(|name| !name.is_empty())
+}
+
+fn get<'a>(list: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    list.iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+fn set(list: &mut Vec<(String, String)>, name: String, value: String) {
+    match list.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, v)) => *v = value,
+        None => list.push((name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_resolved_once() {
+        let mut pp = ShaderPreprocessor::new();
+        pp.add_snippet("a", "float a;");
+        pp.add_snippet("b", "#include \"a\"\nfloat b;");
+        let out = pp
+            .process("main.glsl", "#include <b>\n#include \"a\"\nvoid main() {}")
+            .unwrap();
+        assert_eq!(out.code(), "float a;\nfloat b;\nvoid main() {}");
+        assert_eq!(out.origin(1), Some(("a", 1)));
+        assert_eq!(out.origin(2), Some(("b", 2)));
+        assert_eq!(out.origin(3), Some(("main.glsl", 3)));
+        assert_eq!(out.origin(4), None);
+    }
+
+    #[test]
+    fn cyclic_and_unknown_includes() {
+        let mut pp = ShaderPreprocessor::new();
+        pp.add_snippet("a", "#include \"b\"\nfloat a;");
+        pp.add_snippet("b", "#include \"a\"\nfloat b;");
+        let out = pp.process("main", "#include \"a\"").unwrap();
+        assert_eq!(out.code(), "float b;\nfloat a;");
+        let err = pp.process("main", "x;\n#include \"missing\"").unwrap_err();
+        assert!(err.text.starts_with("main:2:"));
+        assert!(pp.process("main", "#include missing").is_err());
+    }
+
+    #[test]
+    fn defines_after_version() {
+        let mut pp = ShaderPreprocessor::new();
+        pp.define("COUNT", "4");
+        pp.define("COUNT", "8");
+        pp.define("FAST", "");
+        let out = pp.process("main", "#version 100\nvoid main() {}").unwrap();
+        assert_eq!(
+            out.code(),
+            "#version 100\n#define COUNT 8\n#define FAST \nvoid main() {}"
+        );
+        assert_eq!(out.origin(3), Some(("defines", 2)));
+        assert_eq!(out.origin(4), Some(("main", 2)));
+        pp.undefine("COUNT");
+        let out = pp.process("main", "void main() {}").unwrap();
+        assert_eq!(out.code(), "#define FAST \nvoid main() {}");
+    }
+
+    #[test]
+    fn error_log_mapped() {
+        let mut pp = ShaderPreprocessor::new();
+        pp.add_snippet("lib", "float f;\nfloat g;");
+        let out = pp.process("shader.frag", "#include \"lib\"\nx").unwrap();
+        let log = "ERROR: 0:2: 'g' : redefinition\nERROR: 0:3: 'x' : syntax error\nERROR: 2 compilation errors.";
+        assert_eq!(
+            out.map_log(log),
+            "ERROR: lib:2: 'g' : redefinition\nERROR: shader.frag:2: 'x' : syntax error\nERROR: 2 compilation errors."
+        );
+    }
+
+    #[test]
+    fn default_chunks_registered() {
+        let pp = ShaderPreprocessor::new();
+        assert!(pp.snippet("paddle/vertex").is_some());
+        assert!(pp.snippet("paddle/fragment").is_some());
+    }
+}
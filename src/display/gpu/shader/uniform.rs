@@ -2,7 +2,13 @@ use std::rc::Rc;
 use web_sys::WebGlRenderingContext;
 
 use crate::display::gpu::RenderPipeline;
-use crate::Transform;
+use crate::quicksilver_compat::Color;
+use crate::{Image, Transform, Vector};
+
+/// Texture units available to sampler uniforms, unit 0 is taken by the image of the paint.
+///
+/// WebGL guarantees at least 8 texture units in fragment shaders.
+const MAX_SAMPLER_UNITS: usize = 7;
 
 /// A list of valued uniforms to associate with primitives when drawn.
 #[derive(Clone, PartialEq, Default)]
@@ -24,11 +30,33 @@ pub struct UniformDescriptor {
     value: UniformValue,
 }
 
+/// Value of a uniform, each variant corresponds to a GLSL type.
+///
+/// Arrays are assigned to uniform arrays of the same type, e.g. `uniform float Weights[4]`.
 #[derive(Clone, PartialEq)]
 pub enum UniformValue {
+    /// `mat3`, in column-major order
     Matrix3fv([f32; 9]),
+    /// `mat4`, in column-major order
+    Matrix4fv([f32; 16]),
     Vec2F32(f32, f32),
+    Vec3F32(f32, f32, f32),
+    Vec4F32(f32, f32, f32, f32),
     F32(f32),
+    I32(i32),
+    Vec2I32(i32, i32),
+    Vec3I32(i32, i32, i32),
+    Vec4I32(i32, i32, i32, i32),
+    Bool(bool),
+    F32Array(Vec<f32>),
+    Vec2F32Array(Vec<[f32; 2]>),
+    Vec3F32Array(Vec<[f32; 3]>),
+    Vec4F32Array(Vec<[f32; 4]>),
+    /// `sampler2D`, bound to a texture unit of its own.
+    ///
+    /// The full texture of the image is sampled, regardless of the region of the image.
+    /// Each render pipeline supports up to 7 different sampler uniforms, in addition to the image of the paint.
+    Sampler(Image),
 }
 
 impl UniformDescriptor {
@@ -38,24 +66,79 @@ impl UniformDescriptor {
 }
 
 impl RenderPipeline {
-    pub fn prepare_uniform(&self, gl: &WebGlRenderingContext, name: &str, value: &UniformValue) {
+    pub fn prepare_uniform(
+        &mut self,
+        gl: &WebGlRenderingContext,
+        name: &'static str,
+        value: &UniformValue,
+    ) {
         let uloc = gl.get_uniform_location(self.program(), name);
+        let uloc = uloc.as_ref();
         match value {
             UniformValue::Matrix3fv(data) => {
-                gl.uniform_matrix3fv_with_f32_array(uloc.as_ref(), false, data);
+                gl.uniform_matrix3fv_with_f32_array(uloc, false, data);
+            }
+            UniformValue::Matrix4fv(data) => {
+                gl.uniform_matrix4fv_with_f32_array(uloc, false, data);
+            }
+            UniformValue::F32(data) => gl.uniform1f(uloc, *data),
+            UniformValue::Vec2F32(x, y) => gl.uniform2f(uloc, *x, *y),
+            UniformValue::Vec3F32(x, y, z) => gl.uniform3f(uloc, *x, *y, *z),
+            UniformValue::Vec4F32(x, y, z, w) => gl.uniform4f(uloc, *x, *y, *z, *w),
+            UniformValue::I32(data) => gl.uniform1i(uloc, *data),
+            UniformValue::Vec2I32(x, y) => gl.uniform2i(uloc, *x, *y),
+            UniformValue::Vec3I32(x, y, z) => gl.uniform3i(uloc, *x, *y, *z),
+            UniformValue::Vec4I32(x, y, z, w) => gl.uniform4i(uloc, *x, *y, *z, *w),
+            UniformValue::Bool(data) => gl.uniform1i(uloc, *data as i32),
+            UniformValue::F32Array(data) => gl.uniform1fv_with_f32_array(uloc, data),
+            UniformValue::Vec2F32Array(data) => {
+                gl.uniform2fv_with_f32_array(uloc, data.concat().as_slice())
+            }
+            UniformValue::Vec3F32Array(data) => {
+                gl.uniform3fv_with_f32_array(uloc, data.concat().as_slice())
+            }
+            UniformValue::Vec4F32Array(data) => {
+                gl.uniform4fv_with_f32_array(uloc, data.concat().as_slice())
+            }
+            UniformValue::Sampler(image) => {
+                if let Some(unit) = self.sampler_unit(name) {
+                    bind_sampler(gl, unit, image);
+                    gl.uniform1i(uloc, unit as i32);
+                }
             }
-            UniformValue::F32(data) => gl.uniform1f(uloc.as_ref(), *data),
-            UniformValue::Vec2F32(x, y) => gl.uniform2f(uloc.as_ref(), *x, *y),
         }
     }
 
-    pub fn prepare_uniforms(&self, gl: &WebGlRenderingContext, uniforms: &crate::UniformList) {
+    pub fn prepare_uniforms(&mut self, gl: &WebGlRenderingContext, uniforms: &crate::UniformList) {
         if let Some(inner) = uniforms.0.as_ref() {
             for UniformDescriptor { name, value } in inner.iter() {
-                self.prepare_uniform(gl, name, &value);
+                self.prepare_uniform(gl, name, value);
             }
         }
     }
+
+    /// Texture unit of a sampler uniform, assigned on first use. None if all units are taken.
+    fn sampler_unit(&mut self, name: &'static str) -> Option<u32> {
+        let index = match self.sampler_names.iter().position(|n| *n == name) {
+            Some(index) => index,
+            None if self.sampler_names.len() < MAX_SAMPLER_UNITS => {
+                self.sampler_names.push(name);
+                self.sampler_names.len() - 1
+            }
+            None => return None,
+        };
+        Some(index as u32 + 1)
+    }
+}
+
+/// Bind the texture of the image to a texture unit. Units are shared by all programs, unlike uniform values.
+pub(crate) fn bind_sampler(gl: &WebGlRenderingContext, unit: u32, image: &Image) {
+    gl.active_texture(WebGlRenderingContext::TEXTURE0 + unit);
+    gl.bind_texture(
+        WebGlRenderingContext::TEXTURE_2D,
+        Some(&image.texture.webgl_texture()),
+    );
+    gl.active_texture(WebGlRenderingContext::TEXTURE0);
 }
 
 impl From<Transform> for UniformValue {
@@ -69,3 +152,34 @@ impl From<f32> for UniformValue {
         UniformValue::F32(value)
     }
 }
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        UniformValue::I32(value)
+    }
+}
+
+impl From<bool> for UniformValue {
+    fn from(value: bool) -> Self {
+        UniformValue::Bool(value)
+    }
+}
+
+impl From<Vector> for UniformValue {
+    fn from(value: Vector) -> Self {
+        UniformValue::Vec2F32(value.x, value.y)
+    }
+}
+
+/// Colors become a `vec4` with components in the range 0.0 to 1.0
+impl From<Color> for UniformValue {
+    fn from(value: Color) -> Self {
+        UniformValue::Vec4F32(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Image> for UniformValue {
+    fn from(value: Image) -> Self {
+        UniformValue::Sampler(value)
+    }
+}
@@ -0,0 +1,48 @@
+//! Masking of draw calls with arbitrary shapes, using the stencil buffer.
+//!
+//! Each active mask occupies one bit of the stencil buffer. Drawing a mask writes its bit wherever the shape covers the canvas.
+//! Subsequent draws are only visible where all active masks agree with their `MaskMode`.
+
+/// Defines which part of the canvas stays visible after a mask has been pushed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MaskMode {
+    /// Only draw inside the mask shape.
+    Inside,
+    /// Only draw outside the mask shape.
+    Outside,
+}
+
+/// Number of masks that can be active at the same time, one for each bit of an 8-bit stencil buffer
+pub(crate) const MAX_MASKS: usize = 8;
+
+/// The bit in the stencil buffer used by the mask at the given stack level
+pub(crate) fn mask_bit(level: usize) -> u32 {
+    1 << level
+}
+
+/// Reference value and bit mask for an `EQUAL` stencil test that enforces all active masks
+pub(crate) fn stencil_test(masks: &[MaskMode]) -> (i32, u32) {
+    let all_bits = (1u32 << masks.len()) - 1;
+    let reference = masks
+        .iter()
+        .enumerate()
+        .filter(|(_, mode)| **mode == MaskMode::Inside)
+        .fold(0, |acc, (level, _)| acc | mask_bit(level));
+    (reference as i32, all_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stencil_test_values() {
+        assert_eq!(stencil_test(&[MaskMode::Inside]), (0b1, 0b1));
+        assert_eq!(stencil_test(&[MaskMode::Outside]), (0b0, 0b1));
+        assert_eq!(
+            stencil_test(&[MaskMode::Outside, MaskMode::Inside, MaskMode::Inside]),
+            (0b110, 0b111)
+        );
+        assert_eq!(stencil_test(&[MaskMode::Inside; MAX_MASKS]), (0xFF, 0xFF));
+    }
+}
@@ -0,0 +1,78 @@
+//! Storage of particle emitters on the display, stepped on every `UpdateWorld`.
+
+use std::collections::HashMap;
+
+use crate::quicksilver_compat::Color;
+use crate::{BlendMode, Domain, Image, Paint, ParticleEmitter, UpdateWorld};
+
+/// Refers to a `ParticleEmitter` that has been added to the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EmitterHandle(u64);
+
+pub(crate) struct ParticleSystems {
+    emitters: HashMap<EmitterHandle, ParticleEmitter>,
+    next_id: u64,
+    /// Simulated time per update, as configured in `DisplayConfig::update_delay_ms`
+    step_ms: f32,
+}
+
+impl ParticleSystems {
+    pub(crate) fn new(step_ms: f32) -> Self {
+        Self {
+            emitters: HashMap::new(),
+            next_id: 0,
+            step_ms,
+        }
+    }
+    pub(crate) fn add(&mut self, emitter: ParticleEmitter) -> EmitterHandle {
+        let handle = EmitterHandle(self.next_id);
+        self.next_id += 1;
+        self.emitters.insert(handle, emitter);
+        handle
+    }
+    pub(crate) fn get(&self, handle: EmitterHandle) -> Option<&ParticleEmitter> {
+        self.emitters.get(&handle)
+    }
+    pub(crate) fn get_mut(&mut self, handle: EmitterHandle) -> Option<&mut ParticleEmitter> {
+        self.emitters.get_mut(&handle)
+    }
+    pub(crate) fn remove(&mut self, handle: EmitterHandle) -> Option<ParticleEmitter> {
+        self.emitters.remove(&handle)
+    }
+    fn update(&mut self) {
+        for emitter in self.emitters.values_mut() {
+            emitter.update(self.step_ms);
+        }
+    }
+}
+
+/// Internal activity that moves all particles forward in time.
+pub(crate) struct ParticleActivity;
+
+impl ParticleActivity {
+    pub(crate) fn register() {
+        let activity = nuts::new_domained_activity(ParticleActivity, &Domain::Frame);
+        activity.subscribe_domained(|_, domain, _: &UpdateWorld| {
+            crate::Display::from_domain(domain).particles.update();
+        });
+    }
+}
+
+/// Paint of a single particle. All particles of an emitter share texture and blend mode, which keeps them in one batch.
+pub(crate) struct ParticlePaint<'a> {
+    pub image: Option<&'a Image>,
+    pub color: Color,
+    pub blend_mode: BlendMode,
+}
+
+impl Paint for ParticlePaint<'_> {
+    fn paint_image(&self) -> Option<&Image> {
+        self.image
+    }
+    fn paint_color(&self) -> Option<Color> {
+        Some(self.color)
+    }
+    fn paint_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+}
@@ -22,27 +22,29 @@ impl Render for AbstractMesh {
         let z = z as f32 / Z_MAX as f32;
         let n = gpu_mesh.vertices.len() as u32;
         let col = paint.paint_color().unwrap_or(Color::WHITE);
+        let repeat = paint.paint_texture_repeat();
+        let extra_layout = paint.paint_extra_vertex_attribute_layout();
 
         for (index, abstract_vertex) in self.vertices.iter().enumerate() {
             let pos = *transform * abstract_vertex.pos;
-            let region = paint
+            let mut region = paint
                 .paint_image()
                 .map(|img| img.region)
                 .unwrap_or(Rectangle::new_sized((1, 1)));
+            region.size = region.size.times(repeat);
             let st = super::gpu::sample(&ABSTRACT_SPACE, &abstract_vertex.pos, &region);
-            let tex = paint
-                .paint_image()
-                .map(|img| img.texture.webgl_texture().clone());
+            let tex = paint.paint_image().map(|img| img.texture.webgl_texture());
             let extra = paint.paint_extra_vertex_attributes(index, abstract_vertex);
-            gpu_mesh
-                .vertices
-                .push(GpuVertex::new(pos, tex, st, col, z, extra));
+            gpu_mesh.vertices.push(
+                GpuVertex::new(pos, tex, st, col, z, extra).with_extra_layout(extra_layout.clone()),
+            );
         }
         let uniforms = paint.paint_uniforms();
+        let blend_mode = paint.paint_blend_mode();
         gpu_mesh.triangles.extend(
             self.triangles
                 .iter()
-                .map(|t| GpuTriangle::from_abstract(t, n, z, uniforms.clone())),
+                .map(|t| GpuTriangle::from_abstract(t, n, z, uniforms.clone(), blend_mode)),
         );
     }
 }
@@ -0,0 +1,231 @@
+//! Automatic scaling of the display when the browser window or the canvas container changes its size.
+//!
+//! Scaling is configured once through `DisplayConfig` (or `PaddleConfig::with_scaling`).
+//! Paddle then listens to `resize`, `orientationchange` and `ResizeObserver` events and re-applies the policy.
+//! The observed container may change its size because the canvas was scaled, such events are ignored when the available area is unchanged.
+//! After each change, frames receive a `DisplayResized` message, which can be handled with `FrameHandle::listen`.
+
+use crate::{js, Domain, NutsCheck, Rectangle, Vector};
+use wasm_bindgen::closure::Closure;
+use web_sys::Element;
+
+/// Policy how the game area is mapped onto the available browser area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScalingMode {
+    /// Scale the display as large as possible while keeping the aspect ratio and center it. Unused space shows as bars. (letterbox)
+    Fit,
+    /// Cover the full available area, distorting the aspect ratio if necessary.
+    Stretch,
+    /// Cover the full available area by extending the game area along one axis, keeping the aspect ratio of game coordinates.
+    /// The configured resolution is the minimum that is guaranteed to be visible.
+    Expand,
+    /// Like `Fit` but only scales by integer factors and disables texture smoothing in the browser, for crisp pixel art.
+    PixelPerfect,
+}
+
+/// The browser area that is considered available when scaling the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScalingTarget {
+    /// The visible part of the browser window, starting at the canvas position.
+    Window,
+    /// The parent element of the canvas.
+    Container,
+}
+
+/// Defines how the display is scaled automatically when the browser area changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScalingConfig {
+    pub(crate) mode: ScalingMode,
+    pub(crate) target: ScalingTarget,
+    pub(crate) margin: f32,
+}
+
+/// Shared with all frames after the display has been rescaled automatically.
+///
+/// Custom render pipelines that were created with `Display::webgl_transform()` may need to be updated when game coordinates changed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayResized {
+    /// New position and size of the canvas, in browser coordinates
+    pub browser_region: Rectangle,
+    /// New size of the full display in game coordinates. Only changes in `ScalingMode::Expand`.
+    pub game_size: Vector,
+}
+
+/// Result of applying a `ScalingMode` to an available area.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ScaledLayout {
+    /// Size of the canvas in browser pixels
+    pub canvas_size: Vector,
+    /// Offset of the canvas within the available area, in browser pixels
+    pub offset: Vector,
+    /// Size of the full display in game coordinates
+    pub game_size: Vector,
+}
+
+impl ScalingConfig {
+    pub fn new(mode: ScalingMode) -> Self {
+        Self {
+            mode,
+            target: ScalingTarget::Window,
+            margin: 0.0,
+        }
+    }
+    /// Scale to the visible browser window. (default)
+    #[inline(always)]
+    pub fn in_window(mut self) -> Self {
+        self.target = ScalingTarget::Window;
+        self
+    }
+    /// Scale to the parent element of the canvas.
+    #[inline(always)]
+    pub fn in_container(mut self) -> Self {
+        self.target = ScalingTarget::Container;
+        self
+    }
+    /// Keep some space free at the bottom and to the right, in browser pixels.
+    #[inline(always)]
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+    pub fn mode(&self) -> ScalingMode {
+        self.mode
+    }
+}
+
+impl From<ScalingMode> for ScalingConfig {
+    fn from(mode: ScalingMode) -> Self {
+        ScalingConfig::new(mode)
+    }
+}
+
+impl ScalingMode {
+    /// Computes canvas size and game area for the given available space (in browser pixels) and configured game size.
+    pub(crate) fn layout(&self, available: Vector, game_size: Vector) -> ScaledLayout {
+        let available = available.max(Vector::ONE);
+        let (canvas_size, game_size) = match self {
+            ScalingMode::Fit => {
+                let (w, h) = super::scale_to_ratio(
+                    available.x as f64,
+                    available.y as f64,
+                    game_size.x as f64,
+                    game_size.y as f64,
+                );
+                (Vector::new(w, h), game_size)
+            }
+            ScalingMode::Stretch => (available, game_size),
+            ScalingMode::Expand => {
+                let expanded = if available.x * game_size.y > available.y * game_size.x {
+                    Vector::new(
+                        (game_size.y * available.x / available.y).floor(),
+                        game_size.y,
+                    )
+                } else {
+                    Vector::new(
+                        game_size.x,
+                        (game_size.x * available.y / available.x).floor(),
+                    )
+                };
+                (available, expanded)
+            }
+            ScalingMode::PixelPerfect => {
+                let factor = (available.x / game_size.x)
+                    .min(available.y / game_size.y)
+                    .floor()
+                    .max(1.0);
+                (game_size * factor, game_size)
+            }
+        };
+        let offset = ((available - canvas_size) / 2.0).max(Vector::ZERO);
+        let offset = Vector::new(offset.x.floor(), offset.y.floor());
+        ScaledLayout {
+            canvas_size,
+            offset,
+            game_size,
+        }
+    }
+}
+
+/// Internal activity that keeps the browser listeners alive and forwards resize events to the display.
+pub(crate) struct ScalingActivity {
+    _closure_guard: Closure<dyn FnMut()>,
+    /// Available area measured after the last scaling, including changes caused by resizing the canvas itself
+    settled: Option<Vector>,
+}
+
+/// Published by the browser listeners, handled by `ScalingActivity`.
+struct BrowserResized;
+
+impl ScalingActivity {
+    pub(crate) fn register(container: &Element) {
+        let closure = Closure::wrap(Box::new(|| nuts::publish(BrowserResized)) as Box<dyn FnMut()>);
+        js::register_resize_listener(container, &closure);
+        let activity = nuts::new_domained_activity(
+            ScalingActivity {
+                _closure_guard: closure,
+                settled: None,
+            },
+            &Domain::Frame,
+        );
+        activity.subscribe_domained(|a, domain, _: &BrowserResized| {
+            let display = crate::Display::from_domain(domain);
+            let available = display.scaling_available_area().nuts_check().flatten();
+            if available.is_some() && available == a.settled {
+                // Most likely the observed container adapted to the canvas size, which must not start another round of scaling
+                return;
+            }
+            if let Some(true) = display.apply_scaling().nuts_check() {
+                crate::share(DisplayResized {
+                    browser_region: display.browser_region(),
+                    game_size: display.game_size(),
+                });
+            }
+            a.settled = display.scaling_available_area().nuts_check().flatten();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_letterbox() {
+        let layout = ScalingMode::Fit.layout(Vector::new(1000, 1000), Vector::new(200, 100));
+        assert_eq!(layout.canvas_size, Vector::new(1000, 500));
+        assert_eq!(layout.offset, Vector::new(0, 250));
+        assert_eq!(layout.game_size, Vector::new(200, 100));
+    }
+
+    #[test]
+    fn stretch() {
+        let layout = ScalingMode::Stretch.layout(Vector::new(1000, 1000), Vector::new(200, 100));
+        assert_eq!(layout.canvas_size, Vector::new(1000, 1000));
+        assert_eq!(layout.offset, Vector::ZERO);
+        assert_eq!(layout.game_size, Vector::new(200, 100));
+    }
+
+    #[test]
+    fn expand() {
+        let wide = ScalingMode::Expand.layout(Vector::new(400, 100), Vector::new(200, 100));
+        assert_eq!(wide.canvas_size, Vector::new(400, 100));
+        assert_eq!(wide.game_size, Vector::new(400, 100));
+
+        let tall = ScalingMode::Expand.layout(Vector::new(300, 300), Vector::new(200, 100));
+        assert_eq!(tall.canvas_size, Vector::new(300, 300));
+        assert_eq!(tall.game_size, Vector::new(200, 200));
+    }
+
+    #[test]
+    fn pixel_perfect() {
+        let layout =
+            ScalingMode::PixelPerfect.layout(Vector::new(1000, 700), Vector::new(320, 180));
+        assert_eq!(layout.canvas_size, Vector::new(960, 540));
+        assert_eq!(layout.offset, Vector::new(20, 80));
+
+        let too_small =
+            ScalingMode::PixelPerfect.layout(Vector::new(100, 100), Vector::new(320, 180));
+        assert_eq!(too_small.canvas_size, Vector::new(320, 180));
+        assert_eq!(too_small.offset, Vector::ZERO);
+    }
+}
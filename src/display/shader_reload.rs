@@ -0,0 +1,134 @@
+//! Reloading shaders from URLs while the game runs, to iterate on GLSL without rebuilding the crate.
+//!
+//! Watched sources are fetched with `load_file` once per poll interval. When they have changed, the render pipeline is compiled again in place.
+//! If that fails, the previous program stays in use and the error log is reported like any other error.
+//! This is meant for development, in a release build the shader sources should be included in the binary.
+
+use nuts::DomainState;
+
+use crate::web_integration::{start_thread, ThreadHandler};
+use crate::{load_file, Domain, ErrorMessage, NutsCheck, PaddleResult, RenderPipelineHandle};
+
+/// Time between two polls of all watched shader sources
+const POLL_INTERVAL_MS: i32 = 1000;
+
+/// Internal activity that polls shader sources and recompiles render pipelines when they change.
+pub(crate) struct ShaderReloadActivity {
+    watched: Vec<WatchedPipeline>,
+    /// Started with the first watched pipeline
+    poll_thread: Option<ThreadHandler>,
+    /// Appended to URLs to bypass the browser cache
+    polls: u64,
+}
+
+struct WatchedPipeline {
+    rp: RenderPipelineHandle,
+    vertex_url: String,
+    fragment_url: String,
+    /// Sources as of the last poll, to recompile only after a change
+    last_sources: Option<(String, String)>,
+    loading: bool,
+}
+
+/// Sent by `Display::watch_render_pipeline`
+pub(crate) struct WatchShaders {
+    pub rp: RenderPipelineHandle,
+    pub vertex_url: String,
+    pub fragment_url: String,
+}
+/// Sent by `Display::unwatch_render_pipeline`
+pub(crate) struct UnwatchShaders(pub RenderPipelineHandle);
+/// Published by the poll thread
+struct PollShaders;
+struct LoadedShaderSources {
+    rp: RenderPipelineHandle,
+    sources: PaddleResult<(String, String)>,
+}
+
+impl ShaderReloadActivity {
+    pub(crate) fn register() {
+        let activity = ShaderReloadActivity {
+            watched: Vec::new(),
+            poll_thread: None,
+            polls: 0,
+        };
+        let aid = nuts::new_domained_activity(activity, &Domain::Frame);
+        aid.private_channel(ShaderReloadActivity::watch);
+        aid.private_channel(ShaderReloadActivity::unwatch);
+        aid.subscribe(ShaderReloadActivity::poll);
+        aid.private_domained_channel(ShaderReloadActivity::sources_loaded);
+    }
+    fn watch(&mut self, msg: WatchShaders) {
+        self.watched.retain(|w| w.rp != msg.rp);
+        self.watched.push(WatchedPipeline {
+            rp: msg.rp,
+            vertex_url: msg.vertex_url,
+            fragment_url: msg.fragment_url,
+            last_sources: None,
+            loading: false,
+        });
+        if self.poll_thread.is_none() {
+            self.poll_thread =
+                start_thread(|| nuts::publish(PollShaders), POLL_INTERVAL_MS).nuts_check();
+        }
+        self.poll(&PollShaders);
+    }
+    fn unwatch(&mut self, msg: UnwatchShaders) {
+        self.watched.retain(|w| w.rp != msg.0);
+        if self.watched.is_empty() {
+            // Dropping the handler stops the interval
+            self.poll_thread = None;
+        }
+    }
+    fn poll(&mut self, _: &PollShaders) {
+        self.polls += 1;
+        for watched in self.watched.iter_mut().filter(|w| !w.loading) {
+            watched.loading = true;
+            let rp = watched.rp;
+            let vertex_url = uncached_url(&watched.vertex_url, self.polls);
+            let fragment_url = uncached_url(&watched.fragment_url, self.polls);
+            wasm_bindgen_futures::spawn_local(async move {
+                let sources = load_sources(&vertex_url, &fragment_url).await;
+                nuts::send_to::<ShaderReloadActivity, _>(LoadedShaderSources { rp, sources });
+            });
+        }
+    }
+    fn sources_loaded(&mut self, domain: &mut DomainState, msg: LoadedShaderSources) {
+        let watched = match self.watched.iter_mut().find(|w| w.rp == msg.rp) {
+            Some(watched) => watched,
+            // No longer watched
+            None => return,
+        };
+        watched.loading = false;
+        let sources = match msg.sources.nuts_check() {
+            Some(sources) => sources,
+            None => return,
+        };
+        if watched.last_sources.as_ref() == Some(&sources) {
+            return;
+        }
+        crate::Display::from_domain(domain)
+            .canvas_mut()
+            .recompile_render_pipeline(msg.rp, &sources.0, &sources.1)
+            .nuts_check();
+        // Also remembered after errors, to report them only once per change
+        watched.last_sources = Some(sources);
+    }
+}
+
+async fn load_sources(vertex_url: &str, fragment_url: &str) -> PaddleResult<(String, String)> {
+    let vertex = load_text(vertex_url).await?;
+    let fragment = load_text(fragment_url).await?;
+    Ok((vertex, fragment))
+}
+
+async fn load_text(url: &str) -> PaddleResult<String> {
+    let bytes = load_file(url).await?;
+    String::from_utf8(bytes)
+        .map_err(|_| ErrorMessage::technical(format!("Shader source {} is not valid UTF-8", url)))
+}
+
+fn uncached_url(url: &str, poll: u64) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}paddle_reload={}", url, separator, poll)
+}
@@ -1,9 +1,14 @@
 mod floating_text;
+mod rich_text;
+mod text_metrics;
 mod text_node;
 mod text_pool;
 mod text_to_user;
 
 pub use floating_text::*;
+pub use rich_text::{RichSpan, RichText, SpanStyle};
+pub(crate) use text_metrics::wrap_ranges;
+pub use text_metrics::{CssFont, TextMetrics};
 pub use text_node::*;
 pub use text_pool::*;
 pub use text_to_user::*;
@@ -1,4 +1,8 @@
-use crate::{DisplayArea, FitStrategy, PaddleResult, Rectangle, TextNode, Vector};
+use super::text_metrics::ellipsize;
+use crate::{
+    AssetLibrary, CssFont, DisplayArea, ErrorMessage, FitStrategy, JsError, NutsCheck,
+    PaddleResult, Rectangle, RichText, TextNode, Vector,
+};
 use div::DivHandle;
 
 // dev note: the API on this is pretty bad, lots of verbose house keeping
@@ -16,6 +20,27 @@ pub struct FloatingText {
     node: TextNode,
     pane: DivHandle,
     fit: FitStrategy,
+    overflow: TextOverflow,
+    /// Font from the style sheets, before shrinking it
+    base_font: Option<CssFont>,
+    /// False if the text has to be fitted into the area again on the next draw
+    fitted: bool,
+    /// False if the text has to be scaled to the area again on the next draw, see `FitStrategy::css_fit`
+    scaled: bool,
+}
+
+/// How a `FloatingText` handles text that does not fit into its area
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextOverflow {
+    /// Text may exceed the area (default)
+    #[default]
+    Visible,
+    /// Reduce the font size until the text fits, but not below `min_size` in CSS pixels
+    Shrink { min_size: f32 },
+    /// Cut the text after the last line that fits and end it with "…".
+    ///
+    /// Only applies to plain text, rich text is shown in full.
+    Ellipsis,
 }
 
 impl FloatingText {
@@ -54,6 +79,10 @@ impl FloatingText {
             node,
             pane,
             fit,
+            overflow: TextOverflow::Visible,
+            base_font: None,
+            fitted: false,
+            scaled: false,
         };
         Ok(float)
     }
@@ -65,6 +94,14 @@ impl FloatingText {
             area.width() as u32,
             area.height() as u32,
         );
+        if (w, h) != (self.w, self.h) {
+            self.fitted = false;
+            self.scaled = false;
+        }
+        self.x = x;
+        self.y = y;
+        self.w = w;
+        self.h = h;
         self.z = z;
         self.node.set_z(z)?;
 
@@ -83,16 +120,53 @@ impl FloatingText {
     pub fn update_text(&mut self, text: &str) {
         self.node.update(text);
     }
+    /// Show text with styled spans and inline icons, see `RichText` for the markup.
+    ///
+    /// Icons are looked up in the asset library, for example from `DisplayArea::full().assets()`.
+    pub fn update_rich_text(&mut self, text: &RichText, assets: &AssetLibrary) -> PaddleResult<()> {
+        if self.node.rich() != Some(text) {
+            self.scaled = false;
+        }
+        self.node.update_rich(text, assets)
+    }
     pub fn update_fit_strategy(&mut self, fit: FitStrategy) -> Result<(), div::DivError> {
         if self.fit == fit {
             return Ok(());
         }
         fit.apply_css(self.pane)?;
         self.fit = fit;
+        self.scaled = false;
+        Ok(())
+    }
+    /// Fit text into the area by shrinking or shortening it, see `TextOverflow`.
+    ///
+    /// The text is measured again on the next draw after the text or the size of the area changed.
+    pub fn update_overflow(&mut self, overflow: TextOverflow) -> PaddleResult<()> {
+        if self.overflow == overflow {
+            return Ok(());
+        }
+        if let (TextOverflow::Shrink { .. }, Some(base)) = (self.overflow, &self.base_font) {
+            self.pane
+                .set_css("font-size", &format!("{}px", base.size()))?;
+        }
+        if self.overflow == TextOverflow::Ellipsis && !self.node.is_rich() {
+            self.node.mark_dirty();
+        }
+        self.overflow = overflow;
+        self.fitted = false;
         Ok(())
     }
     pub fn draw(&mut self) {
+        if self.node.is_dirty() {
+            self.scaled = false;
+        }
+        if self.overflow != TextOverflow::Visible && (!self.fitted || self.node.is_dirty()) {
+            self.fitted = self.fit_text().nuts_check().unwrap_or(false);
+        }
         self.node.draw();
+        if !self.scaled {
+            self.scaled = self.scale_text().nuts_check().unwrap_or(false);
+        }
     }
     pub fn show(&self) -> Result<(), div::DivError> {
         self.pane.show()
@@ -119,9 +193,102 @@ impl FloatingText {
         Ok(())
     }
     pub fn add_css(&mut self, property: &str, value: &str) -> Result<(), div::DivError> {
+        if property.starts_with("font") || property == "line-height" {
+            self.base_font = None;
+            self.fitted = false;
+            self.scaled = false;
+        }
         self.pane.set_css(property, value)
     }
 
+    /// Returns false if the text could not be measured because the element is not shown
+    fn fit_text(&mut self) -> PaddleResult<bool> {
+        let rect = self.node.element().get_bounding_client_rect();
+        let (width, height) = (rect.width() as f32, rect.height() as f32);
+        if width <= 0.0 || height <= 0.0 {
+            return Ok(false);
+        }
+        let base = match &self.base_font {
+            Some(font) => font.clone(),
+            None => {
+                let font = CssFont::from_element(self.node.element())?;
+                self.base_font = Some(font.clone());
+                font
+            }
+        };
+        match self.overflow {
+            TextOverflow::Visible => {}
+            TextOverflow::Shrink { min_size } => {
+                let min_size = min_size.min(base.size());
+                let mut font = base;
+                loop {
+                    let size = font.measure(self.node.text(), Some(width))?.size;
+                    if (size.x <= width && size.y <= height) || font.size() <= min_size {
+                        break;
+                    }
+                    let smaller = (font.size() * 0.9).max(min_size);
+                    font = font.with_size(smaller);
+                }
+                self.pane
+                    .set_css("font-size", &format!("{}px", font.size()))?;
+            }
+            TextOverflow::Ellipsis => {
+                if !self.node.is_rich() {
+                    let metrics = base.measure(self.node.text(), Some(width))?;
+                    let max_lines = (height / metrics.line_height).floor() as usize;
+                    let lines = ellipsize(metrics.lines, max_lines, width, |line| {
+                        base.line_width(line).unwrap_or(0.0)
+                    });
+                    // The browser breaks the lines again at the same spaces
+                    self.node.draw_as(&lines.join(" "));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Scale the text with a CSS transform for `FitStrategy::Stretch`, `Contain` and `Cover`.
+    ///
+    /// Returns false if the text could not be measured because the element is not shown
+    fn scale_text(&mut self) -> PaddleResult<bool> {
+        self.pane.set_css("transform", "none")?;
+        self.pane.set_css("clip-path", "none")?;
+        if !self.fit.scales_content() {
+            return Ok(true);
+        }
+        let element = self.node.element();
+        let frame = element.get_bounding_client_rect();
+        if frame.width() <= 0.0 || frame.height() <= 0.0 {
+            return Ok(false);
+        }
+        let content = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| ErrorMessage::technical("No document to measure text".to_owned()))?
+            .create_range()
+            .map_err(JsError::from_js_value)?;
+        content
+            .select_node_contents(element)
+            .map_err(JsError::from_js_value)?;
+        let content = content.get_bounding_client_rect();
+        let fit = self.fit.css_fit(
+            Vector::new(content.width() as f32, content.height() as f32),
+            Vector::new(frame.width() as f32, frame.height() as f32),
+        );
+        if let Some(fit) = fit {
+            self.pane.set_css(
+                "transform",
+                &format!("scale({}, {})", fit.scale.x, fit.scale.y),
+            )?;
+            if fit.clip != Vector::ZERO {
+                self.pane.set_css(
+                    "clip-path",
+                    &format!("inset({}px {}px)", fit.clip.y, fit.clip.x),
+                )?;
+            }
+        }
+        Ok(true)
+    }
+
     /// Position in screen coordinates.
     fn pos(&self) -> Rectangle {
         Rectangle::new((self.x, self.y), (self.w, self.h))
@@ -130,23 +297,28 @@ impl FloatingText {
 
 impl FitStrategy {
     fn apply_css(&self, pane: DivHandle) -> Result<(), div::DivError> {
-        match self {
-            FitStrategy::TopLeft => {
-                pane.set_css("display", "flex")?;
-                pane.set_css("justify-content", "start")?;
-                pane.set_css("align-items", "normal")?;
-            }
-            FitStrategy::LeftCenter => {
-                pane.set_css("display", "flex")?;
-                pane.set_css("justify-content", "start")?;
-                pane.set_css("align-items", "center")?;
-            }
-            FitStrategy::Center => {
-                pane.set_css("display", "flex")?;
-                pane.set_css("justify-content", "center")?;
-                pane.set_css("align-items", "center")?;
-            }
-        }
+        let (justify_content, text_align) = match self.alignment().0 {
+            x if x < 0.5 => ("start", "left"),
+            x if x > 0.5 => ("end", "right"),
+            _ => ("center", "center"),
+        };
+        let align_items = match self.alignment().1 {
+            y if y < 0.5 => "normal",
+            y if y > 0.5 => "flex-end",
+            _ => "center",
+        };
+        // Scaling strategies center the text on a single line, `FloatingText::scale_text` then scales the element around its center
+        let (justify_content, align_items, text_align, white_space) = if self.scales_content() {
+            ("center", "center", "center", "nowrap")
+        } else {
+            (justify_content, align_items, text_align, "normal")
+        };
+        pane.set_css("display", "flex")?;
+        pane.set_css("justify-content", justify_content)?;
+        pane.set_css("align-items", align_items)?;
+        pane.set_css("text-align", text_align)?;
+        pane.set_css("white-space", white_space)?;
+        pane.set_css("transform-origin", "center")?;
         Ok(())
     }
 }
@@ -0,0 +1,329 @@
+//! Text with inline styles, parsed from a small markup format.
+//!
+//! Supported tags are `[b]`, `[i]`, `[u]` and `[color=...]` (closed with `[/b]`, `[/i]`, `[/u]`, `[/color]`) and `[img=name]` for inline icons.
+//! Colors are named (`red`) or hexadecimal (`#f80`, `#ff8800`). A literal `[` is written as `[[`.
+//! Unknown or malformed tags stay in the text as they are, the parser never fails.
+
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement};
+
+use crate::quicksilver_compat::Color;
+use crate::{parse_color, AssetLibrary, JsError, PaddleResult};
+
+/// Text split into styled spans and inline icons.
+///
+/// # Example
+/// ```
+/// # use paddle::RichText;
+/// let text = RichText::parse("Costs [color=#ffd700][b]20[/b][/color] [img=coin]");
+/// assert_eq!(text.plain_text(), "Costs 20 ");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichText {
+    spans: Vec<RichSpan>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RichSpan {
+    Text {
+        text: String,
+        style: SpanStyle,
+    },
+    /// Name of an icon, registered with `AssetLibrary::add_text_icon`
+    Icon(String),
+}
+
+/// Style of a text span. Unset colors fall back to the color of the surrounding text.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpanStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl RichText {
+    /// Text without any markup
+    pub fn plain(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let spans = if text.is_empty() {
+            vec![]
+        } else {
+            vec![RichSpan::Text {
+                text,
+                style: SpanStyle::default(),
+            }]
+        };
+        Self { spans }
+    }
+    /// Parse text with markup tags, see the module documentation for the format.
+    pub fn parse(markup: &str) -> Self {
+        let mut parser = Parser::default();
+        let mut rest = markup;
+        while let Some(start) = rest.find('[') {
+            parser.text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("[[") {
+                parser.text.push('[');
+                rest = after;
+                continue;
+            }
+            let tag = rest[1..].find(']').map(|end| &rest[1..end + 1]);
+            match tag {
+                Some(tag) if parser.apply_tag(tag) => rest = &rest[tag.len() + 2..],
+                _ => {
+                    parser.text.push('[');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        parser.text.push_str(rest);
+        parser.flush();
+        Self {
+            spans: parser.spans,
+        }
+    }
+    pub fn spans(&self) -> &[RichSpan] {
+        &self.spans
+    }
+    /// The text content without styles, icons are left out
+    pub fn plain_text(&self) -> String {
+        self.spans
+            .iter()
+            .filter_map(|span| match span {
+                RichSpan::Text { text, .. } => Some(text.as_str()),
+                RichSpan::Icon(_) => None,
+            })
+            .collect()
+    }
+
+    /// Replace the children of a DOM element with the styled spans.
+    ///
+    /// Text is only ever inserted as text content, markup can therefore not inject HTML.
+    pub(crate) fn render_dom(&self, parent: &Element, assets: &AssetLibrary) -> PaddleResult<()> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        parent.set_text_content(None);
+        for span in &self.spans {
+            let node = match span {
+                RichSpan::Text { text, style } => {
+                    let node = create_html_element(&document, "span")?;
+                    node.set_text_content(Some(text));
+                    let css = node.style();
+                    if let Some(color) = style.color {
+                        css.set_property("color", &css_color(color))
+                            .map_err(JsError::from_js_value)?;
+                    }
+                    if style.bold {
+                        css.set_property("font-weight", "bold")
+                            .map_err(JsError::from_js_value)?;
+                    }
+                    if style.italic {
+                        css.set_property("font-style", "italic")
+                            .map_err(JsError::from_js_value)?;
+                    }
+                    if style.underline {
+                        css.set_property("text-decoration", "underline")
+                            .map_err(JsError::from_js_value)?;
+                    }
+                    node
+                }
+                RichSpan::Icon(name) => {
+                    let url = match assets.text_icon(name) {
+                        Some(desc) => desc.url().map(str::to_owned),
+                        None => None,
+                    };
+                    let url = match url {
+                        Some(url) => url,
+                        None => continue,
+                    };
+                    let node = create_html_element(&document, "img")?;
+                    node.set_attribute("src", &url)
+                        .map_err(JsError::from_js_value)?;
+                    node.set_attribute("alt", name)
+                        .map_err(JsError::from_js_value)?;
+                    let css = node.style();
+                    css.set_property("height", "1em")
+                        .map_err(JsError::from_js_value)?;
+                    css.set_property("vertical-align", "middle")
+                        .map_err(JsError::from_js_value)?;
+                    node
+                }
+            };
+            parent.append_child(&node).map_err(JsError::from_js_value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Parser {
+    spans: Vec<RichSpan>,
+    text: String,
+    colors: Vec<Color>,
+    bold: usize,
+    italic: usize,
+    underline: usize,
+}
+
+impl Parser {
+    fn style(&self) -> SpanStyle {
+        SpanStyle {
+            color: self.colors.last().cloned(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            underline: self.underline > 0,
+        }
+    }
+    /// Finish the current text span, merging it with the previous span of the same style
+    fn flush(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let text = std::mem::take(&mut self.text);
+        let style = self.style();
+        if let Some(RichSpan::Text {
+            text: previous,
+            style: previous_style,
+        }) = self.spans.last_mut()
+        {
+            if *previous_style == style {
+                previous.push_str(&text);
+                return;
+            }
+        }
+        self.spans.push(RichSpan::Text { text, style });
+    }
+    /// Returns false if the tag is not valid at this point, in which case nothing changes
+    fn apply_tag(&mut self, tag: &str) -> bool {
+        if let Some(name) = tag.strip_prefix("img=") {
+            if name.is_empty() {
+                return false;
+            }
+            self.flush();
+            self.spans.push(RichSpan::Icon(name.to_owned()));
+            return true;
+        }
+        if let Some(value) = tag.strip_prefix("color=") {
+            return match parse_color(value) {
+                Some(color) => {
+                    self.flush();
+                    self.colors.push(color);
+                    true
+                }
+                None => false,
+            };
+        }
+        let (closing, name) = match tag.strip_prefix('/') {
+            Some(name) => (true, name),
+            None => (false, tag),
+        };
+        let open = match name {
+            "b" => self.bold,
+            "i" => self.italic,
+            "u" => self.underline,
+            "color" if closing => self.colors.len(),
+            _ => return false,
+        };
+        if closing && open == 0 {
+            return false;
+        }
+        self.flush();
+        match (name, closing) {
+            ("color", _) => {
+                self.colors.pop();
+            }
+            ("b", false) => self.bold += 1,
+            ("b", true) => self.bold -= 1,
+            ("i", false) => self.italic += 1,
+            ("i", true) => self.italic -= 1,
+            ("u", false) => self.underline += 1,
+            ("u", true) => self.underline -= 1,
+            _ => unreachable!(),
+        }
+        true
+    }
+}
+
+fn create_html_element(document: &web_sys::Document, tag: &str) -> PaddleResult<HtmlElement> {
+    let element = document
+        .create_element(tag)
+        .map_err(JsError::from_js_value)?;
+    Ok(element.unchecked_into())
+}
+
+fn css_color(color: Color) -> String {
+    format!(
+        "rgba({},{},{},{})",
+        (color.r * 255.0).round(),
+        (color.g * 255.0).round(),
+        (color.b * 255.0).round(),
+        color.a,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, style: SpanStyle) -> RichSpan {
+        RichSpan::Text {
+            text: text.to_owned(),
+            style,
+        }
+    }
+
+    #[test]
+    fn nested_styles() {
+        let rich = RichText::parse("a[b]b[color=red]c[/b]d[/color][/b]e");
+        let bold = SpanStyle {
+            bold: true,
+            ..Default::default()
+        };
+        let red = SpanStyle {
+            color: Some(Color::RED),
+            ..Default::default()
+        };
+        assert_eq!(
+            rich.spans(),
+            &[
+                text("a", SpanStyle::default()),
+                text("b", bold),
+                text(
+                    "c",
+                    SpanStyle {
+                        color: Some(Color::RED),
+                        bold: true,
+                        ..Default::default()
+                    }
+                ),
+                text("d", red),
+                // The unmatched closing tag stays in the text
+                text("[/b]e", SpanStyle::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn icons_and_escapes() {
+        let rich = RichText::parse("[[b] [img=coin]x[img=]");
+        assert_eq!(
+            rich.spans(),
+            &[
+                text("[b] ", SpanStyle::default()),
+                RichSpan::Icon("coin".to_owned()),
+                text("x[img=]", SpanStyle::default()),
+            ]
+        );
+        assert_eq!(rich.plain_text(), "[b] x[img=]");
+    }
+
+    #[test]
+    fn markup_is_not_html() {
+        let rich = RichText::parse("<script>[color=nope]x[/color]</script>[unknown");
+        assert_eq!(
+            rich.plain_text(),
+            "<script>[color=nope]x[/color]</script>[unknown"
+        );
+        assert_eq!(RichText::plain("[b]"), RichText::parse("[[b]"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Measuring text before it is shown, for bitmap fonts and for CSS fonts rendered by the browser.
+
+use std::cell::RefCell;
+use std::ops::Range;
+
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, Element, HtmlCanvasElement};
+
+use crate::{ErrorMessage, JsError, PaddleResult, Vector};
+
+/// Size and line breaks of a block of text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextMetrics {
+    /// Text of each line, without the spaces at which lines were broken
+    pub lines: Vec<String>,
+    pub line_widths: Vec<f32>,
+    pub line_height: f32,
+    /// Width of the widest line and height of all lines together
+    pub size: Vector,
+}
+
+/// A font as specified in CSS, used to measure text of a `FloatingText` or any other DOM text.
+///
+/// Sizes are in CSS pixels. Measuring uses a 2d canvas, the result matches the browser closely but not necessarily exactly.
+///
+/// # Example
+/// ```no_run
+/// # use paddle::CssFont;
+/// let font = CssFont::new("sans-serif", 16.0).with_weight("bold");
+/// let metrics = font.measure("Start new game", Some(120.0)).unwrap();
+/// if metrics.lines.len() > 1 {
+///     // does not fit on a single line
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CssFont {
+    pub(crate) family: String,
+    pub(crate) size: f32,
+    pub(crate) weight: String,
+    pub(crate) style: String,
+    /// Line height relative to the font size
+    pub(crate) line_height: f32,
+}
+
+impl TextMetrics {
+    pub(crate) fn from_lines(
+        lines: Vec<String>,
+        line_height: f32,
+        mut width_of: impl FnMut(&str) -> f32,
+    ) -> Self {
+        let line_widths: Vec<f32> = lines.iter().map(|line| width_of(line)).collect();
+        let width = line_widths.iter().cloned().fold(0.0, f32::max);
+        let size = Vector::new(width, lines.len() as f32 * line_height);
+        Self {
+            lines,
+            line_widths,
+            line_height,
+            size,
+        }
+    }
+}
+
+impl CssFont {
+    /// Normal weight and style, with a line height of 1.2 times the size (the usual value for `line-height: normal`)
+    pub fn new(family: impl Into<String>, size: f32) -> Self {
+        Self {
+            family: family.into(),
+            size,
+            weight: "normal".to_owned(),
+            style: "normal".to_owned(),
+            line_height: 1.2,
+        }
+    }
+    /// The font an element is currently shown with, as computed by the browser
+    pub fn from_element(element: &Element) -> PaddleResult<Self> {
+        let style = web_sys::window()
+            .unwrap()
+            .get_computed_style(element)
+            .map_err(JsError::from_js_value)?
+            .ok_or_else(|| ErrorMessage::technical("No computed style for element".to_owned()))?;
+        let property = |name: &str| {
+            style
+                .get_property_value(name)
+                .map_err(JsError::from_js_value)
+        };
+        let size = parse_px(&property("font-size")?)
+            .ok_or_else(|| ErrorMessage::technical("Font size is not in pixels".to_owned()))?;
+        let line_height = parse_px(&property("line-height")?)
+            .map(|px| px / size)
+            .unwrap_or(1.2);
+        Ok(Self {
+            family: property("font-family")?,
+            size,
+            weight: property("font-weight")?,
+            style: property("font-style")?,
+            line_height,
+        })
+    }
+    /// Same font in another size, the line height stays relative to the size
+    #[inline(always)]
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+    /// CSS font weight, such as `bold` or `600`
+    #[inline(always)]
+    pub fn with_weight(mut self, weight: impl Into<String>) -> Self {
+        self.weight = weight.into();
+        self
+    }
+    #[inline(always)]
+    pub fn with_italic(mut self, italic: bool) -> Self {
+        self.style = if italic { "italic" } else { "normal" }.to_owned();
+        self
+    }
+    /// Line height relative to the font size
+    #[inline(always)]
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = line_height;
+        self
+    }
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+    /// Distance between two lines in pixels
+    pub fn line_height(&self) -> f32 {
+        self.size * self.line_height
+    }
+    /// The font in CSS shorthand notation, e.g. `italic bold 16px sans-serif`
+    pub fn css(&self) -> String {
+        format!(
+            "{} {} {}px {}",
+            self.style, self.weight, self.size, self.family
+        )
+    }
+    /// Width of a single line of text
+    pub fn line_width(&self, line: &str) -> PaddleResult<f32> {
+        with_measuring_context(|ctx| {
+            ctx.set_font(&self.css());
+            measure(ctx, line)
+        })
+    }
+    /// Break the text into lines at line breaks and wherever the next word would exceed the maximum width, and measure them.
+    ///
+    /// Words wider than the maximum width are put on a line of their own, without breaking them.
+    pub fn measure(&self, text: &str, max_width: Option<f32>) -> PaddleResult<TextMetrics> {
+        with_measuring_context(|ctx| {
+            ctx.set_font(&self.css());
+            let mut error = None;
+            let mut width_of = |line: &str| match measure(ctx, line) {
+                Ok(width) => width,
+                Err(e) => {
+                    error.get_or_insert(e);
+                    0.0
+                }
+            };
+            let lines = wrap_words(text, max_width, &mut width_of);
+            let metrics = TextMetrics::from_lines(lines, self.line_height(), &mut width_of);
+            match error {
+                Some(e) => Err(e),
+                None => Ok(metrics),
+            }
+        })
+    }
+}
+
+/// Split text into lines, at line breaks and wherever the next word would exceed the maximum width.
+pub(crate) fn wrap_words(
+    text: &str,
+    max_width: Option<f32>,
+    mut width_of: impl FnMut(&str) -> f32,
+) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    wrap_ranges(
+        &chars,
+        |c| Some(*c),
+        max_width,
+        |line| width_of(&line.iter().collect::<String>()),
+    )
+    .into_iter()
+    .map(|line| chars[line].iter().collect())
+    .collect()
+}
+
+/// Index ranges of the items on each line, split at line breaks and wherever the next word would exceed the maximum width.
+///
+/// Only items that are a `'\n'` or `' '` character are considered for breaking lines.
+/// The spaces where lines were wrapped are not part of any line.
+/// Words wider than the maximum width are put on a line of their own, without breaking them.
+///
+/// This is the line breaking shared by bitmap fonts and CSS fonts, such that both wrap text the same way.
+pub(crate) fn wrap_ranges<T>(
+    items: &[T],
+    as_char: impl Fn(&T) -> Option<char>,
+    max_width: Option<f32>,
+    mut width_of: impl FnMut(&[T]) -> f32,
+) -> Vec<Range<usize>> {
+    let is_char = |i: usize, ch: char| as_char(&items[i]) == Some(ch);
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+    loop {
+        let paragraph_end = (paragraph_start..items.len())
+            .find(|i| is_char(*i, '\n'))
+            .unwrap_or(items.len());
+        let mut line = paragraph_start..paragraph_start;
+        let mut word_start = paragraph_start;
+        loop {
+            let word_end = (word_start..paragraph_end)
+                .find(|i| is_char(*i, ' '))
+                .unwrap_or(paragraph_end);
+            let exceeds = match max_width {
+                Some(max) => width_of(&items[line.start..word_end]) > max,
+                None => false,
+            };
+            if exceeds && line.start < word_start {
+                lines.push(line.clone());
+                line.start = word_start;
+            }
+            line.end = word_end;
+            if word_end == paragraph_end {
+                break;
+            }
+            word_start = word_end + 1;
+        }
+        lines.push(line);
+        if paragraph_end == items.len() {
+            break;
+        }
+        paragraph_start = paragraph_end + 1;
+    }
+    lines
+}
+
+/// Keep at most `max_lines` lines. If lines were cut, the last one ends with an ellipsis.
+/// Lines wider than the maximum width are shortened and end with an ellipsis, too.
+pub(crate) fn ellipsize(
+    mut lines: Vec<String>,
+    max_lines: usize,
+    max_width: f32,
+    mut width_of: impl FnMut(&str) -> f32,
+) -> Vec<String> {
+    let cut = lines.len() > max_lines.max(1);
+    lines.truncate(max_lines.max(1));
+    let last = lines.len().saturating_sub(1);
+    for (i, line) in lines.iter_mut().enumerate() {
+        if (cut && i == last) || width_of(line) > max_width {
+            *line = shorten(line, max_width, &mut width_of);
+        }
+    }
+    lines
+}
+
+fn shorten(line: &str, max_width: f32, mut width_of: impl FnMut(&str) -> f32) -> String {
+    let mut chars: Vec<char> = line.chars().collect();
+    loop {
+        let kept: String = chars.iter().collect();
+        let candidate = format!("{}…", kept.trim_end());
+        if chars.is_empty() || width_of(&candidate) <= max_width {
+            return candidate;
+        }
+        chars.pop();
+    }
+}
+
+fn parse_px(value: &str) -> Option<f32> {
+    value.trim().strip_suffix("px")?.parse().ok()
+}
+
+fn measure(ctx: &CanvasRenderingContext2d, line: &str) -> PaddleResult<f32> {
+    let metrics = ctx.measure_text(line).map_err(JsError::from_js_value)?;
+    Ok(metrics.width() as f32)
+}
+
+thread_local! {
+    /// Canvas that is never attached to the document, only used for measuring text
+    static MEASURING_CONTEXT: RefCell<Option<CanvasRenderingContext2d>> = const { RefCell::new(None) };
+}
+
+fn with_measuring_context<T>(
+    f: impl FnOnce(&CanvasRenderingContext2d) -> PaddleResult<T>,
+) -> PaddleResult<T> {
+    MEASURING_CONTEXT.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            *cell = Some(new_measuring_context()?);
+        }
+        f(cell.as_ref().unwrap())
+    })
+}
+
+fn new_measuring_context() -> PaddleResult<CanvasRenderingContext2d> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .map_err(JsError::from_js_value)?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| ErrorMessage::technical("Failed creating canvas".to_owned()))?;
+    canvas
+        .get_context("2d")
+        .map_err(JsError::from_js_value)?
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| ErrorMessage::technical("Failed loading 2d canvas context".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is 10 wide
+    fn width(text: &str) -> f32 {
+        text.chars().count() as f32 * 10.0
+    }
+
+    #[test]
+    fn wrap_at_words() {
+        assert_eq!(
+            wrap_words("AB CA B\nCCCC", Some(50.0), width),
+            vec!["AB CA", "B", "CCCC"]
+        );
+        assert_eq!(
+            wrap_words("ABCABC A", Some(30.0), width),
+            vec!["ABCABC", "A"]
+        );
+        assert_eq!(wrap_words("AB CA B", None, width), vec!["AB CA B"]);
+        let metrics = TextMetrics::from_lines(wrap_words("AB C\nC", None, width), 20.0, width);
+        assert_eq!(metrics.line_widths, vec![40.0, 10.0]);
+        assert_eq!(metrics.size, Vector::new(40.0, 40.0));
+    }
+
+    #[test]
+    fn wrap_items_without_chars() {
+        // Items that are not characters never break a line, but count towards its width
+        let items = [Some('A'), None, Some(' '), None, Some('\n'), Some('A')];
+        let width = |line: &[Option<char>]| line.len() as f32 * 10.0;
+        assert_eq!(
+            wrap_ranges(&items, |item| *item, Some(30.0), width),
+            vec![0..2, 3..4, 5..6]
+        );
+        assert_eq!(
+            wrap_ranges(&items, |item| *item, None, width),
+            vec![0..4, 5..6]
+        );
+    }
+
+    #[test]
+    fn ellipsis() {
+        let lines = wrap_words("Start a new game now", Some(100.0), width);
+        assert_eq!(lines, vec!["Start a", "new game", "now"]);
+        assert_eq!(
+            ellipsize(lines.clone(), 2, 100.0, width),
+            vec!["Start a", "new game…"]
+        );
+        assert_eq!(ellipsize(lines.clone(), 3, 100.0, width), lines);
+        // Overlong words are shortened even when all lines are kept
+        assert_eq!(
+            ellipsize(vec!["Unbelievable".to_owned()], 1, 60.0, width),
+            vec!["Unbel…"]
+        );
+        assert_eq!(ellipsize(lines, 0, 40.0, width), vec!["Sta…"]);
+    }
+}
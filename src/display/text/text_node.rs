@@ -1,6 +1,6 @@
 use web_sys::HtmlElement;
 
-use crate::{JsError, PaddleResult};
+use crate::{AssetLibrary, JsError, PaddleResult, RichText};
 
 #[derive(Debug)]
 pub struct TextNode {
@@ -8,6 +8,8 @@ pub struct TextNode {
     text: String,
     dirty: bool,
     z: i16,
+    /// Set while the node shows styled spans instead of plain text
+    rich: Option<RichText>,
 }
 
 impl TextNode {
@@ -17,24 +19,62 @@ impl TextNode {
             dom_node,
             dirty: true,
             z: 0,
+            rich: None,
         }
     }
     /// Update the inner text (without redrawing it)
     /// Performs string comparison and also a string copy when necessary
     pub fn update(&mut self, text: &str) {
-        if self.dirty || text != self.text {
+        if self.dirty || self.rich.is_some() || text != self.text {
             self.text.clear();
             self.text.push_str(text);
             self.dirty = true;
+            self.rich = None;
         }
     }
     /// Same as `update` but takes ownership of string and avoids copying the string content
     pub fn update_owned(&mut self, text: String) {
-        if self.dirty || text != self.text {
+        if self.dirty || self.rich.is_some() || text != self.text {
             self.text = text;
             self.dirty = true;
+            self.rich = None;
         }
     }
+    /// Replace the content with styled spans, immediately. Does nothing if the same text is already shown.
+    pub fn update_rich(&mut self, text: &RichText, assets: &AssetLibrary) -> PaddleResult<()> {
+        if self.rich.as_ref() != Some(text) {
+            text.render_dom(&self.dom_node, assets)?;
+            self.text = text.plain_text();
+            self.rich = Some(text.clone());
+            self.dirty = false;
+        }
+        Ok(())
+    }
+    /// The current text, without styles
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub(crate) fn element(&self) -> &HtmlElement {
+        &self.dom_node
+    }
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    pub(crate) fn is_rich(&self) -> bool {
+        self.rich.is_some()
+    }
+    pub(crate) fn rich(&self) -> Option<&RichText> {
+        self.rich.as_ref()
+    }
+    /// Draw the stored text again on the next draw
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+    /// Show a different string than the stored text, such as a shortened version of it
+    pub(crate) fn draw_as(&mut self, shown: &str) {
+        self.dom_node.set_text_content(Some(shown));
+        self.dirty = false;
+    }
     pub fn draw(&mut self) {
         if self.dirty {
             self.dom_node.set_text_content(Some(&self.text));
@@ -87,7 +87,7 @@ impl<STATE: 'static, F: Frame<State = STATE> + Activity> FrameHandle<F> {
                 let (global_state, ctx) = d.try_get_2_mut::<F::State, Context>();
                 let global_state: &mut F::State = global_state.expect("Activity State missing");
                 let display = ctx.expect("Context missing").display.full();
-                let projected_pos = msg.1 / display.browser_to_game_pixel_ratio();
+                let projected_pos = msg.1.times(display.browser_to_game_pixel_ratios().recip());
                 a.pointer(global_state, PointerEvent(msg.0, projected_pos))
             });
             // Clicks are available in all browsers and should be generated even from touchstart + touchend, as long as it is not cancelled explicitly.
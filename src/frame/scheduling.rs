@@ -67,5 +67,8 @@ impl FrameHelper {
         let ctx = domain.get_mut::<Context>();
         let canvas = ctx.canvas_mut();
         canvas.flush().nuts_check();
+        if let Some(stats) = ctx.display.full_mut().end_frame() {
+            nuts::publish(stats);
+        }
     }
 }
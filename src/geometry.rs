@@ -2,6 +2,7 @@ mod complex_shape;
 mod grid;
 mod rectangle;
 mod scalar;
+mod stroke;
 mod transform;
 mod vector;
 
@@ -9,6 +10,7 @@ pub use complex_shape::*;
 pub use grid::*;
 pub use rectangle::*;
 pub use scalar::*;
+pub use stroke::*;
 pub use transform::*;
 pub use vector::*;
 
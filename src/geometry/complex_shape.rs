@@ -1,4 +1,10 @@
-use crate::{AbstractMesh, Rectangle, Tessellate, Transform};
+use crate::{
+    quicksilver_compat::{geom::Triangle, Circle},
+    AbstractMesh, ErrorMessage, PaddleResult, Rectangle, StrokeOptions, Tessellate, Transform,
+    Vector,
+};
+use lyon::path::{Path, Winding};
+pub use lyon::tessellation::FillRule;
 
 /// A single mesh of triangles ready to be drawn
 pub struct ComplexShape {
@@ -48,6 +54,73 @@ impl ComplexShape {
         Self { mesh, bounding_box }
     }
 
+    /// Tessellate the area enclosed by a lyon path.
+    pub fn fill(path: &Path, fill_rule: FillRule) -> PaddleResult<Self> {
+        let mut mesh = AbstractMesh::new();
+        let mut shape = crate::ShapeRenderer::new(&mut mesh);
+        let options = lyon::lyon_tessellation::FillOptions::default().with_fill_rule(fill_rule);
+        lyon::lyon_tessellation::FillTessellator::new()
+            .tessellate_path(path, &options, &mut shape)
+            .map_err(|e| ErrorMessage::technical(format!("Fill tessellation failed: {:?}", e)))?;
+
+        let bounding_box = mesh
+            .bounding_box()
+            .ok_or_else(|| ErrorMessage::technical("Cannot fill a path without area".to_owned()))?;
+        mesh.normalize(&bounding_box);
+        Ok(Self { mesh, bounding_box })
+    }
+
+    /// Tessellate the outline of a lyon path.
+    ///
+    /// The bounding box covers the full stroke, including half of the line width on each side of the path.
+    pub fn stroke(path: &Path, options: &StrokeOptions) -> PaddleResult<Self> {
+        let path = options.apply_dashes(path);
+        let mut mesh = AbstractMesh::new();
+        let mut shape = crate::ShapeRenderer::new(&mut mesh);
+        lyon::lyon_tessellation::StrokeTessellator::new()
+            .tessellate_path(&path, &options.lyon_options(), &mut shape)
+            .map_err(|e| ErrorMessage::technical(format!("Stroke tessellation failed: {:?}", e)))?;
+
+        let bounding_box = mesh.bounding_box().ok_or_else(|| {
+            ErrorMessage::technical("Cannot stroke a path without area".to_owned())
+        })?;
+        mesh.normalize(&bounding_box);
+        Ok(Self { mesh, bounding_box })
+    }
+
+    /// Outline of a rectangle
+    pub fn stroke_rectangle(rect: &Rectangle, options: &StrokeOptions) -> PaddleResult<Self> {
+        let mut builder = Path::builder();
+        builder.add_rectangle(&(*rect).into(), Winding::Positive);
+        Self::stroke(&builder.build(), options)
+    }
+
+    /// Outline of a circle
+    pub fn stroke_circle(circle: &Circle, options: &StrokeOptions) -> PaddleResult<Self> {
+        let mut builder = Path::builder();
+        builder.add_circle(circle.pos.into(), circle.radius, Winding::Positive);
+        Self::stroke(&builder.build(), options)
+    }
+
+    /// Outline of a triangle
+    pub fn stroke_triangle(triangle: &Triangle, options: &StrokeOptions) -> PaddleResult<Self> {
+        Self::stroke_polyline(&[triangle.a, triangle.b, triangle.c], true, options)
+    }
+
+    /// Connected line segments through all points. With `closed`, the last point is also connected to the first.
+    pub fn stroke_polyline(
+        points: &[Vector],
+        closed: bool,
+        options: &StrokeOptions,
+    ) -> PaddleResult<Self> {
+        let mut builder = Path::builder();
+        builder.add_polygon(lyon::path::Polygon {
+            points: &points.iter().map(|p| (*p).into()).collect::<Vec<_>>(),
+            closed,
+        });
+        Self::stroke(&builder.build(), options)
+    }
+
     pub fn resize(&mut self, bounding_box: &Rectangle) {
         self.bounding_box = *bounding_box;
     }
@@ -88,6 +88,9 @@ impl Rectangle {
         self.fit_into_ex(frame, fit_strat, true)
     }
     /// Shrinks and moves the rectangle to fit within the given frame, without changing proportions
+    ///
+    /// `FitStrategy::Stretch` and `FitStrategy::Cover` are exceptions, which change proportions or overflow the frame respectively.
+    /// `FitStrategy::Contain` and `FitStrategy::Cover` always grow when necessary, regardless of `allow_grow`.
     #[must_use]
     pub fn fit_into_ex(
         mut self,
@@ -95,37 +98,30 @@ impl Rectangle {
         fit_strat: FitStrategy,
         allow_grow: bool,
     ) -> Rectangle {
-        let stretch_factor = (frame.width() / self.width()).min(frame.height() / self.height());
-        if allow_grow || stretch_factor < 1.0 {
-            self.size *= stretch_factor;
-        }
+        let x_factor = frame.width() / self.width();
+        let y_factor = frame.height() / self.height();
         match fit_strat {
-            FitStrategy::TopLeft => self.pos = frame.pos,
-            FitStrategy::LeftCenter => {
-                self.pos = frame.pos + ((frame.size - self.size).y_comp() / 2.0);
-            }
-            FitStrategy::Center => {
-                self.pos = frame.pos + ((frame.size - self.size) / 2.0);
+            FitStrategy::Stretch => return *frame,
+            FitStrategy::Cover => self.size *= x_factor.max(y_factor),
+            FitStrategy::Contain => self.size *= x_factor.min(y_factor),
+            _ => {
+                let stretch_factor = x_factor.min(y_factor);
+                if allow_grow || stretch_factor < 1.0 {
+                    self.size *= stretch_factor;
+                }
             }
         }
+        self.pos = frame.pos + (frame.size - self.size).times(fit_strat.alignment());
         self
     }
     /// Finds the largest square that fits into the given rectangle
+    ///
+    /// Strategies that do not define an anchor (`Stretch`, `Contain`, `Cover`) place the square in the center.
     #[must_use]
     pub fn fit_square(&self, fit_strat: FitStrategy) -> Rectangle {
         let s = self.width().min(self.height());
-        let mut rect = Rectangle::new(self.pos, (s, s));
-        match fit_strat {
-            FitStrategy::Center => {
-                rect = rect.translate(((self.width() - rect.width()) / 2.0, 0.0));
-                rect = rect.translate((0.0, (self.height() - rect.height()) / 2.0));
-            }
-            FitStrategy::LeftCenter => {
-                rect = rect.translate((0.0, (self.height() - rect.height()) / 2.0));
-            }
-            FitStrategy::TopLeft => {}
-        }
-        rect
+        let rect = Rectangle::new(self.pos, (s, s));
+        rect.translate((self.size - rect.size).times(fit_strat.alignment()))
     }
 
     pub fn grid(&self, cols: usize, rows: usize) -> Grid {
@@ -203,7 +199,7 @@ impl Into<lyon::math::Box2D> for Rectangle {
 
 #[cfg(test)]
 mod tests {
-    use crate::{quicksilver_compat::*, Rectangle, Vector};
+    use crate::{quicksilver_compat::*, FitStrategy, Rectangle, Vector};
 
     #[test]
     fn overlap() {
@@ -241,6 +237,58 @@ mod tests {
         let translated = a.translate(v);
         assert_eq!(a.top_left() + v, translated.top_left());
     }
+    #[test]
+    fn fit_into_anchors() {
+        let frame = Rectangle::new((10, 10), (100, 50));
+        let rect = Rectangle::new_sized((20, 20));
+        let fit = |strat| rect.fit_into(&frame, strat);
+        assert_eq!(
+            fit(FitStrategy::TopLeft),
+            Rectangle::new((10, 10), (50, 50))
+        );
+        assert_eq!(
+            fit(FitStrategy::TopCenter),
+            Rectangle::new((35, 10), (50, 50))
+        );
+        assert_eq!(
+            fit(FitStrategy::TopRight),
+            Rectangle::new((60, 10), (50, 50))
+        );
+        assert_eq!(fit(FitStrategy::Center), Rectangle::new((35, 10), (50, 50)));
+        assert_eq!(
+            fit(FitStrategy::BottomRight),
+            Rectangle::new((60, 10), (50, 50))
+        );
+
+        let small = rect.shrink_and_fit_into(&frame, FitStrategy::BottomRight);
+        assert_eq!(small, Rectangle::new((90, 40), (20, 20)));
+        let small = rect.shrink_and_fit_into(&frame, FitStrategy::RightCenter);
+        assert_eq!(small, Rectangle::new((90, 25), (20, 20)));
+        let small = rect.shrink_and_fit_into(&frame, FitStrategy::BottomCenter);
+        assert_eq!(small, Rectangle::new((50, 40), (20, 20)));
+    }
+
+    #[test]
+    fn fit_into_object_fit() {
+        let frame = Rectangle::new((10, 10), (100, 50));
+        let rect = Rectangle::new_sized((20, 20));
+        let stretched = rect.shrink_and_fit_into(&frame, FitStrategy::Stretch);
+        assert_eq!(stretched, frame);
+        let contained = rect.shrink_and_fit_into(&frame, FitStrategy::Contain);
+        assert_eq!(contained, Rectangle::new((35, 10), (50, 50)));
+        let covered = rect.shrink_and_fit_into(&frame, FitStrategy::Cover);
+        assert_eq!(covered, Rectangle::new((10, -15), (100, 100)));
+    }
+
+    #[test]
+    fn fit_square() {
+        let rect = Rectangle::new((0, 0), (100, 50));
+        let square = rect.fit_square(FitStrategy::TopRight);
+        assert_eq!(square, Rectangle::new((50, 0), (50, 50)));
+        let square = rect.fit_square(FitStrategy::Cover);
+        assert_eq!(square, Rectangle::new((25, 0), (50, 50)));
+    }
+
     #[test]
     fn project() {
         let a = Rectangle::new((10, 10), (5, 5));
@@ -0,0 +1,244 @@
+use crate::Vector;
+use lyon::path::{iterator::PathIterator, Path, PathEvent};
+pub use lyon::tessellation::{LineCap, LineJoin};
+
+/// Parameters for drawing the outline of a shape or path, used by `ComplexShape::stroke` and related constructors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeOptions {
+    pub(crate) width: f32,
+    pub(crate) line_join: LineJoin,
+    pub(crate) start_cap: LineCap,
+    pub(crate) end_cap: LineCap,
+    pub(crate) miter_limit: f32,
+    pub(crate) tolerance: f32,
+    /// Alternating lengths of visible and invisible segments. Empty for a solid line.
+    pub(crate) dashes: Vec<f32>,
+    pub(crate) dash_offset: f32,
+}
+
+impl StrokeOptions {
+    /// A solid line with the given width, miter joins and butt caps
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            line_join: LineJoin::Miter,
+            start_cap: LineCap::Butt,
+            end_cap: LineCap::Butt,
+            miter_limit: lyon::tessellation::StrokeOptions::DEFAULT_MITER_LIMIT,
+            tolerance: lyon::tessellation::StrokeOptions::DEFAULT_TOLERANCE,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+    #[inline(always)]
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+    #[inline(always)]
+    pub fn with_line_join(mut self, join: LineJoin) -> Self {
+        self.line_join = join;
+        self
+    }
+    /// Set the caps on both ends of the line
+    #[inline(always)]
+    pub fn with_line_cap(mut self, cap: LineCap) -> Self {
+        self.start_cap = cap;
+        self.end_cap = cap;
+        self
+    }
+    #[inline(always)]
+    pub fn with_start_cap(mut self, cap: LineCap) -> Self {
+        self.start_cap = cap;
+        self
+    }
+    #[inline(always)]
+    pub fn with_end_cap(mut self, cap: LineCap) -> Self {
+        self.end_cap = cap;
+        self
+    }
+    #[inline(always)]
+    pub fn with_miter_limit(mut self, limit: f32) -> Self {
+        self.miter_limit = limit;
+        self
+    }
+    /// Maximum distance between curves and their approximation by line segments
+    #[inline(always)]
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+    /// Draw a dashed line instead of a solid one.
+    ///
+    /// The pattern alternates between visible and invisible lengths, starting with a visible dash.
+    /// Like in SVG, a pattern with an odd number of entries is repeated to make it even.
+    /// The offset defines how far into the pattern each sub-path starts.
+    pub fn with_dashes(mut self, pattern: &[f32], offset: f32) -> Self {
+        self.dashes = pattern.to_vec();
+        if self.dashes.len() % 2 == 1 {
+            self.dashes.extend_from_slice(pattern);
+        }
+        self.dash_offset = offset;
+        self
+    }
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub(crate) fn lyon_options(&self) -> lyon::tessellation::StrokeOptions {
+        lyon::tessellation::StrokeOptions::default()
+            .with_line_width(self.width)
+            .with_line_join(self.line_join)
+            .with_start_cap(self.start_cap)
+            .with_end_cap(self.end_cap)
+            .with_miter_limit(self.miter_limit)
+            .with_tolerance(self.tolerance)
+    }
+
+    fn is_dashed(&self) -> bool {
+        !self.dashes.is_empty()
+            && self.dashes.iter().all(|d| *d >= 0.0)
+            && self.dashes.iter().sum::<f32>() > 0.0
+    }
+
+    /// Splits the path into one open sub-path per visible dash. Without a (valid) dash pattern, the path is returned unchanged.
+    pub(crate) fn apply_dashes(&self, path: &Path) -> Path {
+        if !self.is_dashed() {
+            return path.clone();
+        }
+        let mut dasher = Dasher::new(&self.dashes, self.dash_offset);
+        for event in path.iter().flattened(self.tolerance) {
+            match event {
+                PathEvent::Begin { .. } => dasher.restart(),
+                PathEvent::Line { from, to } => dasher.segment(from.into(), to.into()),
+                PathEvent::End { last, first, close } => {
+                    if close {
+                        dasher.segment(last.into(), first.into());
+                    }
+                    dasher.finish_dash();
+                }
+                _ => {}
+            }
+        }
+        dasher.builder.build()
+    }
+}
+
+/// Walks along line segments and emits the visible parts of a dash pattern as sub-paths
+struct Dasher<'a> {
+    pattern: &'a [f32],
+    offset: f32,
+    builder: lyon::path::path::Builder,
+    index: usize,
+    remaining: f32,
+    drawing: bool,
+}
+
+impl<'a> Dasher<'a> {
+    fn new(pattern: &'a [f32], offset: f32) -> Self {
+        let mut dasher = Self {
+            pattern,
+            offset,
+            builder: Path::builder(),
+            index: 0,
+            remaining: 0.0,
+            drawing: false,
+        };
+        dasher.restart();
+        dasher
+    }
+    /// Start at the beginning of the pattern, advanced by the offset
+    fn restart(&mut self) {
+        self.finish_dash();
+        let total: f32 = self.pattern.iter().sum();
+        let mut offset = self.offset.rem_euclid(total);
+        self.index = 0;
+        while offset >= self.pattern[self.index] {
+            offset -= self.pattern[self.index];
+            self.index = (self.index + 1) % self.pattern.len();
+        }
+        self.remaining = self.pattern[self.index] - offset;
+    }
+    fn visible(&self) -> bool {
+        self.index % 2 == 0
+    }
+    fn segment(&mut self, from: Vector, to: Vector) {
+        let length = from.distance(to);
+        if length <= 0.0 {
+            return;
+        }
+        let dir = (to - from) / length;
+        let mut pos = 0.0;
+        while pos < length {
+            let step = self.remaining.min(length - pos);
+            if self.visible() {
+                if !self.drawing {
+                    self.builder.begin((from + dir * pos).into());
+                    self.drawing = true;
+                }
+                self.builder.line_to((from + dir * (pos + step)).into());
+            }
+            pos += step;
+            self.remaining -= step;
+            if self.remaining <= 0.0 {
+                self.finish_dash();
+                self.index = (self.index + 1) % self.pattern.len();
+                self.remaining = self.pattern[self.index];
+            }
+        }
+    }
+    fn finish_dash(&mut self) {
+        if self.drawing {
+            self.builder.end(false);
+            self.drawing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(length: f32) -> Path {
+        let mut builder = Path::builder();
+        builder.begin(lyon::math::point(0.0, 0.0));
+        builder.line_to(lyon::math::point(length, 0.0));
+        builder.end(false);
+        builder.build()
+    }
+
+    fn dashes(path: &Path) -> Vec<(f32, f32)> {
+        path.iter()
+            .filter_map(|e| match e {
+                PathEvent::End { last, first, .. } => Some((first.x, last.x)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn solid_line_unchanged() {
+        let path = line(10.0);
+        let dashed = StrokeOptions::new(1.0).apply_dashes(&path);
+        assert_eq!(dashes(&dashed), vec![(0.0, 10.0)]);
+    }
+
+    #[test]
+    fn dash_pattern() {
+        let path = line(10.0);
+        let dashed = StrokeOptions::new(1.0)
+            .with_dashes(&[3.0, 1.0], 0.0)
+            .apply_dashes(&path);
+        assert_eq!(dashes(&dashed), vec![(0.0, 3.0), (4.0, 7.0), (8.0, 10.0)]);
+    }
+
+    #[test]
+    fn dash_offset_and_odd_pattern() {
+        let path = line(6.0);
+        // [2] is treated as [2, 2]
+        let dashed = StrokeOptions::new(1.0)
+            .with_dashes(&[2.0], 1.0)
+            .apply_dashes(&path);
+        assert_eq!(dashes(&dashed), vec![(0.0, 1.0), (3.0, 5.0)]);
+    }
+}
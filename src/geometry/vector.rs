@@ -251,6 +251,12 @@ impl Into<lyon::math::Point> for Vector {
     }
 }
 
+impl From<lyon::math::Point> for Vector {
+    fn from(p: lyon::math::Point) -> Vector {
+        Vector::new(p.x, p.y)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::quicksilver_compat::about_equal;
@@ -1,10 +1,24 @@
 //! Parent module for stuff related to graphics manipulation, such as textures and tessellation.
 //! (exclusive display + GPU)
+mod animation;
+mod bitmap_font;
+mod gradient;
+mod nine_slice;
 mod paint;
+mod particles;
+mod sdf_text;
 mod tessellation;
 mod texture;
+mod tilemap;
 
+pub use animation::*;
+pub use bitmap_font::*;
+pub use gradient::*;
+pub use nine_slice::{NineSlice, NineSliceMode};
 pub use paint::*;
+pub use particles::*;
+pub use sdf_text::*;
 pub use tessellation::*;
 pub use texture::TextureConfig;
 pub use texture::*;
+pub use tilemap::*;
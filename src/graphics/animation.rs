@@ -0,0 +1,258 @@
+use crate::{Image, Rectangle};
+
+/// Frames cut out of a sprite sheet, grouped into named clips.
+///
+/// Store it in the asset library with `AnimationDesc::define` and draw it with the descriptor as paint.
+#[derive(Clone, Debug)]
+pub struct SpriteAnimation {
+    frames: Vec<Image>,
+    /// In definition order, the first clip is played if none is selected
+    clips: Vec<(&'static str, AnimationClip)>,
+}
+
+/// A sequence of frames of a `SpriteAnimation`, each shown for its own duration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    /// Index into the frames of the animation and duration in ms
+    frames: Vec<(usize, f64)>,
+    mode: AnimationMode,
+}
+
+/// Defines what happens when the last frame of an `AnimationClip` has been shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AnimationMode {
+    /// Start again at the first frame. (default)
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forward again, and so on.
+    PingPong,
+    /// Stay on the last frame.
+    Once,
+}
+
+/// Explicit playback state of an animated object, which clip it plays and since when.
+///
+/// Combine it with an `AnimationDesc` for drawing, using `AnimationDesc::with_state`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationState {
+    clip: Option<&'static str>,
+    start_ms: f64,
+    speed: f64,
+}
+
+impl AnimationState {
+    /// Plays the default clip, starting at time 0
+    pub fn new() -> Self {
+        Self {
+            clip: None,
+            start_ms: 0.0,
+            speed: 1.0,
+        }
+    }
+    /// Start playing the named clip. If the clip is already playing, it continues without a restart.
+    pub fn play(&mut self, clip: &'static str, now_ms: f64) {
+        if self.clip != Some(clip) {
+            self.clip = Some(clip);
+            self.restart(now_ms);
+        }
+    }
+    /// Play the current clip from the beginning
+    pub fn restart(&mut self, now_ms: f64) {
+        self.start_ms = now_ms;
+    }
+    /// Play faster (> 1.0) or slower (< 1.0) than defined in the clip. The speed must be positive.
+    pub fn set_speed(&mut self, speed: f64, now_ms: f64) {
+        debug_assert!(speed > 0.0);
+        // Keep the current position in the clip
+        let elapsed = self.elapsed_ms(now_ms);
+        self.speed = speed;
+        self.start_ms = now_ms - elapsed / speed;
+    }
+    pub fn clip(&self) -> Option<&'static str> {
+        self.clip
+    }
+    /// Playback time of the current clip, considering the speed
+    pub fn elapsed_ms(&self, now_ms: f64) -> f64 {
+        (now_ms - self.start_ms) * self.speed
+    }
+}
+
+impl Default for AnimationState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpriteAnimation {
+    /// Use the given regions of the sprite sheet as frames, in texel coordinates.
+    pub fn from_frames(sheet: &Image, frames: &[Rectangle]) -> Self {
+        Self {
+            frames: frames
+                .iter()
+                .map(|rect| sheet.subimage_texels(*rect))
+                .collect(),
+            clips: Vec::new(),
+        }
+    }
+    /// Split the sprite sheet into a grid of equally sized frames, numbered row by row.
+    pub fn from_grid(sheet: &Image, columns: usize, rows: usize) -> Self {
+        let w = sheet.natural_width() / columns as f32;
+        let h = sheet.natural_height() / rows as f32;
+        let frames: Vec<Rectangle> = (0..rows)
+            .flat_map(|row| {
+                (0..columns)
+                    .map(move |col| Rectangle::new((col as f32 * w, row as f32 * h), (w, h)))
+            })
+            .collect();
+        Self::from_frames(sheet, &frames)
+    }
+    /// Add a named clip. The first clip added is played when no clip is selected.
+    pub fn with_clip(mut self, name: &'static str, clip: AnimationClip) -> Self {
+        self.clips.retain(|(n, _)| *n != name);
+        self.clips.push((name, clip));
+        self
+    }
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.iter().find(|(n, _)| *n == name).map(|(_, c)| c)
+    }
+    /// The frame to show after playing the clip (or the first clip) for the given time.
+    ///
+    /// Without any clips, the first frame is shown.
+    pub fn frame(&self, clip: Option<&str>, elapsed_ms: f64) -> Option<&Image> {
+        let clip = match clip {
+            Some(name) => self.clip(name),
+            None => self.clips.first().map(|(_, c)| c),
+        };
+        let index = match clip {
+            Some(clip) => clip.frame_at(elapsed_ms)?,
+            None => 0,
+        };
+        self.frames.get(index)
+    }
+}
+
+impl AnimationClip {
+    /// Frames of the animation (by index) shown for the same duration each
+    pub fn new(frames: impl IntoIterator<Item = usize>, frame_duration_ms: f64) -> Self {
+        Self::with_durations(frames.into_iter().map(|i| (i, frame_duration_ms)))
+    }
+    /// Frames of the animation (by index) with individual durations in ms
+    pub fn with_durations(frames: impl IntoIterator<Item = (usize, f64)>) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+            mode: AnimationMode::default(),
+        }
+    }
+    #[inline(always)]
+    pub fn with_mode(mut self, mode: AnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    /// Time to play all frames once
+    pub fn duration_ms(&self) -> f64 {
+        self.frames.iter().map(|(_, d)| d).sum()
+    }
+    /// Only clips played once can finish
+    pub fn is_finished(&self, elapsed_ms: f64) -> bool {
+        self.mode == AnimationMode::Once && elapsed_ms >= self.duration_ms()
+    }
+    /// Index of the animation frame to show after playing the clip for the given time
+    pub fn frame_at(&self, elapsed_ms: f64) -> Option<usize> {
+        let (last, _) = *self.frames.last()?;
+        let duration = self.duration_ms();
+        if duration <= 0.0 {
+            return Some(self.frames[0].0);
+        }
+        let t = elapsed_ms.max(0.0);
+        match self.mode {
+            AnimationMode::Loop => locate(self.frames.iter(), t % duration),
+            AnimationMode::Once if t >= duration => Some(last),
+            AnimationMode::Once => locate(self.frames.iter(), t),
+            AnimationMode::PingPong => {
+                // The way back skips the last and the first frame, they are shown only once per cycle
+                let n = self.frames.len();
+                let inner = if n > 2 { &self.frames[1..n - 1] } else { &[] };
+                let back_duration: f64 = inner.iter().map(|(_, d)| d).sum();
+                let t = t % (duration + back_duration);
+                if t < duration {
+                    locate(self.frames.iter(), t)
+                } else {
+                    locate(inner.iter().rev(), t - duration)
+                }
+            }
+        }
+    }
+}
+
+fn locate<'a>(frames: impl Iterator<Item = &'a (usize, f64)>, mut t: f64) -> Option<usize> {
+    let mut result = None;
+    for (index, duration) in frames {
+        result = Some(*index);
+        if t < *duration {
+            break;
+        }
+        t -= duration;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(clip: &AnimationClip, times: &[f64]) -> Vec<usize> {
+        times.iter().map(|t| clip.frame_at(*t).unwrap()).collect()
+    }
+
+    #[test]
+    fn loop_clip() {
+        let clip = AnimationClip::new(3..6, 100.0);
+        assert_eq!(
+            frames(&clip, &[0.0, 99.0, 100.0, 250.0, 300.0, 420.0]),
+            vec![3, 3, 4, 5, 3, 4]
+        );
+        assert!(!clip.is_finished(1000.0));
+    }
+
+    #[test]
+    fn once_clip() {
+        let clip = AnimationClip::with_durations(vec![(0, 50.0), (1, 150.0)])
+            .with_mode(AnimationMode::Once);
+        assert_eq!(
+            frames(&clip, &[-10.0, 49.0, 50.0, 199.0, 5000.0]),
+            vec![0, 0, 1, 1, 1]
+        );
+        assert!(clip.is_finished(200.0));
+    }
+
+    #[test]
+    fn ping_pong_clip() {
+        let clip = AnimationClip::new(0..4, 10.0).with_mode(AnimationMode::PingPong);
+        let times: Vec<f64> = (0..8).map(|i| i as f64 * 10.0 + 5.0).collect();
+        assert_eq!(frames(&clip, &times), vec![0, 1, 2, 3, 2, 1, 0, 1]);
+
+        let two_frames = AnimationClip::new(0..2, 10.0).with_mode(AnimationMode::PingPong);
+        assert_eq!(frames(&two_frames, &[5.0, 15.0, 25.0]), vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn state_keeps_position_on_speed_change() {
+        let mut state = AnimationState::new();
+        state.play("walk", 1000.0);
+        state.play("walk", 1200.0);
+        assert_eq!(state.elapsed_ms(1500.0), 500.0);
+        state.set_speed(2.0, 1500.0);
+        assert_eq!(state.elapsed_ms(1500.0), 500.0);
+        assert_eq!(state.elapsed_ms(1600.0), 700.0);
+        state.play("jump", 1600.0);
+        assert_eq!(state.elapsed_ms(1600.0), 0.0);
+    }
+
+    #[test]
+    fn empty_clip() {
+        assert_eq!(AnimationClip::new(0..0, 10.0).frame_at(0.0), None);
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::display::wrap_ranges;
+use crate::quicksilver_compat::Color;
+use crate::{
+    BitmapFontDesc, DistanceField, FitStrategy, Image, ImageDesc, Paint, Rectangle, RichSpan,
+    RichText, SdfTextPaint, TextMetrics, Vector,
+};
+
+/// A font made of glyph images on one or more atlas pages, loaded from a BMFont (AngelCode) file.
+///
+/// All measures are in pixels of the atlas, which are scaled to the requested text size for drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapFont {
+    pub(crate) line_height: f32,
+    pub(crate) base: f32,
+    pub(crate) pages: Vec<ImageDesc>,
+    pub(crate) glyphs: HashMap<char, Glyph>,
+    pub(crate) kerning: HashMap<(char, char), f32>,
+    /// Set for atlases of distance fields, which are drawn with the SDF text pipeline
+    pub(crate) distance_field: Option<DistanceField>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Glyph {
+    /// Area on the atlas page, in texels
+    pub region: Rectangle,
+    /// Offset from the pen position to the top left of the glyph
+    pub offset: Vector,
+    pub advance: f32,
+    pub page: usize,
+}
+
+/// Element of a text to lay out, referring to the span of a `RichText` it belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TextItem {
+    Char { c: char, span: usize },
+    Icon { width: f32, span: usize },
+}
+
+/// A glyph positioned in a text block, relative to the top left of the block
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PlacedGlyph {
+    pub area: Rectangle,
+    pub region: Rectangle,
+    pub page: usize,
+    pub span: usize,
+}
+
+pub(crate) struct TextLayout {
+    pub glyphs: Vec<PlacedGlyph>,
+    /// Area of each inline icon and the span it belongs to
+    pub icons: Vec<(Rectangle, usize)>,
+    pub size: Vector,
+}
+
+/// A text to be drawn with a `BitmapFont`, using `DisplayArea::draw_text`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapText {
+    pub(crate) font: BitmapFontDesc,
+    pub(crate) text: RichText,
+    /// Line height in display coordinates, the native size of the font if unset
+    pub(crate) size: Option<f32>,
+    pub(crate) color: Color,
+    pub(crate) fit: FitStrategy,
+    pub(crate) wrap: bool,
+    pub(crate) sdf: Option<SdfTextPaint>,
+}
+
+impl BitmapFont {
+    /// Distance between two lines
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+    /// Distance from the top of a line to the baseline
+    pub fn base(&self) -> f32 {
+        self.base
+    }
+    /// The encoding of distance field fonts, as declared in the font file
+    pub fn distance_field(&self) -> Option<DistanceField> {
+        self.distance_field
+    }
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+    /// Split the text into lines, at line breaks and wherever the next word would exceed the maximum width.
+    ///
+    /// Words wider than the maximum width are put on a line of their own, without breaking them.
+    pub fn wrap_lines(&self, text: &str, max_width: Option<f32>) -> Vec<String> {
+        let items = plain_items(text);
+        self.wrap_items(&items, max_width)
+            .into_iter()
+            .map(|line| {
+                items[line]
+                    .iter()
+                    .filter_map(|item| match item {
+                        TextItem::Char { c, .. } => Some(*c),
+                        TextItem::Icon { .. } => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+    /// Width of a single line of text, including kerning
+    pub fn line_width(&self, line: &str) -> f32 {
+        self.items_width(&plain_items(line))
+    }
+    /// Size of the text block after wrapping it to the maximum width
+    pub fn text_size(&self, text: &str, max_width: Option<f32>) -> Vector {
+        let lines = self.wrap_lines(text, max_width);
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line))
+            .fold(0.0, f32::max);
+        Vector::new(width, lines.len() as f32 * self.line_height)
+    }
+    /// Line breaks and size of the text block after wrapping it to the maximum width
+    pub fn metrics(&self, text: &str, max_width: Option<f32>) -> TextMetrics {
+        let lines = self.wrap_lines(text, max_width);
+        TextMetrics::from_lines(lines, self.line_height, |line| self.line_width(line))
+    }
+    /// Position all glyphs and icons, aligning each line horizontally within the block (0.0 = left, 1.0 = right).
+    pub(crate) fn layout(
+        &self,
+        items: &[TextItem],
+        max_width: Option<f32>,
+        align: f32,
+    ) -> TextLayout {
+        let lines = self.wrap_items(items, max_width);
+        let widths: Vec<f32> = lines
+            .iter()
+            .map(|line| self.items_width(&items[line.clone()]))
+            .collect();
+        let block_width = widths.iter().cloned().fold(0.0, f32::max);
+        let mut layout = TextLayout {
+            glyphs: Vec::new(),
+            icons: Vec::new(),
+            size: Vector::new(block_width, lines.len() as f32 * self.line_height),
+        };
+        for (row, (line, width)) in lines.into_iter().zip(&widths).enumerate() {
+            let mut pen = Vector::new((block_width - width) * align, row as f32 * self.line_height);
+            let mut previous = None;
+            for item in &items[line] {
+                match *item {
+                    TextItem::Char { c, span } => {
+                        if let Some(glyph) = self.glyphs.get(&c) {
+                            pen.x += self.kerning_between(previous, c);
+                            if glyph.region.width() > 0.0 && glyph.region.height() > 0.0 {
+                                layout.glyphs.push(PlacedGlyph {
+                                    area: Rectangle::new(pen + glyph.offset, glyph.region.size),
+                                    region: glyph.region,
+                                    page: glyph.page,
+                                    span,
+                                });
+                            }
+                            pen.x += glyph.advance;
+                        }
+                        previous = Some(c);
+                    }
+                    TextItem::Icon { span, width } => {
+                        layout
+                            .icons
+                            .push((Rectangle::new(pen, (width, self.line_height)), span));
+                        pen.x += width;
+                        previous = None;
+                    }
+                }
+            }
+        }
+        layout
+    }
+    /// Index ranges of the items on each line, without the spaces where lines were wrapped
+    fn wrap_items(&self, items: &[TextItem], max_width: Option<f32>) -> Vec<Range<usize>> {
+        let as_char = |item: &TextItem| match *item {
+            TextItem::Char { c, .. } => Some(c),
+            TextItem::Icon { .. } => None,
+        };
+        wrap_ranges(items, as_char, max_width, |line| self.items_width(line))
+    }
+    fn items_width(&self, items: &[TextItem]) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for item in items {
+            match *item {
+                TextItem::Char { c, .. } => {
+                    if let Some(glyph) = self.glyphs.get(&c) {
+                        width += self.kerning_between(previous, c) + glyph.advance;
+                    }
+                    previous = Some(c);
+                }
+                TextItem::Icon { width: w, .. } => {
+                    width += w;
+                    previous = None;
+                }
+            }
+        }
+        width
+    }
+    fn kerning_between(&self, previous: Option<char>, c: char) -> f32 {
+        previous
+            .and_then(|p| self.kerning.get(&(p, c)))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+}
+
+fn plain_items(text: &str) -> Vec<TextItem> {
+    text.chars()
+        .map(|c| TextItem::Char { c, span: 0 })
+        .collect()
+}
+
+/// Items of all spans, icons get the width for a line height of 1.0 returned by `icon_aspect`, or are left out
+pub(crate) fn rich_items(
+    text: &RichText,
+    line_height: f32,
+    icon_aspect: impl Fn(&str) -> Option<f32>,
+) -> Vec<TextItem> {
+    let mut items = Vec::new();
+    for (span, content) in text.spans().iter().enumerate() {
+        match content {
+            RichSpan::Text { text, .. } => {
+                items.extend(text.chars().map(|c| TextItem::Char { c, span }))
+            }
+            RichSpan::Icon(name) => {
+                if let Some(aspect) = icon_aspect(name) {
+                    items.push(TextItem::Icon {
+                        width: aspect * line_height,
+                        span,
+                    });
+                }
+            }
+        }
+    }
+    items
+}
+
+impl BitmapText {
+    /// Text with styled spans and inline icons, see `RichText` for the markup.
+    ///
+    /// Span colors and icons are drawn, bold spans are emphasized by drawing their glyphs twice with a small offset.
+    /// Italic and underlined spans look like normal text, since bitmap fonts have no variants for them.
+    pub fn rich(font: BitmapFontDesc, text: RichText) -> Self {
+        Self {
+            text,
+            ..Self::new(font, "")
+        }
+    }
+    /// White text at the native size of the font, wrapped at the border of the target area and placed at its top left
+    pub fn new(font: BitmapFontDesc, text: impl Into<String>) -> Self {
+        Self {
+            font,
+            text: RichText::plain(text),
+            size: None,
+            color: Color::WHITE,
+            fit: FitStrategy::TopLeft,
+            wrap: true,
+            sdf: None,
+        }
+    }
+    /// Line height in display coordinates
+    #[inline(always)]
+    pub fn with_size(mut self, line_height: f32) -> Self {
+        self.size = Some(line_height);
+        self
+    }
+    #[inline(always)]
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+    /// Placement of the text block inside the target area. The horizontal alignment also applies to each line.
+    ///
+    /// Anchored strategies shrink text that does not fit, `Contain`, `Cover` and `Stretch` scale it like any other object.
+    #[inline(always)]
+    pub fn with_fit(mut self, fit: FitStrategy) -> Self {
+        self.fit = fit;
+        self
+    }
+    /// Break lines that would exceed the width of the target area (default: true)
+    #[inline(always)]
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+    /// Effects for distance field fonts, such as outlines and shadows
+    #[inline(always)]
+    pub fn with_sdf_paint(mut self, paint: SdfTextPaint) -> Self {
+        self.sdf = Some(paint);
+        self
+    }
+    /// The text without styles
+    pub fn text(&self) -> String {
+        self.text.plain_text()
+    }
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = RichText::plain(text);
+    }
+    pub fn set_rich_text(&mut self, text: RichText) {
+        self.text = text;
+    }
+}
+
+/// Paint of a single glyph, all glyphs on the same page are drawn in one batch.
+pub(crate) struct GlyphPaint {
+    pub image: Image,
+    pub color: Color,
+}
+
+impl Paint for GlyphPaint {
+    fn paint_image(&self) -> Option<&Image> {
+        Some(&self.image)
+    }
+    fn paint_color(&self) -> Option<Color> {
+        Some(self.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Monospace font with glyphs 10 wide and 20 high, "A" and "V" are kerned
+    fn font() -> BitmapFont {
+        let glyph = |i: usize, width: f32| Glyph {
+            region: Rectangle::new((i as f32 * 10.0, 0), (width, 20)),
+            offset: Vector::new(0, 2),
+            advance: 10.0,
+            page: 0,
+        };
+        let glyphs = "ABCV "
+            .chars()
+            .enumerate()
+            .map(|(i, c)| (c, glyph(i, if c == ' ' { 0.0 } else { 10.0 })))
+            .collect();
+        BitmapFont {
+            line_height: 24.0,
+            base: 18.0,
+            pages: vec![ImageDesc::from_path("font.png")],
+            glyphs,
+            kerning: vec![(('A', 'V'), -2.0)].into_iter().collect(),
+            distance_field: None,
+        }
+    }
+
+    #[test]
+    fn measure_with_kerning() {
+        let font = font();
+        assert_eq!(font.line_width("ABC"), 30.0);
+        assert_eq!(font.line_width("AVA"), 28.0);
+        // Unknown characters are skipped
+        assert_eq!(font.line_width("A?B"), 20.0);
+        assert_eq!(font.text_size("AB\nC", None), Vector::new(20.0, 48.0));
+        let metrics = font.metrics("AB CA", Some(30.0));
+        assert_eq!(metrics.lines, vec!["AB", "CA"]);
+        assert_eq!(metrics.line_widths, vec![20.0, 20.0]);
+        assert_eq!(metrics.size, font.text_size("AB CA", Some(30.0)));
+    }
+
+    #[test]
+    fn layout_aligns_lines() {
+        let font = font();
+        let layout = font.layout(&plain_items("AB C\nC"), Some(30.0), 1.0);
+        let glyphs = layout.glyphs;
+        assert_eq!(layout.size, Vector::new(20.0, 72.0));
+        // Spaces have no quad
+        assert_eq!(glyphs.len(), 4);
+        assert_eq!(glyphs[0].area, Rectangle::new((0, 2), (10, 20)));
+        assert_eq!(glyphs[2].area, Rectangle::new((10, 26), (10, 20)));
+        assert_eq!(glyphs[3].area, Rectangle::new((10, 50), (10, 20)));
+        assert_eq!(glyphs[3].region, Rectangle::new((20, 0), (10, 20)));
+    }
+
+    #[test]
+    fn rich_layout_with_icons() {
+        let font = font();
+        let text = RichText::parse("A[color=red]B[/color][img=coin] [img=missing]C");
+        let items = rich_items(&text, font.line_height, |name| {
+            if name == "coin" {
+                Some(0.5)
+            } else {
+                None
+            }
+        });
+        let layout = font.layout(&items, Some(40.0), 0.0);
+        let spans: Vec<usize> = layout.glyphs.iter().map(|g| g.span).collect();
+        assert_eq!(spans, vec![0, 1, 5]);
+        // The icon is 12 wide and stays on the line of the word it is attached to
+        assert_eq!(layout.icons, vec![(Rectangle::new((20, 0), (12, 24)), 2)]);
+        assert_eq!(layout.glyphs[2].area.pos, Vector::new(0, 26));
+        assert_eq!(layout.size, Vector::new(32.0, 48.0));
+    }
+}
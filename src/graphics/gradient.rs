@@ -0,0 +1,197 @@
+use crate::quicksilver_compat::Color;
+use crate::{
+    ErrorMessage, NutsCheck, Paint, RenderPipelineHandle, UniformDescriptor, UniformList,
+    UniformValue, Vector,
+};
+
+/// Maximum number of color stops in a single gradient, limited by the uniform arrays of the gradient shader
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+const STOP_COLOR_NAMES: [&str; MAX_GRADIENT_STOPS] = [
+    "StopColors[0]",
+    "StopColors[1]",
+    "StopColors[2]",
+    "StopColors[3]",
+    "StopColors[4]",
+    "StopColors[5]",
+    "StopColors[6]",
+    "StopColors[7]",
+];
+const STOP_OFFSET_NAMES: [&str; MAX_GRADIENT_STOPS] = [
+    "StopOffsets[0]",
+    "StopOffsets[1]",
+    "StopOffsets[2]",
+    "StopOffsets[3]",
+    "StopOffsets[4]",
+    "StopOffsets[5]",
+    "StopOffsets[6]",
+    "StopOffsets[7]",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GradientKind {
+    Linear { start: Vector, end: Vector },
+    Radial { center: Vector, radius: Vector },
+}
+
+/// A paint that fills shapes with smoothly changing colors, computed by a built-in gradient shader.
+///
+/// Coordinates are relative to the bounding box of the drawn shape: (0,0) is the top-left and (1,1) the bottom-right corner.
+/// This way, the same gradient can be used on any `DisplayTessellate` shape, at any size.
+///
+/// Each distinct gradient drawn requires a separate draw call.
+///
+/// # Example
+/// ```
+/// # use paddle::{Gradient, quicksilver_compat::Color};
+/// let sunset = Gradient::vertical(Color::new(1.0, 0.5, 0.0), Color::new(0.2, 0.0, 0.4))
+///     .with_stop(0.5, Color::new(0.9, 0.2, 0.3));
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct Gradient {
+    kind: GradientKind,
+    stops: Vec<(f32, Color)>,
+    uniforms: UniformList,
+}
+
+impl Gradient {
+    /// A gradient along the line from `start` to `end`, with colors constant on lines perpendicular to it.
+    ///
+    /// Add colors with `with_stop`, where offset 0.0 is at `start` and 1.0 at `end`.
+    pub fn linear(start: impl Into<Vector>, end: impl Into<Vector>) -> Self {
+        Self::new(GradientKind::Linear {
+            start: start.into(),
+            end: end.into(),
+        })
+    }
+    /// A gradient of circles around `center`.
+    ///
+    /// Add colors with `with_stop`, where offset 0.0 is at the center and 1.0 at the given radius.
+    /// Since the radius is relative to the bounding box, the circles are stretched to ellipses on non-square shapes.
+    pub fn radial(center: impl Into<Vector>, radius: f32) -> Self {
+        Self::elliptical(center, (radius, radius))
+    }
+    /// A radial gradient with different radii along the x and y axes.
+    pub fn elliptical(center: impl Into<Vector>, radius: impl Into<Vector>) -> Self {
+        Self::new(GradientKind::Radial {
+            center: center.into(),
+            radius: radius.into(),
+        })
+    }
+    /// A linear gradient from the left to the right side of the shape
+    pub fn horizontal(left: Color, right: Color) -> Self {
+        Self::linear((0, 0), (1, 0))
+            .with_stop(0.0, left)
+            .with_stop(1.0, right)
+    }
+    /// A linear gradient from the top to the bottom side of the shape
+    pub fn vertical(top: Color, bottom: Color) -> Self {
+        Self::linear((0, 0), (0, 1))
+            .with_stop(0.0, top)
+            .with_stop(1.0, bottom)
+    }
+    fn new(kind: GradientKind) -> Self {
+        let mut gradient = Self {
+            kind,
+            stops: Vec::new(),
+            uniforms: UniformList::default(),
+        };
+        gradient.update_uniforms();
+        gradient
+    }
+    /// Add a color at the given offset along the gradient, usually between 0.0 and 1.0.
+    ///
+    /// Stops are kept sorted by offset.
+    ///
+    /// The shader supports at most `MAX_GRADIENT_STOPS` stops. Further stops are dropped and reported as error.
+    pub fn with_stop(mut self, offset: f32, color: Color) -> Self {
+        if self.stops.len() >= MAX_GRADIENT_STOPS {
+            Err::<(), _>(ErrorMessage::technical(format!(
+                "A gradient supports at most {} stops, the stop at offset {} is dropped",
+                MAX_GRADIENT_STOPS, offset
+            )))
+            .nuts_check();
+            return self;
+        }
+        let index = self.stops.partition_point(|(o, _)| *o <= offset);
+        self.stops.insert(index, (offset, color));
+        self.update_uniforms();
+        self
+    }
+    /// The color stops of the gradient, sorted by offset
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    fn update_uniforms(&mut self) {
+        let (kind, start, end) = match self.kind {
+            GradientKind::Linear { start, end } => (0.0, start, end),
+            GradientKind::Radial { center, radius } => (1.0, center, radius),
+        };
+        let mut uniforms = vec![
+            UniformDescriptor::new("GradientKind", UniformValue::F32(kind)),
+            UniformDescriptor::new("GradientStart", UniformValue::Vec2F32(start.x, start.y)),
+            UniformDescriptor::new("GradientEnd", UniformValue::Vec2F32(end.x, end.y)),
+            UniformDescriptor::new("StopCount", UniformValue::F32(self.stops.len() as f32)),
+        ];
+        if self.stops.is_empty() {
+            // Without stops, the first color is used as is
+            uniforms.push(UniformDescriptor::new(
+                STOP_COLOR_NAMES[0],
+                UniformValue::Vec4F32(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+        for (i, (offset, color)) in self.stops.iter().enumerate() {
+            uniforms.push(UniformDescriptor::new(
+                STOP_OFFSET_NAMES[i],
+                UniformValue::F32(*offset),
+            ));
+            uniforms.push(UniformDescriptor::new(
+                STOP_COLOR_NAMES[i],
+                UniformValue::Vec4F32(color.r, color.g, color.b, color.a),
+            ));
+        }
+        self.uniforms = UniformList::new(&uniforms);
+    }
+}
+
+impl Paint for Gradient {
+    fn paint_uniforms(&self) -> UniformList {
+        self.uniforms.clone()
+    }
+    fn paint_render_pipeline(&self) -> RenderPipelineHandle {
+        RenderPipelineHandle::GRADIENT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_are_sorted() {
+        let gradient = Gradient::linear((0, 0), (1, 1))
+            .with_stop(1.0, Color::BLUE)
+            .with_stop(0.0, Color::RED)
+            .with_stop(0.5, Color::GREEN);
+        let offsets: Vec<f32> = gradient.stops().iter().map(|(o, _)| *o).collect();
+        assert_eq!(offsets, vec![0.0, 0.5, 1.0]);
+        assert_eq!(gradient.stops()[1].1, Color::GREEN);
+    }
+
+    #[test]
+    fn stops_beyond_limit_dropped() {
+        let mut gradient = Gradient::radial((0.5, 0.5), 0.5);
+        for i in 0..MAX_GRADIENT_STOPS + 2 {
+            gradient = gradient.with_stop(i as f32, Color::WHITE);
+        }
+        assert_eq!(gradient.stops().len(), MAX_GRADIENT_STOPS);
+        // kind, start, end, count + offset and color per stop
+        assert_eq!(gradient.paint_uniforms().len(), 4 + 2 * MAX_GRADIENT_STOPS);
+        // The first stops are kept
+        assert_eq!(
+            gradient.stops().last().map(|(o, _)| *o),
+            Some((MAX_GRADIENT_STOPS - 1) as f32)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{AssetLibrary, DisplayPaint, Image, ImageDesc, Rectangle};
+
+/// An image split into a 3x3 grid, for backgrounds of resizable UI panels.
+///
+/// The corners are drawn unscaled, the edges are stretched (or tiled) along one axis and the center along both.
+/// This way, borders and rounded corners of the image keep their look at any panel size.
+///
+/// Insets define the width of the borders, in texels of the source image.
+/// Draw it with `DisplayArea::draw_nine_slice` or use it as background of a `UiElement`.
+#[derive(Clone, Debug)]
+pub struct NineSlice {
+    source: NineSliceSource,
+    /// left, top, right, bottom (in texels)
+    insets: [f32; 4],
+    border_scale: f32,
+    mode: NineSliceMode,
+}
+
+#[derive(Clone, Debug)]
+enum NineSliceSource {
+    Image(Image),
+    Desc(ImageDesc),
+}
+
+/// Defines how the edges and the center of a `NineSlice` fill the space between the corners.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NineSliceMode {
+    /// Stretch each part to fill its space. (default)
+    Stretch,
+    /// Repeat each part at its natural size, the last repetition is cut off.
+    Tile,
+}
+
+impl NineSlice {
+    /// Nine-slice of a loaded image, with the same border width on all sides (in texels)
+    pub fn new(image: Image, border: f32) -> Self {
+        Self::with_source(NineSliceSource::Image(image), border)
+    }
+    /// Nine-slice of an image in the asset library, with the same border width on all sides (in texels)
+    pub fn from_desc(image: ImageDesc, border: f32) -> Self {
+        Self::with_source(NineSliceSource::Desc(image), border)
+    }
+    fn with_source(source: NineSliceSource, border: f32) -> Self {
+        Self {
+            source,
+            insets: [border; 4],
+            border_scale: 1.0,
+            mode: NineSliceMode::Stretch,
+        }
+    }
+    /// Set the border widths individually (in texels)
+    #[inline(always)]
+    pub fn with_insets(mut self, left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        self.insets = [left, top, right, bottom];
+        self
+    }
+    /// Draw corners and edges larger or smaller than their size in texels
+    #[inline(always)]
+    pub fn with_border_scale(mut self, scale: f32) -> Self {
+        self.border_scale = scale;
+        self
+    }
+    #[inline(always)]
+    pub fn with_mode(mut self, mode: NineSliceMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub(crate) fn image<'a>(&'a self, assets: &'a AssetLibrary) -> Option<&'a Image> {
+        match &self.source {
+            NineSliceSource::Image(img) => Some(img),
+            NineSliceSource::Desc(desc) => DisplayPaint::image(desc, assets),
+        }
+    }
+
+    /// All parts to draw into the given area, as pairs of target area and source region in texels
+    pub(crate) fn slices(
+        &self,
+        area: &Rectangle,
+        image_size: (f32, f32),
+    ) -> Vec<(Rectangle, Rectangle)> {
+        let [left, top, right, bottom] = self.insets;
+        let tile = self.mode == NineSliceMode::Tile;
+        let columns = axis_segments(
+            area.x(),
+            area.width(),
+            image_size.0,
+            (left, right),
+            self.border_scale,
+            tile,
+        );
+        let rows = axis_segments(
+            area.y(),
+            area.height(),
+            image_size.1,
+            (top, bottom),
+            self.border_scale,
+            tile,
+        );
+        let mut slices = Vec::with_capacity(columns.len() * rows.len());
+        for row in &rows {
+            for column in &columns {
+                slices.push((
+                    Rectangle::new(
+                        (column.target.0, row.target.0),
+                        (column.target.1, row.target.1),
+                    ),
+                    Rectangle::new(
+                        (column.source.0, row.source.0),
+                        (column.source.1, row.source.1),
+                    ),
+                ));
+            }
+        }
+        slices
+    }
+}
+
+/// One part along a single axis, as (start, length) in target coordinates and in texels
+#[derive(Debug, PartialEq)]
+struct Segment {
+    target: (f32, f32),
+    source: (f32, f32),
+}
+
+/// Splits one axis into the two borders and the part in between.
+///
+/// If the target is too small for both borders, they are shrunk proportionally.
+fn axis_segments(
+    start: f32,
+    length: f32,
+    texels: f32,
+    insets: (f32, f32),
+    scale: f32,
+    tile: bool,
+) -> Vec<Segment> {
+    let (inset_a, inset_b) = insets;
+    let mut border_a = inset_a * scale;
+    let mut border_b = inset_b * scale;
+    if border_a + border_b > length && border_a + border_b > 0.0 {
+        let shrink = length / (border_a + border_b);
+        border_a *= shrink;
+        border_b *= shrink;
+    }
+    let middle_texels = texels - inset_a - inset_b;
+    let middle_length = length - border_a - border_b;
+
+    let mut segments = vec![Segment {
+        target: (start, border_a),
+        source: (0.0, inset_a),
+    }];
+    if tile && middle_texels > 0.0 && scale > 0.0 {
+        let step = middle_texels * scale;
+        let mut pos = 0.0;
+        while pos < middle_length {
+            let part = step.min(middle_length - pos);
+            segments.push(Segment {
+                target: (start + border_a + pos, part),
+                source: (inset_a, part / scale),
+            });
+            pos += step;
+        }
+    } else {
+        segments.push(Segment {
+            target: (start + border_a, middle_length),
+            source: (inset_a, middle_texels),
+        });
+    }
+    segments.push(Segment {
+        target: (start + length - border_b, border_b),
+        source: (texels - inset_b, inset_b),
+    });
+    segments.retain(|s| s.target.1 > 0.0 && s.source.1 > 0.0);
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(segments: &[Segment]) -> Vec<(f32, f32)> {
+        segments.iter().map(|s| s.target).collect()
+    }
+    fn sources(segments: &[Segment]) -> Vec<(f32, f32)> {
+        segments.iter().map(|s| s.source).collect()
+    }
+
+    #[test]
+    fn stretched_axis() {
+        let segments = axis_segments(10.0, 100.0, 32.0, (8.0, 4.0), 1.0, false);
+        assert_eq!(
+            targets(&segments),
+            vec![(10.0, 8.0), (18.0, 88.0), (106.0, 4.0)]
+        );
+        assert_eq!(
+            sources(&segments),
+            vec![(0.0, 8.0), (8.0, 20.0), (28.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn tiled_axis() {
+        // middle part is 10 texels wide, drawn at double size
+        let segments = axis_segments(0.0, 58.0, 14.0, (2.0, 2.0), 2.0, true);
+        assert_eq!(
+            targets(&segments),
+            vec![
+                (0.0, 4.0),
+                (4.0, 20.0),
+                (24.0, 20.0),
+                (44.0, 10.0),
+                (54.0, 4.0)
+            ]
+        );
+        assert_eq!(
+            sources(&segments),
+            vec![
+                (0.0, 2.0),
+                (2.0, 10.0),
+                (2.0, 10.0),
+                (2.0, 5.0),
+                (12.0, 2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn borders_shrink_in_small_area() {
+        let segments = axis_segments(0.0, 10.0, 32.0, (10.0, 10.0), 1.0, false);
+        assert_eq!(targets(&segments), vec![(0.0, 5.0), (5.0, 5.0)]);
+        assert_eq!(sources(&segments), vec![(0.0, 10.0), (22.0, 10.0)]);
+    }
+}
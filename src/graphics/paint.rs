@@ -1,6 +1,8 @@
 use crate::quicksilver_compat::graphics::Color;
 use crate::UniformList;
-use crate::{graphics::Image, AbstractVertex, RenderPipelineHandle};
+use crate::{
+    graphics::Image, AbstractVertex, BlendMode, RenderPipelineHandle, Vector, VertexAttributeLayout,
+};
 
 /// Implementors of this trait can be used to define non-positional attributes of GPU vertices. (Color/texture/custom attributes)
 pub trait Paint {
@@ -17,6 +19,13 @@ pub trait Paint {
     ) -> Option<Vec<f32>> {
         None
     }
+    /// Names and sizes of the values returned by `paint_extra_vertex_attributes`, in their order.
+    ///
+    /// With a layout, values are matched to the attributes of the render pipeline's `VertexDescriptor` by name.
+    /// Without, they must follow the declared order.
+    fn paint_extra_vertex_attribute_layout(&self) -> Option<VertexAttributeLayout> {
+        None
+    }
     /// Uniforms specific to this paint.
     ///
     /// If different uniform values are used on the same render pipeline, it
@@ -27,6 +36,16 @@ pub trait Paint {
     fn paint_render_pipeline(&self) -> RenderPipelineHandle {
         RenderPipelineHandle::default()
     }
+    /// How the painted shape is combined with what has been drawn before.
+    ///
+    /// Shapes with different blend modes are drawn in separate draw calls.
+    fn paint_blend_mode(&self) -> BlendMode {
+        BlendMode::default()
+    }
+    /// How many times the image is repeated over the painted shape, along each axis.
+    fn paint_texture_repeat(&self) -> Vector {
+        Vector::ONE
+    }
 }
 
 impl Paint for Image {
@@ -0,0 +1,337 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::quicksilver_compat::Color;
+use crate::{BlendMode, Image, Rectangle, Vector};
+
+/// Values that can change smoothly over the life of a particle
+pub trait Interpolate: Clone {
+    /// Blend between self (at t = 0.0) and other (at t = 1.0)
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Color {
+            r: self.r.interpolate(&other.r, t),
+            g: self.g.interpolate(&other.g, t),
+            b: self.b.interpolate(&other.b, t),
+            a: self.a.interpolate(&other.a, t),
+        }
+    }
+}
+
+/// A value defined by keyframes between 0.0 (birth of a particle) and 1.0 (end of its lifetime), linearly interpolated
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+    /// Sorted by time
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Interpolate> Curve<T> {
+    /// The same value over the whole lifetime
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+    /// Change from one value at birth to another at the end of life
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+    /// Add a keyframe at a point in life, between 0.0 and 1.0
+    pub fn with_key(mut self, t: f32, value: T) -> Self {
+        let index = self.keys.partition_point(|(k, _)| *k <= t);
+        self.keys.insert(index, (t, value));
+        self
+    }
+    pub fn sample(&self, t: f32) -> T {
+        let next = self.keys.partition_point(|(k, _)| *k <= t);
+        if next == 0 {
+            return self.keys[0].1.clone();
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1.clone();
+        }
+        let (t0, v0) = &self.keys[next - 1];
+        let (t1, v1) = &self.keys[next];
+        v0.interpolate(v1, (t - t0) / (t1 - t0))
+    }
+}
+
+/// A single living particle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub position: Vector,
+    /// In units per second
+    pub velocity: Vector,
+    pub age_ms: f32,
+    pub lifetime_ms: f32,
+}
+
+impl Particle {
+    /// Progress from birth (0.0) to death (1.0)
+    pub fn life(&self) -> f32 {
+        (self.age_ms / self.lifetime_ms).min(1.0)
+    }
+}
+
+/// Spawns particles and moves them, until their lifetime has passed.
+///
+/// Add emitters to the display with `DisplayArea::add_emitter`, they are updated on every `UpdateWorld` and can be drawn with `DisplayArea::draw_particles`.
+/// All particles of an emitter share a paint, which allows to draw them in a single draw call.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    pub(crate) position: Vector,
+    /// Particles spawn uniformly distributed in an area of this size, centered at the position
+    spawn_area: Vector,
+    /// Particles per second
+    spawn_rate: f32,
+    lifetime_ms: (f32, f32),
+    /// Direction of the velocity cone in degrees, 0 points to the right and 90 down
+    direction: f32,
+    /// Opening angle of the velocity cone in degrees
+    spread: f32,
+    speed: (f32, f32),
+    gravity: Vector,
+    pub(crate) color: Curve<Color>,
+    pub(crate) size: Curve<f32>,
+    pub(crate) image: Option<Image>,
+    pub(crate) blend_mode: BlendMode,
+    max_particles: usize,
+    active: bool,
+    /// Fraction of a particle that is spawned with the next update
+    spawn_debt: f32,
+    pub(crate) particles: Vec<Particle>,
+    rng: StdRng,
+}
+
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0);
+
+impl ParticleEmitter {
+    /// An emitter of white 4x4 particles moving upwards, 10 per second with a lifetime of 1s
+    pub fn new(position: impl Into<Vector>) -> Self {
+        Self {
+            position: position.into(),
+            spawn_area: Vector::ZERO,
+            spawn_rate: 10.0,
+            lifetime_ms: (1000.0, 1000.0),
+            direction: -90.0,
+            spread: 0.0,
+            speed: (50.0, 50.0),
+            gravity: Vector::ZERO,
+            color: Curve::constant(Color::WHITE),
+            size: Curve::constant(4.0),
+            image: None,
+            blend_mode: BlendMode::default(),
+            max_particles: 1000,
+            active: true,
+            spawn_debt: 0.0,
+            particles: Vec::new(),
+            rng: StdRng::seed_from_u64(NEXT_SEED.fetch_add(1, Ordering::Relaxed)),
+        }
+    }
+    /// Number of particles spawned per second while active
+    #[inline(always)]
+    pub fn with_spawn_rate(mut self, per_second: f32) -> Self {
+        self.spawn_rate = per_second;
+        self
+    }
+    /// Spawn particles randomly within a rectangle of the given size, centered at the emitter position
+    #[inline(always)]
+    pub fn with_spawn_area(mut self, size: impl Into<Vector>) -> Self {
+        self.spawn_area = size.into();
+        self
+    }
+    /// Each particle lives for a random time in the range
+    #[inline(always)]
+    pub fn with_lifetime(mut self, min_ms: f32, max_ms: f32) -> Self {
+        self.lifetime_ms = (min_ms, max_ms);
+        self
+    }
+    /// Particles start moving in a random direction within the cone, at a random speed (units per second) within the range.
+    ///
+    /// Angles are in degrees, with 0 pointing right and 90 pointing down.
+    #[inline(always)]
+    pub fn with_velocity_cone(mut self, direction: f32, spread: f32, speed: (f32, f32)) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self.speed = speed;
+        self
+    }
+    /// Constant acceleration, in units per second squared
+    #[inline(always)]
+    pub fn with_gravity(mut self, gravity: impl Into<Vector>) -> Self {
+        self.gravity = gravity.into();
+        self
+    }
+    #[inline(always)]
+    pub fn with_color_over_life(mut self, color: Curve<Color>) -> Self {
+        self.color = color;
+        self
+    }
+    /// Side length of the particle squares over their lifetime
+    #[inline(always)]
+    pub fn with_size_over_life(mut self, size: Curve<f32>) -> Self {
+        self.size = size;
+        self
+    }
+    /// Draw each particle as the image, tinted with its color
+    #[inline(always)]
+    pub fn with_image(mut self, image: Image) -> Self {
+        self.image = Some(image);
+        self
+    }
+    #[inline(always)]
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+    /// Particles beyond this number are not spawned
+    #[inline(always)]
+    pub fn with_max_particles(mut self, max: usize) -> Self {
+        self.max_particles = max;
+        self
+    }
+    /// Use a specific random seed, for reproducible effects
+    #[inline(always)]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+    pub fn position(&self) -> Vector {
+        self.position
+    }
+    pub fn set_position(&mut self, position: impl Into<Vector>) {
+        self.position = position.into();
+    }
+    /// Start or stop spawning particles. Existing particles live on.
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+    /// True if the emitter is inactive and all its particles are gone
+    pub fn is_finished(&self) -> bool {
+        !self.active && self.particles.is_empty()
+    }
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+    /// Spawn a number of particles at once, independent of the spawn rate
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.particles.len() >= self.max_particles {
+                break;
+            }
+            let particle = self.spawn();
+            self.particles.push(particle);
+        }
+    }
+    /// Advance the simulation by the given time
+    pub fn update(&mut self, dt_ms: f32) {
+        let dt = dt_ms / 1000.0;
+        let gravity = self.gravity;
+        self.particles.retain_mut(|p| {
+            p.age_ms += dt_ms;
+            p.velocity += gravity * dt;
+            p.position += p.velocity * dt;
+            p.age_ms < p.lifetime_ms
+        });
+        if self.active {
+            self.spawn_debt += self.spawn_rate * dt;
+            let count = self.spawn_debt.floor();
+            self.spawn_debt -= count;
+            self.burst(count as usize);
+        }
+    }
+    /// The area covered by a particle, in the coordinate system of the emitter position
+    pub(crate) fn particle_area(&self, particle: &Particle) -> Rectangle {
+        let size = self.size.sample(particle.life());
+        Rectangle::new(
+            particle.position - Vector::new(size, size) / 2.0,
+            (size, size),
+        )
+    }
+    fn spawn(&mut self) -> Particle {
+        let offset = Vector::new(
+            (self.rng.gen::<f32>() - 0.5) * self.spawn_area.x,
+            (self.rng.gen::<f32>() - 0.5) * self.spawn_area.y,
+        );
+        let angle = (self.direction + (self.rng.gen::<f32>() - 0.5) * self.spread).to_radians();
+        let speed = random_in(&mut self.rng, self.speed);
+        Particle {
+            position: self.position + offset,
+            velocity: Vector::new(angle.cos(), angle.sin()) * speed,
+            age_ms: 0.0,
+            lifetime_ms: random_in(&mut self.rng, self.lifetime_ms),
+        }
+    }
+}
+
+fn random_in(rng: &mut StdRng, (min, max): (f32, f32)) -> f32 {
+    min + (max - min) * rng.gen::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_sampling() {
+        let curve = Curve::linear(0.0, 10.0).with_key(0.5, 2.0);
+        assert_eq!(curve.sample(-1.0), 0.0);
+        assert_eq!(curve.sample(0.25), 1.0);
+        assert_eq!(curve.sample(0.75), 6.0);
+        assert_eq!(curve.sample(2.0), 10.0);
+        assert_eq!(Curve::constant(3.0).sample(0.5), 3.0);
+    }
+
+    #[test]
+    fn spawn_rate_and_lifetime() {
+        let mut emitter = ParticleEmitter::new((0, 0))
+            .with_spawn_rate(100.0)
+            .with_lifetime(50.0, 50.0)
+            .with_seed(1);
+        // 2.5 particles per step, the fraction is carried over
+        emitter.update(25.0);
+        assert_eq!(emitter.particles().len(), 2);
+        emitter.update(25.0);
+        assert_eq!(emitter.particles().len(), 5);
+        // First particles die after 50ms
+        emitter.update(25.0);
+        assert_eq!(emitter.particles().len(), 5);
+        emitter.set_active(false);
+        emitter.update(50.0);
+        assert!(emitter.is_finished());
+    }
+
+    #[test]
+    fn movement_with_gravity() {
+        let mut emitter = ParticleEmitter::new((10, 10))
+            .with_velocity_cone(0.0, 0.0, (100.0, 100.0))
+            .with_gravity((0, 100))
+            .with_spawn_rate(0.0)
+            .with_max_particles(3);
+        emitter.burst(5);
+        assert_eq!(emitter.particles().len(), 3);
+        emitter.update(500.0);
+        let p = &emitter.particles()[0];
+        assert_eq!(p.velocity, Vector::new(100.0, 50.0));
+        assert_eq!(p.position, Vector::new(60.0, 35.0));
+    }
+}
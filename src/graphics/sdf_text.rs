@@ -0,0 +1,184 @@
+use crate::quicksilver_compat::Color;
+use crate::{
+    Image, Paint, RenderPipelineHandle, UniformDescriptor, UniformList, UniformValue, Vector,
+};
+
+/// Encoding of the glyph atlas of a distance field font.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceField {
+    /// Single channel signed distance field, stored in the alpha channel
+    Sdf {
+        /// Distance in atlas pixels covered by the full value range of the field
+        range: f32,
+    },
+    /// Multi-channel signed distance field, which keeps sharp corners
+    Msdf {
+        /// Distance in atlas pixels covered by the full value range of the field
+        range: f32,
+    },
+}
+
+/// Effects for text drawn with a distance field font, rendered by the built-in SDF text pipeline.
+///
+/// Distance field fonts stay crisp at any scale, which makes them the choice for text inside a zoomed camera.
+/// Widths and offsets are in pixels of the font atlas, hence they scale with the text.
+/// Use it with `BitmapText::with_sdf_paint`, fonts with a distance field are drawn with default effects otherwise.
+///
+/// # Example
+/// ```
+/// # use paddle::{SdfTextPaint, quicksilver_compat::Color};
+/// let title = SdfTextPaint::new()
+///     .with_outline(2.0, Color::BLACK)
+///     .with_shadow((3.0, 3.0), Color::BLACK.with_alpha(0.5), 4.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SdfTextPaint {
+    pub(crate) outline: Option<(f32, Color)>,
+    pub(crate) shadow: Option<(Vector, Color, f32)>,
+    pub(crate) softness: f32,
+    pub(crate) field: Option<DistanceField>,
+}
+
+impl DistanceField {
+    pub fn range(&self) -> f32 {
+        match self {
+            DistanceField::Sdf { range } | DistanceField::Msdf { range } => *range,
+        }
+    }
+}
+
+impl SdfTextPaint {
+    /// Plain text with anti-aliased edges
+    pub fn new() -> Self {
+        Self {
+            outline: None,
+            shadow: None,
+            softness: 1.0,
+            field: None,
+        }
+    }
+    /// Draw an outline of the given width around the glyphs.
+    ///
+    /// The width is limited by half the distance range of the font atlas.
+    #[inline(always)]
+    pub fn with_outline(mut self, width: f32, color: Color) -> Self {
+        self.outline = Some((width, color));
+        self
+    }
+    /// Draw a drop shadow below the text, shifted by the offset and blurred over `softness` screen pixels.
+    ///
+    /// Shadows are clipped at the quad of each glyph, so the offset should stay within the padding of the atlas.
+    #[inline(always)]
+    pub fn with_shadow(mut self, offset: impl Into<Vector>, color: Color, softness: f32) -> Self {
+        self.shadow = Some((offset.into(), color, softness));
+        self
+    }
+    /// Width of the anti-aliased edge in screen pixels (default: 1.0). Larger values blur the text.
+    #[inline(always)]
+    pub fn with_softness(mut self, softness: f32) -> Self {
+        self.softness = softness;
+        self
+    }
+    /// Treat the font atlas as distance field, for fonts that do not declare it in their file (e.g. exported by Hiero)
+    #[inline(always)]
+    pub fn with_field(mut self, field: DistanceField) -> Self {
+        self.field = Some(field);
+        self
+    }
+
+    /// Uniforms for the SDF text pipeline.
+    ///
+    /// `screen_px_per_texel` is the scale from the atlas to the screen, `page_size` the size of the atlas page in texels.
+    pub(crate) fn uniforms(
+        &self,
+        field: DistanceField,
+        screen_px_per_texel: f32,
+        page_size: Vector,
+    ) -> UniformList {
+        let range = field.range();
+        let kind = match field {
+            DistanceField::Sdf { .. } => 0.0,
+            DistanceField::Msdf { .. } => 1.0,
+        };
+        let (outline_width, outline_color) = self.outline.unwrap_or((0.0, Color::BLACK));
+        let (shadow_offset, shadow_color, shadow_softness) =
+            self.shadow
+                .unwrap_or((Vector::ZERO, Color::BLACK.with_alpha(0.0), 1.0));
+        let shadow_uv = shadow_offset.times(page_size.recip());
+        UniformList::new(&[
+            UniformDescriptor::new("FieldKind", UniformValue::F32(kind)),
+            UniformDescriptor::new(
+                "ScreenPxRange",
+                UniformValue::F32((range * screen_px_per_texel).max(1.0)),
+            ),
+            UniformDescriptor::new("Softness", UniformValue::F32(self.softness)),
+            UniformDescriptor::new("OutlineWidth", UniformValue::F32(outline_width / range)),
+            UniformDescriptor::new("OutlineColor", color_value(outline_color)),
+            UniformDescriptor::new(
+                "ShadowOffset",
+                UniformValue::Vec2F32(shadow_uv.x, shadow_uv.y),
+            ),
+            UniformDescriptor::new("ShadowColor", color_value(shadow_color)),
+            UniformDescriptor::new("ShadowSoftness", UniformValue::F32(shadow_softness)),
+        ])
+    }
+}
+
+impl Default for SdfTextPaint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn color_value(color: Color) -> UniformValue {
+    UniformValue::Vec4F32(color.r, color.g, color.b, color.a)
+}
+
+/// Paint of a single glyph of a distance field font. Glyphs of the same text and page share uniforms and are drawn in one batch.
+pub(crate) struct SdfGlyphPaint {
+    pub image: Image,
+    pub color: Color,
+    pub uniforms: UniformList,
+}
+
+impl Paint for SdfGlyphPaint {
+    fn paint_image(&self) -> Option<&Image> {
+        Some(&self.image)
+    }
+    fn paint_color(&self) -> Option<Color> {
+        Some(self.color)
+    }
+    fn paint_uniforms(&self) -> UniformList {
+        self.uniforms.clone()
+    }
+    fn paint_render_pipeline(&self) -> RenderPipelineHandle {
+        RenderPipelineHandle::SDF_TEXT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniforms_relative_to_range() {
+        let paint = SdfTextPaint::new()
+            .with_outline(2.0, Color::RED)
+            .with_shadow((4.0, -2.0), Color::BLACK, 3.0);
+        let uniforms = paint.uniforms(DistanceField::Msdf { range: 8.0 }, 0.5, (256, 128).into());
+        let expected = UniformList::new(&[
+            UniformDescriptor::new("FieldKind", UniformValue::F32(1.0)),
+            UniformDescriptor::new("ScreenPxRange", UniformValue::F32(4.0)),
+            UniformDescriptor::new("Softness", UniformValue::F32(1.0)),
+            UniformDescriptor::new("OutlineWidth", UniformValue::F32(0.25)),
+            UniformDescriptor::new("OutlineColor", color_value(Color::RED)),
+            UniformDescriptor::new(
+                "ShadowOffset",
+                UniformValue::Vec2F32(4.0 / 256.0, -2.0 / 128.0),
+            ),
+            UniformDescriptor::new("ShadowColor", color_value(Color::BLACK)),
+            UniformDescriptor::new("ShadowSoftness", UniformValue::F32(3.0)),
+        ]);
+        assert!(uniforms == expected);
+    }
+}
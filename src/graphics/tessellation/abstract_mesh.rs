@@ -67,6 +67,20 @@ impl AbstractMesh {
         }
     }
 
+    /// The smallest rectangle containing all vertices, or None if it would have no area
+    pub fn bounding_box(&self) -> Option<Rectangle> {
+        let first = self.vertices.first()?.pos;
+        let (min, max) = self.vertices.iter().fold((first, first), |(min, max), v| {
+            (min.min(v.pos), max.max(v.pos))
+        });
+        let size = max - min;
+        if size.x > 0.0 && size.y > 0.0 {
+            Some(Rectangle::new(min, size))
+        } else {
+            None
+        }
+    }
+
     /// Transforms the mesh from a coordinate space within `bounding_box` into the normalized space (x,y in [-1,+1])
     pub fn normalize(&mut self, bounding_box: &Rectangle) {
         let min_x = bounding_box.pos.x;
@@ -1,17 +1,59 @@
 mod image;
 mod texture_config;
 pub use image::*;
-pub use texture_config::TextureConfig;
+pub use texture_config::{TextureConfig, WrapMode};
+
+use texture_config::ColorFormat;
 
 use crate::{ErrorMessage, JsError, PaddleResult};
-use web_sys::{HtmlImageElement, WebGlRenderingContext, WebGlTexture};
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+};
+use wasm_bindgen::JsCast;
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, WebGlRenderingContext,
+    WebGlTexture,
+};
 
 /// Holds the raw data for a loaded 2D texture
+///
+/// Clones share the same GPU texture, which allows to upload it again in-place when the WebGL context has been restored.
+/// The GPU texture is deleted when the last clone is dropped.
 #[derive(Debug, Clone)]
 pub(crate) struct Texture2D {
+    inner: Rc<RefCell<TextureData>>,
+}
+
+/// Weak reference to a texture, which does not keep it alive
+pub(crate) struct WeakTexture2D(Weak<RefCell<TextureData>>);
+
+thread_local! {
+    /// GPU textures no longer referenced by any `Texture2D`, waiting to be deleted
+    static RELEASED_TEXTURES: RefCell<Vec<WebGlTexture>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug)]
+struct TextureData {
+    gl: WebGlRenderingContext,
     webgl_texture: WebGlTexture,
-    pub(crate) texel_width: f32,
-    pub(crate) texel_height: f32,
+    texel_width: f32,
+    texel_height: f32,
+    /// The source is kept to upload it again when necessary
+    source: TextureSource,
+    config: TextureConfig,
+}
+
+/// Where the texels of a texture come from
+#[derive(Debug)]
+enum TextureSource {
+    Image(HtmlImageElement),
+    /// 4 bytes per pixel, row by row starting at the top left
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
 }
 
 impl Texture2D {
@@ -21,89 +63,375 @@ impl Texture2D {
         img: &HtmlImageElement,
         config: &TextureConfig,
     ) -> PaddleResult<Self> {
-        let webgl_texture = gl
-            .create_texture()
-            .ok_or_else(|| ErrorMessage::technical("Failed to create texture".to_owned()))?;
-        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&webgl_texture));
+        let source = TextureSource::Image(img.clone());
+        let texel_width = img.width() as f32;
+        let texel_height = img.height() as f32;
+        Self::upload(gl, source, texel_width, texel_height, *config)
+    }
+    /// Upload a new texture from raw pixels, 4 bytes (red, green, blue, alpha) per pixel
+    pub fn from_rgba(
+        gl: &WebGlRenderingContext,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+        config: &TextureConfig,
+    ) -> PaddleResult<Self> {
+        check_rgba_region((width, height), (0, 0), (width, height), pixels.len())?;
+        if config.wrap_mode.requires_power_of_two()
+            && !(width.is_power_of_two() && height.is_power_of_two())
+        {
+            return Err(ErrorMessage::technical(format!(
+                "Repeating textures need power-of-two dimensions, got {}x{} pixels",
+                width, height
+            )));
+        }
+        let source = TextureSource::Rgba {
+            width,
+            height,
+            pixels,
+        };
+        Self::upload(gl, source, width as f32, height as f32, config.with_rgba())
+    }
+    fn upload(
+        gl: &WebGlRenderingContext,
+        source: TextureSource,
+        texel_width: f32,
+        texel_height: f32,
+        config: TextureConfig,
+    ) -> PaddleResult<Self> {
+        let webgl_texture = upload_texture(gl, &source, &config)?;
+        let data = TextureData {
+            gl: gl.clone(),
+            webgl_texture,
+            texel_width,
+            texel_height,
+            source,
+            config,
+        };
+        Ok(Self {
+            inner: Rc::new(RefCell::new(data)),
+        })
+    }
+    pub fn webgl_texture(&self) -> WebGlTexture {
+        self.inner.borrow().webgl_texture.clone()
+    }
+    pub fn texel_width(&self) -> f32 {
+        self.inner.borrow().texel_width
+    }
+    pub fn texel_height(&self) -> f32 {
+        self.inner.borrow().texel_height
+    }
+    /// Upload the texture from its source again, for example after the WebGL context has been restored.
+    pub fn restore(&self, gl: &WebGlRenderingContext) -> PaddleResult<()> {
+        let mut data = self.inner.borrow_mut();
+        data.webgl_texture = upload_texture(gl, &data.source, &data.config)?;
+        Ok(())
+    }
+    /// Overwrite a rectangle of texels with RGBA pixels.
+    ///
+    /// Textures created from pixels remember the change, to restore it with the WebGL context.
+    /// Changes to textures loaded from images are lost when the WebGL context is restored.
+    /// Images scaled up to power-of-two dimensions for repeating cannot be updated, as their texels no longer match the pixels of the image.
+    pub fn update_region(
+        &self,
+        offset: (u32, u32),
+        size: (u32, u32),
+        rgba: &[u8],
+    ) -> PaddleResult<()> {
+        let mut data = self.inner.borrow_mut();
+        if data.config.color_format != ColorFormat::RGBA {
+            return Err(ErrorMessage::technical(
+                "Only RGBA textures can be updated with pixels".to_owned(),
+            ));
+        }
+        let texture_size = (data.texel_width as u32, data.texel_height as u32);
+        let uploaded = uploaded_size(texture_size, &data.config);
+        if uploaded != texture_size {
+            return Err(ErrorMessage::technical(format!(
+                "Cannot update the region of a texture that was scaled from {}x{} to {}x{} pixels for repeating",
+                texture_size.0, texture_size.1, uploaded.0, uploaded.1
+            )));
+        }
+        check_rgba_region(uploaded, offset, size, rgba.len())?;
 
-        // Clamp to edge allows using non-power-of-two sized texture sources. Always use that to keep it simple. (for now)
-        gl.tex_parameteri(
-            WebGlRenderingContext::TEXTURE_2D,
-            WebGlRenderingContext::TEXTURE_WRAP_S,
-            WebGlRenderingContext::CLAMP_TO_EDGE as i32,
-        );
-        gl.tex_parameteri(
-            WebGlRenderingContext::TEXTURE_2D,
-            WebGlRenderingContext::TEXTURE_WRAP_T,
-            WebGlRenderingContext::CLAMP_TO_EDGE as i32,
-        );
+        let gl = &data.gl;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&data.webgl_texture));
+        // JS equivalent: texSubImage2D()
+        let result = gl
+            .tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(
+                WebGlRenderingContext::TEXTURE_2D,
+                0,
+                offset.0 as i32,
+                offset.1 as i32,
+                size.0 as i32,
+                size.1 as i32,
+                WebGlRenderingContext::RGBA,
+                WebGlRenderingContext::UNSIGNED_BYTE,
+                Some(rgba),
+            )
+            .map_err(JsError::from_js_value);
+        if result.is_ok() && data.config.mipmap_level.on() {
+            gl.generate_mipmap(WebGlRenderingContext::TEXTURE_2D);
+        }
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, None);
+        result?;
 
-        gl.tex_parameteri(
-            WebGlRenderingContext::TEXTURE_2D,
-            WebGlRenderingContext::TEXTURE_MIN_FILTER,
-            config.minification_filter.webgl_num(&config.mipmap_level),
-        );
-        gl.tex_parameteri(
-            WebGlRenderingContext::TEXTURE_2D,
-            WebGlRenderingContext::TEXTURE_MAG_FILTER,
-            config.magnification_filter.webgl_num(),
-        );
-        let color_format = config.color_format.webgl_num();
+        if let TextureSource::Rgba { width, pixels, .. } = &mut data.source {
+            copy_region(pixels, *width, offset, size, rgba);
+        }
+        Ok(())
+    }
+    pub fn downgrade(&self) -> WeakTexture2D {
+        WeakTexture2D(Rc::downgrade(&self.inner))
+    }
+}
+
+impl WeakTexture2D {
+    pub fn upgrade(&self) -> Option<Texture2D> {
+        self.0.upgrade().map(|inner| Texture2D { inner })
+    }
+    pub fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
+fn upload_texture(
+    gl: &WebGlRenderingContext,
+    source: &TextureSource,
+    config: &TextureConfig,
+) -> PaddleResult<WebGlTexture> {
+    let webgl_texture = gl
+        .create_texture()
+        .ok_or_else(|| ErrorMessage::technical("Failed to create texture".to_owned()))?;
+    gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&webgl_texture));
+
+    // Clamp to edge allows using non-power-of-two sized texture sources, repeating requires scaling them first.
+    gl.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_WRAP_S,
+        config.wrap_mode.webgl_num(),
+    );
+    gl.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_WRAP_T,
+        config.wrap_mode.webgl_num(),
+    );
 
-        // JS equivalent: texImage2D()
-        gl.tex_image_2d_with_u32_and_u32_and_image(
+    gl.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_MIN_FILTER,
+        config.minification_filter.webgl_num(&config.mipmap_level),
+    );
+    gl.tex_parameteri(
+        WebGlRenderingContext::TEXTURE_2D,
+        WebGlRenderingContext::TEXTURE_MAG_FILTER,
+        config.magnification_filter.webgl_num(),
+    );
+    let color_format = config.color_format.webgl_num();
+
+    // JS equivalent: texImage2D()
+    match source {
+        TextureSource::Image(img)
+            if config.wrap_mode.requires_power_of_two() && !is_power_of_two(img) =>
+        {
+            gl.tex_image_2d_with_u32_and_u32_and_canvas(
+                WebGlRenderingContext::TEXTURE_2D,
+                0,
+                color_format,
+                color_format as u32,
+                WebGlRenderingContext::UNSIGNED_BYTE,
+                &scale_to_power_of_two(img)?,
+            )
+        }
+        TextureSource::Image(img) => gl.tex_image_2d_with_u32_and_u32_and_image(
             WebGlRenderingContext::TEXTURE_2D,
             0,
             color_format,
             color_format as u32,
             WebGlRenderingContext::UNSIGNED_BYTE,
-            &img,
-        )
-        .map_err(JsError::from_js_value)?;
+            img,
+        ),
+        TextureSource::Rgba {
+            width,
+            height,
+            pixels,
+        } => gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGlRenderingContext::TEXTURE_2D,
+            0,
+            color_format,
+            *width as i32,
+            *height as i32,
+            0,
+            color_format as u32,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            Some(pixels),
+        ),
+    }
+    .map_err(JsError::from_js_value)?;
 
-        if config.mipmap_level.on() {
-            gl.generate_mipmap(WebGlRenderingContext::TEXTURE_2D);
-        }
+    if config.mipmap_level.on() {
+        gl.generate_mipmap(WebGlRenderingContext::TEXTURE_2D);
+    }
 
-        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, None);
+    gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, None);
+    Ok(webgl_texture)
+}
 
-        let texel_width = img.width() as f32;
-        let texel_height = img.height() as f32;
-        Ok(Self {
-            webgl_texture,
-            texel_width,
-            texel_height,
-        })
+/// Checks that the region lies within the texture and that there are exactly 4 bytes for each of its pixels.
+fn check_rgba_region(
+    texture_size: (u32, u32),
+    offset: (u32, u32),
+    size: (u32, u32),
+    num_bytes: usize,
+) -> PaddleResult<()> {
+    let expected = size.0 as usize * size.1 as usize * 4;
+    if num_bytes != expected {
+        return Err(ErrorMessage::technical(format!(
+            "Expected {} bytes of RGBA data for {}x{} pixels, got {}",
+            expected, size.0, size.1, num_bytes
+        )));
+    }
+    let fits =
+        |offset: u32, size: u32, max: u32| offset.checked_add(size).map_or(false, |end| end <= max);
+    if !fits(offset.0, size.0, texture_size.0) || !fits(offset.1, size.1, texture_size.1) {
+        return Err(ErrorMessage::technical(format!(
+            "Region of {}x{} pixels at ({}|{}) exceeds the texture of {}x{} pixels",
+            size.0, size.1, offset.0, offset.1, texture_size.0, texture_size.1
+        )));
+    }
+    Ok(())
+}
+
+/// Copy RGBA rows into a larger RGBA image of the given width. The region must be checked beforehand.
+fn copy_region(dst: &mut [u8], dst_width: u32, offset: (u32, u32), size: (u32, u32), src: &[u8]) {
+    let row_len = size.0 as usize * 4;
+    if row_len == 0 {
+        return;
     }
-    pub fn webgl_texture(&self) -> &WebGlTexture {
-        &self.webgl_texture
+    for (row, src_row) in src.chunks_exact(row_len).enumerate() {
+        let start = ((offset.1 as usize + row) * dst_width as usize + offset.0 as usize) * 4;
+        dst[start..start + row_len].copy_from_slice(src_row);
     }
 }
 
+fn is_power_of_two(img: &HtmlImageElement) -> bool {
+    img.width().is_power_of_two() && img.height().is_power_of_two()
+}
+
+/// Size of the texture on the GPU for a source of the given size, which differs when it has to be scaled for repeating
+fn uploaded_size(size: (u32, u32), config: &TextureConfig) -> (u32, u32) {
+    if config.wrap_mode.requires_power_of_two()
+        && !(size.0.is_power_of_two() && size.1.is_power_of_two())
+    {
+        (size.0.next_power_of_two(), size.1.next_power_of_two())
+    } else {
+        size
+    }
+}
+
+/// Draws the image stretched onto a canvas with the next larger power-of-two dimensions.
+fn scale_to_power_of_two(img: &HtmlImageElement) -> PaddleResult<HtmlCanvasElement> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .map_err(JsError::from_js_value)?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| ErrorMessage::technical("Failed creating canvas".to_owned()))?;
+    let width = img.width().next_power_of_two();
+    let height = img.height().next_power_of_two();
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let ctx = canvas
+        .get_context("2d")
+        .map_err(JsError::from_js_value)?
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| ErrorMessage::technical("Failed loading 2d canvas context".to_owned()))?;
+    ctx.draw_image_with_html_image_element_and_dw_and_dh(
+        img,
+        0.0,
+        0.0,
+        width as f64,
+        height as f64,
+    )
+    .map_err(JsError::from_js_value)?;
+    Ok(canvas)
+}
+
 impl PartialEq for Texture2D {
     fn eq(&self, other: &Self) -> bool {
-        self.webgl_texture == other.webgl_texture
+        Rc::ptr_eq(&self.inner, &other.inner)
     }
 }
 impl Eq for Texture2D {}
 
-// This could be done if a single reference to the Texture2D was kept. Currently this is clones all over the place...
-// use wasm_bindgen::JsCast;
-// impl Drop for Texture2D {
-//     fn drop(&mut self) {
-//         if let Some(document) = web_sys::window().and_then(|w| w.document()) {
-//             if let Some(canvas) = document.get_elements_by_tag_name("canvas").item(0) {
-//                 let canvas: web_sys::HtmlCanvasElement =
-//                     canvas.dyn_into::<web_sys::HtmlCanvasElement>().unwrap();
-//                 if let Some(gl) = canvas
-//                     .get_context("webgl")
-//                     .ok()
-//                     .flatten()
-//                     .and_then(|ctx| ctx.dyn_into::<WebGlRenderingContext>().ok())
-//                 {
-//                     gl.delete_texture(Some(&self.webgl_texture));
-//                 }
-//             }
-//         }
-//     }
-// }
+impl Drop for TextureData {
+    fn drop(&mut self) {
+        // Vertices waiting to be drawn may still use the texture, hence it is only deleted after the next flush
+        RELEASED_TEXTURES.with(|released| released.borrow_mut().push(self.webgl_texture.clone()));
+    }
+}
+
+/// Delete textures of which the last `Image` has been dropped. Called after drawing all buffered vertices.
+pub(crate) fn delete_released_textures(gl: &WebGlRenderingContext) {
+    RELEASED_TEXTURES.with(|released| {
+        for texture in released.borrow_mut().drain(..) {
+            gl.delete_texture(Some(&texture));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgba_region_checks() {
+        assert!(check_rgba_region((4, 4), (0, 0), (4, 4), 64).is_ok());
+        assert!(check_rgba_region((4, 4), (2, 3), (2, 1), 8).is_ok());
+        assert!(check_rgba_region((4, 4), (0, 0), (4, 4), 63).is_err());
+        assert!(check_rgba_region((4, 4), (3, 0), (2, 1), 8).is_err());
+        assert!(check_rgba_region((4, 4), (0, u32::MAX), (1, 1), 4).is_err());
+    }
+
+    #[test]
+    fn region_copied_row_by_row() {
+        let mut dst = vec![0; 3 * 2 * 4];
+        copy_region(&mut dst, 3, (1, 0), (2, 2), &[1; 16]);
+        let expected: Vec<u8> = [[0; 4], [1; 4], [1; 4], [0; 4], [1; 4], [1; 4]].concat();
+        assert_eq!(dst, expected);
+    }
+
+    #[test]
+    fn resampled_size_for_repeat() {
+        let clamped = TextureConfig::default();
+        let repeated = TextureConfig::default().with_wrap_mode(WrapMode::Repeat);
+        assert_eq!(uploaded_size((100, 30), &clamped), (100, 30));
+        assert_eq!(uploaded_size((100, 30), &repeated), (128, 32));
+        assert_eq!(uploaded_size((64, 32), &repeated), (64, 32));
+        // A single non-power-of-two side is enough to scale the texture
+        let mirrored = TextureConfig::default().with_wrap_mode(WrapMode::MirroredRepeat);
+        assert_eq!(uploaded_size((64, 33), &mirrored), (64, 64));
+        assert_eq!(uploaded_size((1, 1), &mirrored), (1, 1));
+    }
+
+    #[test]
+    fn wrap_mode_selection() {
+        let selected = |mode: WrapMode| TextureConfig::default().with_wrap_mode(mode).wrap_mode;
+        assert_eq!(
+            selected(WrapMode::ClampToEdge).webgl_num(),
+            WebGlRenderingContext::CLAMP_TO_EDGE as i32
+        );
+        assert_eq!(
+            selected(WrapMode::Repeat).webgl_num(),
+            WebGlRenderingContext::REPEAT as i32
+        );
+        assert_eq!(
+            selected(WrapMode::MirroredRepeat).webgl_num(),
+            WebGlRenderingContext::MIRRORED_REPEAT as i32
+        );
+        assert_eq!(TextureConfig::default().wrap_mode, WrapMode::ClampToEdge);
+        assert!(!WrapMode::ClampToEdge.requires_power_of_two());
+        assert!(WrapMode::Repeat.requires_power_of_two());
+        assert!(WrapMode::MirroredRepeat.requires_power_of_two());
+    }
+}
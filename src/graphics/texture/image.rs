@@ -1,5 +1,5 @@
-use crate::graphics::Texture2D;
 use crate::graphics::TextureConfig;
+use crate::graphics::{Texture2D, WeakTexture2D};
 use crate::Vector;
 use std::{cell::Cell, rc::Rc};
 
@@ -21,7 +21,10 @@ struct BindTextureMessage {
     payload: Rc<Cell<BindTexturePayload>>,
 }
 enum BindTexturePayload {
-    Request(HtmlImageElement),
+    /// Image with a specific texture configuration, or `None` to use the one of the image loader
+    Request(HtmlImageElement, Option<TextureConfig>),
+    /// Width, height and RGBA pixels, with an optional texture configuration
+    RequestRgba(u32, u32, Vec<u8>, Option<TextureConfig>),
     Response(Texture2D),
     Done,
 }
@@ -29,11 +32,23 @@ enum BindTexturePayload {
 pub struct ImageLoader {
     gl: WebGlRenderingContext,
     texture_config: TextureConfig,
+    /// All textures created so far, to upload them again after the WebGL context has been restored.
+    textures: Vec<WeakTexture2D>,
 }
 
+/// Published after the WebGL context has been restored, handled by the `ImageLoader`.
+pub(crate) struct RestoreTextures;
+
 impl Image {
     /// Load an image from a URL
     pub async fn load(src: &str) -> PaddleResult<Self> {
+        Self::load_texture(src, None).await
+    }
+    /// Load an image from a URL, with a texture configuration that overrides the global one from `DisplayConfig`.
+    pub async fn load_with_config(src: &str, config: TextureConfig) -> PaddleResult<Self> {
+        Self::load_texture(src, Some(config)).await
+    }
+    async fn load_texture(src: &str, config: Option<TextureConfig>) -> PaddleResult<Self> {
         // Let the browser handle the image loading
         let el = HtmlImageElement::new().map_err(JsError::from_js_value)?;
         el.set_cross_origin(Some(""));
@@ -45,7 +60,29 @@ impl Image {
             .map_err(JsError::from_js_value)?;
 
         // When the image is ready, create a WebGL texture from it in the image loader and place it in a cell.
-        let cell = Rc::new(Cell::new(BindTexturePayload::Request(el)));
+        Self::bind_texture(BindTexturePayload::Request(el, config)).await
+    }
+    /// Create an image from raw pixels, 4 bytes (red, green, blue, alpha) per pixel, row by row starting at the top left.
+    ///
+    /// Useful for procedurally generated textures. Change the pixels later with `update_region`.
+    pub async fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> PaddleResult<Self> {
+        let payload = BindTexturePayload::RequestRgba(width, height, rgba.to_vec(), None);
+        Self::bind_texture(payload).await
+    }
+    /// Create an image from raw RGBA pixels, with a texture configuration that overrides the global one from `DisplayConfig`.
+    ///
+    /// Repeating wrap modes require power-of-two dimensions.
+    pub async fn from_rgba_with_config(
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        config: TextureConfig,
+    ) -> PaddleResult<Self> {
+        let payload = BindTexturePayload::RequestRgba(width, height, rgba.to_vec(), Some(config));
+        Self::bind_texture(payload).await
+    }
+    async fn bind_texture(request: BindTexturePayload) -> PaddleResult<Self> {
+        let cell = Rc::new(Cell::new(request));
         let msg = BindTextureMessage {
             payload: cell.clone(),
         };
@@ -61,12 +98,28 @@ impl Image {
         Ok(Image { texture, region })
     }
 
+    /// Overwrite pixels of the image with RGBA data, 4 bytes per pixel and row by row.
+    ///
+    /// The offset is in texel coordinates, like in `subimage_texels`, hence all images sharing the texture change.
+    /// Images loaded from URLs can only be updated if they use an RGBA texture, and lose updates when the WebGL context is restored.
+    /// Repeating images without power-of-two dimensions are scaled on upload and cannot be updated.
+    pub fn update_region(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+    ) -> PaddleResult<()> {
+        self.texture.update_region((x, y), (width, height), rgba)
+    }
+
     pub fn natural_width(&self) -> f32 {
-        (self.texture.texel_width * self.region.width()).abs()
+        (self.texture.texel_width() * self.region.width()).abs()
     }
 
     pub fn natural_height(&self) -> f32 {
-        (self.texture.texel_height * self.region.height()).abs()
+        (self.texture.texel_height() * self.region.height()).abs()
     }
 
     pub fn natural_size(&self) -> Vector {
@@ -79,10 +132,13 @@ impl Image {
             texture: self.texture.clone(),
             region: Rectangle::new(
                 (
-                    self.region.pos.x + rect.pos.x / self.natural_width(),
-                    self.region.pos.y + rect.pos.y / self.natural_height(),
+                    self.region.pos.x + rect.pos.x / self.texture.texel_width(),
+                    self.region.pos.y + rect.pos.y / self.texture.texel_height(),
+                ),
+                (
+                    rect.width() / self.texture.texel_width(),
+                    rect.height() / self.texture.texel_height(),
                 ),
-                (rect.width(), rect.height()),
             ),
         };
         debug_assert!(img.region.x() <= 1.0);
@@ -95,6 +151,22 @@ impl Image {
         debug_assert!(img.region.height() >= 0.0);
         img
     }
+    /// Mirror the image along the vertical axis (horizontal flip) and/or the horizontal axis (vertical flip)
+    pub fn flipped(&self, horizontal: bool, vertical: bool) -> Image {
+        let mut region = self.region;
+        if horizontal {
+            region.pos.x += region.size.x;
+            region.size.x = -region.size.x;
+        }
+        if vertical {
+            region.pos.y += region.size.y;
+            region.size.y = -region.size.y;
+        }
+        Image {
+            texture: self.texture.clone(),
+            region,
+        }
+    }
     /// Create a view into an existing image, using texture coordinates (from 0.0 to 1.0 in both dimensions)
     pub fn subimage(&self, rect: Rectangle) -> Image {
         let img = Image {
@@ -121,14 +193,40 @@ impl Image {
 
 impl ImageLoader {
     pub fn register(gl: WebGlRenderingContext, texture_config: TextureConfig) {
-        let activity = nuts::new_domained_activity(Self { gl, texture_config }, &Domain::Frame);
+        let loader = Self {
+            gl,
+            texture_config,
+            textures: Vec::new(),
+        };
+        let activity = nuts::new_domained_activity(loader, &Domain::Frame);
         activity.subscribe(move |a, msg: &BindTextureMessage| {
-            if let BindTexturePayload::Request(el) = msg.payload.take() {
-                if let Some(data) = Texture2D::new(&a.gl, &el, &a.texture_config).nuts_check() {
-                    msg.payload.replace(BindTexturePayload::Response(data));
+            let texture = match msg.payload.take() {
+                BindTexturePayload::Request(el, config) => {
+                    Texture2D::new(&a.gl, &el, &config.unwrap_or(a.texture_config))
+                }
+                BindTexturePayload::RequestRgba(width, height, rgba, config) => {
+                    let config = config.unwrap_or(a.texture_config);
+                    Texture2D::from_rgba(&a.gl, width, height, rgba, &config)
                 }
+                _ => return,
+            };
+            if let Some(data) = texture.nuts_check() {
+                a.textures.retain(WeakTexture2D::is_alive);
+                a.textures.push(data.downgrade());
+                msg.payload.replace(BindTexturePayload::Response(data));
+            }
+        });
+        activity.subscribe(|a, _msg: &RestoreTextures| a.restore_textures());
+    }
+    fn restore_textures(&mut self) {
+        let gl = &self.gl;
+        self.textures.retain(|weak| match weak.upgrade() {
+            Some(texture) => {
+                texture.restore(gl).nuts_check();
+                true
             }
-        })
+            None => false,
+        });
     }
 }
 
@@ -1,11 +1,12 @@
 use web_sys::WebGlRenderingContext;
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct TextureConfig {
     pub(crate) magnification_filter: MagnificationFilter,
     pub(crate) minification_filter: MinificationFilter,
     pub(crate) mipmap_level: MipmapLevel,
     pub(crate) color_format: ColorFormat,
+    pub(crate) wrap_mode: WrapMode,
 }
 
 impl TextureConfig {
@@ -50,6 +51,25 @@ impl TextureConfig {
         self.color_format = ColorFormat::RGB;
         self
     }
+    /// Define what is sampled outside of the texture, see `DisplayPaint::with_texture_repeat`.
+    ///
+    /// WebGL 1.0 can only repeat textures with power-of-two dimensions.
+    /// Other images are scaled up to the next power of two when uploaded with a repeating wrap mode.
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+}
+
+/// Defines how textures are sampled outside of their area, which is visible when texture coordinates are scaled up.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WrapMode {
+    /// The pixels at the edge are extended. (default)
+    ClampToEdge,
+    /// The texture is tiled.
+    Repeat,
+    /// The texture is tiled, with every second repetition mirrored.
+    MirroredRepeat,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -116,6 +136,19 @@ impl MinificationFilter {
         }) as i32
     }
 }
+impl WrapMode {
+    pub(crate) fn webgl_num(&self) -> i32 {
+        (match self {
+            WrapMode::ClampToEdge => WebGlRenderingContext::CLAMP_TO_EDGE,
+            WrapMode::Repeat => WebGlRenderingContext::REPEAT,
+            WrapMode::MirroredRepeat => WebGlRenderingContext::MIRRORED_REPEAT,
+        }) as i32
+    }
+    /// Non-power-of-two textures only support `CLAMP_TO_EDGE` in WebGL 1.0
+    pub(crate) fn requires_power_of_two(&self) -> bool {
+        *self != WrapMode::ClampToEdge
+    }
+}
 impl ColorFormat {
     pub(crate) fn webgl_num(&self) -> i32 {
         (match self {
@@ -132,6 +165,7 @@ impl Default for TextureConfig {
             minification_filter: MinificationFilter::Linear,
             mipmap_level: MipmapLevel::Single,
             color_format: ColorFormat::RGBA,
+            wrap_mode: WrapMode::ClampToEdge,
         }
     }
 }
@@ -0,0 +1,426 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::quicksilver_compat::{Color, Shape};
+use crate::{Image, ImageDesc, Paint, Rectangle, Vector};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY);
+
+/// An orthogonal grid of tiles with any number of layers, as stored in the asset library.
+///
+/// Load it from a map created with [Tiled](https://www.mapeditor.org/) using a `TilemapDesc`.
+/// World coordinates are in pixels of the map, with (0,0) at the top left corner of the map.
+#[derive(Clone, Debug)]
+pub struct Tilemap {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) tile_size: Vector,
+    /// Sorted by first GID
+    pub(crate) tilesets: Vec<Tileset>,
+    /// In drawing order, from bottom to top
+    pub(crate) layers: Vec<TileLayer>,
+    pub(crate) object_layers: Vec<ObjectLayer>,
+}
+
+/// A sprite sheet of equally sized tiles, referenced by global tile IDs starting at `first_gid`
+#[derive(Clone, Debug)]
+pub struct Tileset {
+    pub(crate) name: String,
+    pub(crate) first_gid: u32,
+    pub(crate) image: ImageDesc,
+    pub(crate) tile_size: Vector,
+    pub(crate) columns: u32,
+    pub(crate) tile_count: u32,
+    pub(crate) margin: f32,
+    pub(crate) spacing: f32,
+}
+
+/// A grid of tiles covering the map
+#[derive(Clone, Debug)]
+pub struct TileLayer {
+    pub(crate) name: String,
+    /// Global tile IDs including flip flags, row by row. 0 is an empty cell.
+    pub(crate) tiles: Vec<u32>,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) offset: Vector,
+    pub(crate) visible: bool,
+    pub(crate) opacity: f32,
+}
+
+/// A layer of free-form objects, for example spawn points or trigger areas
+#[derive(Clone, Debug)]
+pub struct ObjectLayer {
+    pub(crate) name: String,
+    pub(crate) objects: Vec<MapObject>,
+}
+
+/// An object placed on the map in Tiled
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    /// Called type in older versions of Tiled
+    pub class: String,
+    /// In world coordinates. Points have a size of zero.
+    pub area: Rectangle,
+    pub properties: HashMap<String, PropertyValue>,
+}
+
+/// Value of a custom property in Tiled
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Strings, but also colors, files and other property types
+    String(String),
+}
+
+/// A single non-empty cell of a tile layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// Global tile ID, identifying tileset and tile within it
+    pub gid: u32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    /// Diagonal flips (rotations) are recognized but not rendered
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    /// Decode a global tile ID with flip flags, returns None for empty cells
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        let gid = raw & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+        Some(Self {
+            gid,
+            flip_horizontal: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_vertical: raw & FLIPPED_VERTICALLY != 0,
+            flip_diagonal: raw & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+}
+
+impl Tilemap {
+    /// Number of columns and rows
+    pub fn size_in_tiles(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    /// Size of a grid cell in world coordinates
+    pub fn tile_size(&self) -> Vector {
+        self.tile_size
+    }
+    /// Size of the entire map in world coordinates
+    pub fn world_size(&self) -> Vector {
+        (
+            self.width as f32 * self.tile_size.x,
+            self.height as f32 * self.tile_size.y,
+        )
+            .into()
+    }
+    pub fn tilesets(&self) -> &[Tileset] {
+        &self.tilesets
+    }
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+    pub fn object_layers(&self) -> &[ObjectLayer] {
+        &self.object_layers
+    }
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|l| l.name == name)
+    }
+    /// Column and row of the cell at the world coordinate, if it is on the map
+    pub fn tile_coordinates(&self, world: impl Into<Vector>) -> Option<(usize, usize)> {
+        cell_at(world.into(), self.tile_size, self.width, self.height)
+    }
+    /// The area covered by a cell, in world coordinates
+    pub fn tile_area(&self, column: usize, row: usize) -> Rectangle {
+        Rectangle::new(
+            (
+                column as f32 * self.tile_size.x,
+                row as f32 * self.tile_size.y,
+            ),
+            self.tile_size,
+        )
+    }
+    /// The tile at a world coordinate on the named layer, considering the layer offset
+    pub fn tile_at(&self, layer: &str, world: impl Into<Vector>) -> Option<Tile> {
+        let layer = self.layer(layer)?;
+        let (column, row) = cell_at(
+            world.into() - layer.offset,
+            self.tile_size,
+            layer.width,
+            layer.height,
+        )?;
+        layer.tile(column, row)
+    }
+    /// The tileset that contains the global tile ID
+    pub fn tileset_of(&self, gid: u32) -> Option<&Tileset> {
+        self.tilesets
+            .iter()
+            .rev()
+            .find(|ts| ts.first_gid <= gid)
+            .filter(|ts| gid - ts.first_gid < ts.tile_count)
+    }
+    /// Columns and rows of a layer that overlap the camera area (in world coordinates)
+    pub fn visible_cells(
+        &self,
+        layer: &TileLayer,
+        camera: &Rectangle,
+    ) -> (Range<usize>, Range<usize>) {
+        // Tiles larger than the grid extend upwards and to the right
+        let max_tile = self
+            .tilesets
+            .iter()
+            .fold(self.tile_size, |max, ts| max.max(ts.tile_size));
+        let overhang = (max_tile - self.tile_size).max(Vector::ZERO);
+        let area = Rectangle::new(
+            camera.pos - layer.offset - (overhang.x, 0.0).into(),
+            camera.size + overhang,
+        );
+        (
+            cell_range(area.x(), area.width(), self.tile_size.x, layer.width),
+            cell_range(area.y(), area.height(), self.tile_size.y, layer.height),
+        )
+    }
+    /// Everything to draw for the tiles within the camera area, in drawing order
+    pub(crate) fn visible_tiles(&self, camera: &Rectangle) -> Vec<VisibleTile> {
+        let mut out = Vec::new();
+        for layer in self.layers.iter().filter(|l| l.visible && l.opacity > 0.0) {
+            let (columns, rows) = self.visible_cells(layer, camera);
+            for row in rows {
+                for column in columns.clone() {
+                    let tile = match layer.tile(column, row) {
+                        Some(tile) => tile,
+                        None => continue,
+                    };
+                    let tileset = match self.tileset_of(tile.gid) {
+                        Some(ts) => ts,
+                        None => continue,
+                    };
+                    // Like in Tiled, tiles are aligned to the bottom left corner of their cell
+                    let cell = self.tile_area(column, row);
+                    let area = Rectangle::new(
+                        (
+                            cell.x() + layer.offset.x,
+                            cell.y() + cell.height() - tileset.tile_size.y + layer.offset.y,
+                        ),
+                        tileset.tile_size,
+                    );
+                    out.push(VisibleTile {
+                        area,
+                        image: tileset.image,
+                        region: tileset.tile_region(tile.gid - tileset.first_gid),
+                        tile,
+                        opacity: layer.opacity,
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Tileset {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn first_gid(&self) -> u32 {
+        self.first_gid
+    }
+    pub fn image(&self) -> ImageDesc {
+        self.image
+    }
+    /// Area of a tile in the tileset image, in texels
+    pub fn tile_region(&self, local_id: u32) -> Rectangle {
+        let columns = self.columns.max(1);
+        let column = (local_id % columns) as f32;
+        let row = (local_id / columns) as f32;
+        Rectangle::new(
+            (
+                self.margin + column * (self.tile_size.x + self.spacing),
+                self.margin + row * (self.tile_size.y + self.spacing),
+            ),
+            self.tile_size,
+        )
+    }
+}
+
+impl TileLayer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+    /// The tile in a cell, None if empty or outside the layer
+    pub fn tile(&self, column: usize, row: usize) -> Option<Tile> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        self.tiles
+            .get(row * self.width + column)
+            .and_then(|raw| Tile::from_raw(*raw))
+    }
+}
+
+impl ObjectLayer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn objects(&self) -> &[MapObject] {
+        &self.objects
+    }
+    /// All objects that contain the world coordinate
+    pub fn objects_at(&self, world: impl Into<Vector>) -> impl Iterator<Item = &MapObject> {
+        let world = world.into();
+        self.objects.iter().filter(move |o| o.area.contains(world))
+    }
+}
+
+/// A tile that is drawn, after culling
+pub(crate) struct VisibleTile {
+    /// In world coordinates
+    pub area: Rectangle,
+    pub image: ImageDesc,
+    /// In texels of the tileset image
+    pub region: Rectangle,
+    pub tile: Tile,
+    pub opacity: f32,
+}
+
+/// Paint for tiles, which can be flipped and have the opacity of the layer
+pub(crate) struct TilePaint {
+    image: Image,
+    color: Color,
+}
+
+impl TilePaint {
+    pub(crate) fn new(sheet: &Image, visible: &VisibleTile) -> Self {
+        Self {
+            image: sheet
+                .subimage_texels(visible.region)
+                .flipped(visible.tile.flip_horizontal, visible.tile.flip_vertical),
+            color: Color::WHITE.with_alpha(visible.opacity),
+        }
+    }
+}
+
+impl Paint for TilePaint {
+    fn paint_image(&self) -> Option<&Image> {
+        Some(&self.image)
+    }
+    fn paint_color(&self) -> Option<Color> {
+        Some(self.color)
+    }
+}
+
+fn cell_at(pos: Vector, tile_size: Vector, width: usize, height: usize) -> Option<(usize, usize)> {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return None;
+    }
+    let column = (pos.x / tile_size.x) as usize;
+    let row = (pos.y / tile_size.y) as usize;
+    if column < width && row < height {
+        Some((column, row))
+    } else {
+        None
+    }
+}
+
+fn cell_range(start: f32, length: f32, cell_size: f32, count: usize) -> Range<usize> {
+    let first = (start / cell_size).floor().max(0.0) as usize;
+    let end = ((start + length) / cell_size).ceil().max(0.0) as usize;
+    first.min(count)..end.min(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map() -> Tilemap {
+        Tilemap {
+            width: 4,
+            height: 3,
+            tile_size: (16, 16).into(),
+            tilesets: vec![Tileset {
+                name: "terrain".to_owned(),
+                first_gid: 1,
+                image: ImageDesc::from_path("terrain.png"),
+                tile_size: (16, 16).into(),
+                columns: 8,
+                tile_count: 64,
+                margin: 1.0,
+                spacing: 2.0,
+            }],
+            layers: vec![TileLayer {
+                name: "ground".to_owned(),
+                tiles: vec![1, 2, 0, 0, 0, 10 | FLIPPED_HORIZONTALLY, 0, 0, 0, 0, 0, 65],
+                width: 4,
+                height: 3,
+                offset: Vector::ZERO,
+                visible: true,
+                opacity: 1.0,
+            }],
+            object_layers: vec![],
+        }
+    }
+
+    #[test]
+    fn decode_gid() {
+        assert_eq!(Tile::from_raw(0), None);
+        assert_eq!(Tile::from_raw(FLIPPED_VERTICALLY), None);
+        let tile = Tile::from_raw(7 | FLIPPED_HORIZONTALLY | FLIPPED_DIAGONALLY).unwrap();
+        assert_eq!(tile.gid, 7);
+        assert!(tile.flip_horizontal && tile.flip_diagonal && !tile.flip_vertical);
+    }
+
+    #[test]
+    fn tile_queries() {
+        let map = map();
+        assert_eq!(map.tile_coordinates((17, 40)), Some((1, 2)));
+        assert_eq!(map.tile_coordinates((64, 0)), None);
+        assert_eq!(map.tile_at("ground", (20, 20)).unwrap().gid, 10);
+        assert_eq!(map.tile_at("ground", (40, 0)), None);
+        assert_eq!(map.tile_at("sky", (0, 0)), None);
+        // 65 is beyond the tileset
+        assert!(map.tileset_of(65).is_none());
+        assert_eq!(map.tileset_of(10).unwrap().name(), "terrain");
+    }
+
+    #[test]
+    fn tileset_regions() {
+        let map = map();
+        let ts = &map.tilesets()[0];
+        assert_eq!(ts.tile_region(0), Rectangle::new((1, 1), (16, 16)));
+        assert_eq!(ts.tile_region(9), Rectangle::new((19, 19), (16, 16)));
+    }
+
+    #[test]
+    fn culling() {
+        let map = map();
+        let layer = &map.layers()[0];
+        let (columns, rows) = map.visible_cells(layer, &Rectangle::new((10, 10), (20, 4)));
+        assert_eq!((columns, rows), (0..2, 0..1));
+        let (columns, rows) = map.visible_cells(layer, &Rectangle::new((-100, -100), (1000, 1000)));
+        assert_eq!((columns, rows), (0..4, 0..3));
+
+        let visible = map.visible_tiles(&Rectangle::new((0, 0), (32, 32)));
+        let gids: Vec<u32> = visible.iter().map(|t| t.tile.gid).collect();
+        assert_eq!(gids, vec![1, 2, 10]);
+        assert_eq!(visible[2].area, Rectangle::new((16, 16), (16, 16)));
+    }
+}
@@ -1,7 +1,7 @@
 //! Glue module between paddle.js and the Rust side of Paddle
 
 use wasm_bindgen::prelude::{wasm_bindgen, Closure};
-use web_sys::HtmlElement;
+use web_sys::{Element, HtmlElement};
 
 use crate::input::browser_pointer_events::*;
 use crate::*;
@@ -68,6 +68,16 @@ extern "C" {
 
     #[wasm_bindgen(js_name = supportsPointerEvents)]
     pub fn supports_pointer_events() -> bool;
+
+    #[wasm_bindgen(js_name = registerResizeListener)]
+    pub fn register_resize_listener(element: &Element, callback: &Closure<dyn FnMut()>);
+
+    #[wasm_bindgen(js_name = registerWebGlContextListeners)]
+    pub fn register_webgl_context_listeners(
+        canvas: &Element,
+        lost: &Closure<dyn FnMut()>,
+        restored: &Closure<dyn FnMut()>,
+    );
 }
 
 #[wasm_bindgen(module = "/src/js/enums.js")]
@@ -84,4 +84,26 @@ export class PaddleJsContext {
 
 export function supportsPointerEvents() {
     return window.PointerEvent !== undefined;
-}
\ No newline at end of file
+}
+
+// Calls the callback whenever the window, the screen orientation, or the
+// observed element changes its size.
+// @param element: Element
+//  Typically the parent of the canvas. Only observed in browsers supporting ResizeObserver.
+export function registerResizeListener(element, callback) {
+    window.addEventListener("resize", () => callback());
+    window.addEventListener("orientationchange", () => callback());
+    if (window.ResizeObserver !== undefined) {
+        const observer = new ResizeObserver(() => callback());
+        observer.observe(element);
+    }
+}
+
+export function registerWebGlContextListeners(canvas, lostCallback, restoredCallback) {
+    canvas.addEventListener("webglcontextlost", (event) => {
+        // Without this, the browser will not try to restore the context
+        event.preventDefault();
+        lostCallback();
+    }, false);
+    canvas.addEventListener("webglcontextrestored", () => restoredCallback(), false);
+}
@@ -3,6 +3,7 @@
 //!
 #![cfg_attr(feature = "nightly", feature(const_fn_floating_point_arithmetic))]
 
+pub use lyon;
 pub use nuts;
 
 #[macro_use]
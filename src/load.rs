@@ -1,10 +1,19 @@
+mod asset_keys;
 pub mod asset_library;
+mod bmfont;
 pub mod fetch;
 pub mod load_scheduler;
+mod svg;
+mod tiled;
 
+pub(crate) use asset_keys::{intern, resolve_path};
 pub use asset_library::*;
+pub(crate) use bmfont::parse_bmfont;
 pub use fetch::*;
 pub use load_scheduler::*;
+pub(crate) use svg::{parse_color, TessellatedSvg};
+pub use svg::{SvgDrawing, SvgPart};
+pub(crate) use tiled::{parse_tilemap, JsonValue};
 
 pub(crate) mod load_activity;
 pub(crate) use load_activity::*;
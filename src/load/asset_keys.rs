@@ -0,0 +1,67 @@
+//! Helpers for keys of assets that are only known at runtime, such as paths found inside loaded files.
+//!
+//! Asset descriptors are `Copy` and refer to their assets by `&'static str` keys.
+//! Runtime keys are interned here: each distinct key is allocated once and reused by later loads, so reloading assets does not grow memory.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+thread_local! {
+    static KEYS: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+/// Returns a static string equal to `key`, allocating it only the first time it is seen.
+pub(crate) fn intern(key: &str) -> &'static str {
+    KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        match keys.get(key) {
+            Some(interned) => interned,
+            None => {
+                let interned: &'static str = Box::leak(key.to_owned().into_boxed_str());
+                keys.insert(interned);
+                interned
+            }
+        }
+    })
+}
+
+/// Resolves a path found in a loaded file relative to the directory of that file, interned as asset key.
+///
+/// Absolute paths and URLs are used as they are.
+pub(crate) fn resolve_path(file_path: &str, relative: &str) -> &'static str {
+    if relative.starts_with('/') || relative.contains("://") {
+        return intern(relative);
+    }
+    match file_path.rsplit_once('/') {
+        Some((dir, _)) => intern(&format!("{}/{}", dir, relative)),
+        None => intern(relative),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_once() {
+        let a = intern(&format!("{}#{}", "drawing.svg", 1));
+        let b = intern(&format!("{}#{}", "drawing.svg", 1));
+        assert_eq!(a, "drawing.svg#1");
+        assert!(std::ptr::eq(a, b));
+        assert!(!std::ptr::eq(a, intern("drawing.svg#2")));
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(resolve_path("a/b/map.json", "tiles.png"), "a/b/tiles.png");
+        assert_eq!(resolve_path("map.json", "tiles.png"), "tiles.png");
+        assert_eq!(
+            resolve_path("a/map.json", "https://example.com/t.png"),
+            "https://example.com/t.png"
+        );
+        assert!(std::ptr::eq(
+            resolve_path("fonts/a.fnt", "a_0.png"),
+            resolve_path("fonts/b.fnt", "a_0.png")
+        ));
+    }
+}
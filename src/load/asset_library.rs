@@ -13,8 +13,8 @@ pub use asset_loading::*;
 use nuts::DomainState;
 
 use crate::{
-    AbstractMesh, ComplexShape, Context, DisplayPaint, DisplayTessellate, ErrorMessage, Image,
-    NutsCheck, PaddleResult, Rectangle, Tessellate,
+    AbstractMesh, BitmapFont, ComplexShape, Context, DisplayPaint, DisplayTessellate, ErrorMessage,
+    Image, NutsCheck, PaddleResult, Rectangle, SpriteAnimation, SvgDrawing, Tessellate, Tilemap,
 };
 use std::collections::HashMap;
 
@@ -22,8 +22,12 @@ use std::collections::HashMap;
 #[derive(Default)]
 pub struct AssetLibrary {
     images: HashMap<ImageDesc, Image>,
-    // animations: Vec<AnimatedObject>,
+    animations: HashMap<&'static str, SpriteAnimation>,
     shapes: HashMap<ShapeDesc, ComplexShape>,
+    svgs: HashMap<SvgDesc, SvgDrawing>,
+    tilemaps: HashMap<TilemapDesc, Tilemap>,
+    fonts: HashMap<BitmapFontDesc, BitmapFont>,
+    text_icons: HashMap<&'static str, ImageDesc>,
 }
 
 impl AssetLibrary {
@@ -33,6 +37,35 @@ impl AssetLibrary {
     pub fn add_shape(&mut self, desc: ShapeDesc, shape: ComplexShape) {
         self.shapes.insert(desc, shape);
     }
+    /// Remove an image from the library and return it.
+    ///
+    /// The GPU texture is deleted once no other `Image` refers to it anymore, which includes subimages and animation frames.
+    pub fn unload_image(&mut self, desc: ImageDesc) -> Option<Image> {
+        self.images.remove(&desc)
+    }
+    /// Remove a shape from the library and return it
+    pub fn unload_shape(&mut self, desc: ShapeDesc) -> Option<ComplexShape> {
+        self.shapes.remove(&desc)
+    }
+    pub fn add_svg(&mut self, desc: SvgDesc, drawing: SvgDrawing) {
+        self.svgs.insert(desc, drawing);
+    }
+    pub fn add_tilemap(&mut self, desc: TilemapDesc, map: Tilemap) {
+        self.tilemaps.insert(desc, map);
+    }
+    pub fn add_font(&mut self, desc: BitmapFontDesc, font: BitmapFont) {
+        self.fonts.insert(desc, font);
+    }
+    /// Make an image available as inline icon in rich text, referred to as `[img=name]`
+    pub fn add_text_icon(&mut self, name: &'static str, image: ImageDesc) {
+        self.text_icons.insert(name, image);
+    }
+    pub fn text_icon(&self, name: &str) -> Option<ImageDesc> {
+        self.text_icons.get(name).cloned()
+    }
+    pub fn add_animation(&mut self, desc: AnimationDesc, animation: SpriteAnimation) {
+        self.animations.insert(desc.name, animation);
+    }
     pub(crate) fn from_domain(domain: &mut DomainState) -> &mut Self {
         let context = domain.get_mut::<Context>();
         context.display.full_mut().asset_library()
@@ -45,6 +78,38 @@ impl AssetLibrary {
             ))
         })
     }
+    pub fn lookup_animation(&self, desc: AnimationDesc) -> PaddleResult<&SpriteAnimation> {
+        self.animations.get(desc.name).ok_or_else(|| {
+            ErrorMessage::technical(format!(
+                "Attempted to draw animation that has not been defined: {}",
+                desc.name
+            ))
+        })
+    }
+    pub fn lookup_tilemap(&self, desc: TilemapDesc) -> PaddleResult<&Tilemap> {
+        self.tilemaps.get(&desc).ok_or_else(|| {
+            ErrorMessage::technical(format!(
+                "Attempted to use tilemap that has not been loaded: {:?}",
+                desc
+            ))
+        })
+    }
+    pub fn lookup_font(&self, desc: BitmapFontDesc) -> PaddleResult<&BitmapFont> {
+        self.fonts.get(&desc).ok_or_else(|| {
+            ErrorMessage::technical(format!(
+                "Attempted to use bitmap font that has not been loaded: {:?}",
+                desc
+            ))
+        })
+    }
+    pub fn lookup_svg(&self, desc: SvgDesc) -> PaddleResult<&SvgDrawing> {
+        self.svgs.get(&desc).ok_or_else(|| {
+            ErrorMessage::technical(format!(
+                "Attempted to draw SVG that has not been loaded: {:?}",
+                desc
+            ))
+        })
+    }
 }
 
 impl DisplayPaint for ImageDesc {
@@ -53,6 +118,15 @@ impl DisplayPaint for ImageDesc {
     }
 }
 
+impl DisplayPaint for AnimationDesc {
+    fn image<'a>(&'a self, assets: &'a AssetLibrary) -> Option<&'a Image> {
+        assets
+            .lookup_animation(*self)
+            .nuts_check()?
+            .frame(self.clip, self.time_ms)
+    }
+}
+
 impl DisplayTessellate for ShapeDesc {
     fn tessellate(&self, assets: &AssetLibrary, mesh: &mut AbstractMesh) {
         if let Some(shape) = assets.lookup_shape(*self).nuts_check() {
@@ -1,11 +1,20 @@
 use crate::{
-    ComplexShape, Image, JsError, LoadActivity, LoadedImageAsset, LoadedShapeAsset, PaddleResult,
+    load_file, parse_bmfont, parse_tilemap, AnimationState, ComplexShape, ErrorMessage, Image,
+    JsError, JsonValue, LoadActivity, LoadedAnimationAsset, LoadedBitmapFontAsset,
+    LoadedImageAsset, LoadedShapeAsset, LoadedSvgAsset, LoadedTilemapAsset, NutsCheck,
+    PaddleResult, SpriteAnimation, TessellatedSvg, TextureConfig,
 };
 
 /// Image descriptor: Names an image is loaded and can be used for drawing.
-#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+///
+/// Descriptors are identified by their path alone, the texture configuration only applies when loading.
+/// Hence descriptors of the same path with different configurations name the same image in the asset library,
+/// which holds one texture per path, created with the configuration of the descriptor that was loaded last.
+#[derive(Clone, Copy, Debug)]
 pub struct ImageDesc {
     path: &'static str,
+    /// Overrides the global texture configuration for this image
+    config: Option<TextureConfig>,
 }
 
 /// Shape descriptor: Names a complex shape and can be used for drawing.
@@ -14,16 +23,54 @@ pub struct ShapeDesc {
     pub(crate) name: &'static str,
 }
 
+/// SVG descriptor: Names a vector graphic that is loaded from an SVG file and can be used for drawing.
+///
+/// Each filled or stroked element of the file is also available as a `ShapeDesc`, see `SvgDrawing`.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct SvgDesc {
+    path: &'static str,
+}
+
+/// Tilemap descriptor: Names a map that is loaded from a Tiled JSON file and can be used for drawing.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct TilemapDesc {
+    path: &'static str,
+}
+
+/// Bitmap font descriptor: Names a font that is loaded from a BMFont file (text format) and can be used to draw `BitmapText`.
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+pub struct BitmapFontDesc {
+    path: &'static str,
+}
+
+/// Animation descriptor: Names a sprite animation and can be used for drawing.
+///
+/// As paint, it shows the frame of the selected clip after playing it for the given time.
+/// Use `at` with the time from `DrawWorld::time_ms` to keep it running, or `with_state` with an explicit `AnimationState`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationDesc {
+    pub(crate) name: &'static str,
+    pub(crate) clip: Option<&'static str>,
+    pub(crate) time_ms: f64,
+}
+
 impl ImageDesc {
     pub const fn from_path(path: &'static str) -> Self {
-        Self { path }
+        Self { path, config: None }
+    }
+    /// Use a specific texture configuration for this image, for example to repeat it with `WrapMode::Repeat`.
+    pub const fn with_texture_config(mut self, config: TextureConfig) -> Self {
+        self.config = Some(config);
+        self
     }
     /// Creates a future that loads the specified image and hands it to the asset library.
     /// Usually an `AssetBundle` is the better choice rather than calling this function directly.
     pub async fn load(&self) -> PaddleResult<()> {
-        Image::load(self.path)
-            .await
-            .map(|img| nuts::send_to::<LoadActivity, _>(LoadedImageAsset { desc: *self, img }))
+        let img = match self.config {
+            Some(config) => Image::load_with_config(self.path, config).await,
+            None => Image::load(self.path).await,
+        };
+        img.map(|img| nuts::send_to::<LoadActivity, _>(LoadedImageAsset { desc: *self, img }))
     }
 
     /// Use if you've somehow gotten a PNG without reading it from a URL.
@@ -47,6 +94,18 @@ impl ImageDesc {
     }
 }
 
+impl PartialEq for ImageDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+impl Eq for ImageDesc {}
+impl std::hash::Hash for ImageDesc {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
 impl ShapeDesc {
     pub const fn named(name: &'static str) -> Self {
         Self { name }
@@ -56,3 +115,119 @@ impl ShapeDesc {
         nuts::send_to::<LoadActivity, _>(LoadedShapeAsset { desc: *self, shape })
     }
 }
+
+impl AnimationDesc {
+    pub const fn named(name: &'static str) -> Self {
+        Self {
+            name,
+            clip: None,
+            time_ms: 0.0,
+        }
+    }
+    /// Define the animation and store it in the asset library.
+    pub fn define(&self, animation: SpriteAnimation) {
+        nuts::send_to::<LoadActivity, _>(LoadedAnimationAsset {
+            desc: *self,
+            animation,
+        })
+    }
+    /// Play the named clip instead of the first clip of the animation
+    pub const fn with_clip(mut self, clip: &'static str) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+    /// Show the frame after playing the clip for the given time in ms.
+    pub const fn at(mut self, time_ms: f64) -> Self {
+        self.time_ms = time_ms;
+        self
+    }
+    /// Show the frame of the clip currently played in the state.
+    pub fn with_state(mut self, state: &AnimationState, now_ms: f64) -> Self {
+        self.clip = state.clip().or(self.clip);
+        self.time_ms = state.elapsed_ms(now_ms);
+        self
+    }
+}
+
+impl SvgDesc {
+    pub const fn from_path(path: &'static str) -> Self {
+        Self { path }
+    }
+    /// Creates a future that loads and tessellates the specified SVG file and hands it to the asset library.
+    /// Usually an `AssetBundle` is the better choice rather than calling this function directly.
+    pub async fn load(&self) -> PaddleResult<()> {
+        let data = load_file(self.path).await?;
+        let text = String::from_utf8(data).map_err(|e| {
+            ErrorMessage::technical(format!("SVG file {} is not UTF-8: {}", self.path, e))
+        })?;
+        let mut svg = TessellatedSvg::from_text(&text)?;
+        for skipped in svg.skipped.drain(..) {
+            Err::<(), _>(skipped).nuts_check();
+        }
+        let (drawing, shapes) = svg.into_asset(self.path);
+        nuts::send_to::<LoadActivity, _>(LoadedSvgAsset {
+            desc: *self,
+            drawing,
+            shapes,
+        });
+        Ok(())
+    }
+}
+
+impl BitmapFontDesc {
+    pub const fn from_path(path: &'static str) -> Self {
+        Self { path }
+    }
+    /// Creates a future that loads the font description and all its atlas pages, then hands them to the asset library.
+    /// Usually an `AssetBundle` is the better choice rather than calling this function directly.
+    pub async fn load(&self) -> PaddleResult<()> {
+        let data = load_file(self.path).await?;
+        let text = String::from_utf8(data).map_err(|e| {
+            ErrorMessage::technical(format!("Bitmap font {} is not UTF-8: {}", self.path, e))
+        })?;
+        let font = parse_bmfont(&text, self.path)?;
+        for page in &font.pages {
+            page.load().await?;
+        }
+        nuts::send_to::<LoadActivity, _>(LoadedBitmapFontAsset { desc: *self, font });
+        Ok(())
+    }
+}
+
+impl TilemapDesc {
+    pub const fn from_path(path: &'static str) -> Self {
+        Self { path }
+    }
+    /// Creates a future that loads the specified map and the images of all its tilesets, then hands them to the asset library.
+    /// Usually an `AssetBundle` is the better choice rather than calling this function directly.
+    pub async fn load(&self) -> PaddleResult<()> {
+        let data = load_file(self.path).await?;
+        let text = String::from_utf8(data).map_err(|e| {
+            ErrorMessage::technical(format!("Tiled map {} is not UTF-8: {}", self.path, e))
+        })?;
+        let map = parse_tilemap(&JsonValue::parse(&text)?, self.path)?;
+        for tileset in map.tilesets() {
+            tileset.image().load().await?;
+        }
+        nuts::send_to::<LoadActivity, _>(LoadedTilemapAsset { desc: *self, map });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WrapMode;
+    use std::collections::HashSet;
+
+    #[test]
+    fn image_desc_identified_by_path() {
+        let plain = ImageDesc::from_path("tiles.png");
+        let repeated =
+            plain.with_texture_config(TextureConfig::default().with_wrap_mode(WrapMode::Repeat));
+        assert_eq!(plain, repeated);
+        let set: HashSet<_> = [plain, repeated].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        assert_ne!(plain, ImageDesc::from_path("other.png"));
+    }
+}
@@ -3,11 +3,22 @@ use std::{
     sync::atomic::{AtomicBool, AtomicU16, Ordering},
 };
 
-use crate::{ImageDesc, LoadScheduler, NutsCheck};
+use crate::{BitmapFontDesc, ImageDesc, LoadScheduler, NutsCheck, SvgDesc, TilemapDesc};
 
 /// Helper struct to load multiple assets in parallel and track their progress.
 pub struct AssetBundle {
     images: Vec<ImageDesc>,
+    svgs: Vec<SvgDesc>,
+    tilemaps: Vec<TilemapDesc>,
+    fonts: Vec<BitmapFontDesc>,
+}
+
+/// Any asset that can be part of a bundle
+enum BundledAsset {
+    Image(ImageDesc),
+    Svg(SvgDesc),
+    Tilemap(TilemapDesc),
+    Font(BitmapFontDesc),
 }
 
 #[derive(Clone)]
@@ -21,36 +32,64 @@ struct AssetLoadingTrackerData {
 
 impl AssetBundle {
     pub fn new() -> Self {
-        Self { images: Vec::new() }
+        Self {
+            images: Vec::new(),
+            svgs: Vec::new(),
+            tilemaps: Vec::new(),
+            fonts: Vec::new(),
+        }
     }
     pub fn add_images(&mut self, images: &[ImageDesc]) {
         self.images.extend(images);
     }
+    pub fn add_svgs(&mut self, svgs: &[SvgDesc]) {
+        self.svgs.extend(svgs);
+    }
+    /// Add Tiled maps, their tileset images are loaded with them
+    pub fn add_tilemaps(&mut self, tilemaps: &[TilemapDesc]) {
+        self.tilemaps.extend(tilemaps);
+    }
+    /// Add bitmap fonts, their atlas pages are loaded with them
+    pub fn add_fonts(&mut self, fonts: &[BitmapFontDesc]) {
+        self.fonts.extend(fonts);
+    }
     /// Loads all items in the bundle and insert them into the asset library available to all display frames.
     pub fn load(self) -> AssetLoadingTracker {
         let tracker = AssetLoadingTracker(Rc::new(AssetLoadingTrackerData {
-            total: self.images.len() as u16,
+            total: (self.images.len() + self.svgs.len() + self.tilemaps.len() + self.fonts.len())
+                as u16,
             loaded: AtomicU16::new(0),
             had_error: AtomicBool::new(false),
         }));
 
-        let mut image_futures = Vec::new();
-        image_futures.reserve(self.images.len());
-        for desc in self.images {
+        let assets = self
+            .images
+            .into_iter()
+            .map(BundledAsset::Image)
+            .chain(self.svgs.into_iter().map(BundledAsset::Svg))
+            .chain(self.tilemaps.into_iter().map(BundledAsset::Tilemap))
+            .chain(self.fonts.into_iter().map(BundledAsset::Font));
+        let mut futures = Vec::new();
+        for asset in assets {
             let tracker = tracker.clone();
             let future = async move {
-                let result = desc.load().await;
+                let result = match asset {
+                    BundledAsset::Image(desc) => desc.load().await,
+                    BundledAsset::Svg(desc) => desc.load().await,
+                    BundledAsset::Tilemap(desc) => desc.load().await,
+                    BundledAsset::Font(desc) => desc.load().await,
+                };
                 if result.is_err() {
                     tracker.0.had_error.store(true, Ordering::SeqCst);
                 }
                 result.nuts_check();
                 tracker.0.loaded.fetch_add(1, Ordering::SeqCst);
             };
-            image_futures.push(future);
+            futures.push(future);
         }
 
         let mut scheduler = LoadScheduler::new();
-        scheduler.register_vec(image_futures, "Loading Images");
+        scheduler.register_vec(futures, "Loading Assets");
         scheduler.track_loading();
 
         tracker
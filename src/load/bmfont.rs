@@ -0,0 +1,214 @@
+//! Parser for bitmap fonts in the text format of BMFont (AngelCode).
+//!
+//! Each line starts with a tag, followed by `key=value` pairs. Values with spaces are quoted.
+//! Distance field fonts are recognized by the `distanceField` line written by msdf-bmfont.
+//! The XML and binary variants of the format are not supported.
+
+use std::collections::HashMap;
+
+use crate::load::resolve_path;
+use crate::{
+    BitmapFont, DistanceField, ErrorMessage, Glyph, ImageDesc, PaddleResult, Rectangle, Vector,
+};
+
+pub(crate) fn parse_bmfont(text: &str, font_path: &str) -> PaddleResult<BitmapFont> {
+    let mut common = None;
+    let mut pages = HashMap::new();
+    let mut glyphs = HashMap::new();
+    let mut kerning = HashMap::new();
+    let mut distance_field = None;
+    for (index, line) in text.lines().enumerate() {
+        let mut tokens = tokenize(line).into_iter();
+        let tag = match tokens.next() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let attributes = Attributes {
+            line: index + 1,
+            values: tokens.filter_map(|t| split_pair(&t)).collect(),
+        };
+        match tag.as_str() {
+            "common" => {
+                common = Some((attributes.number("lineHeight")?, attributes.number("base")?));
+            }
+            "page" => {
+                let file = attributes.string("file")?;
+                pages.insert(
+                    attributes.number("id")? as usize,
+                    ImageDesc::from_path(resolve_path(font_path, file)),
+                );
+            }
+            "char" => {
+                let id = attributes.number("id")? as u32;
+                let c = match std::char::from_u32(id) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                glyphs.insert(
+                    c,
+                    Glyph {
+                        region: Rectangle::new(
+                            (attributes.number("x")?, attributes.number("y")?),
+                            (attributes.number("width")?, attributes.number("height")?),
+                        ),
+                        offset: Vector::new(
+                            attributes.number("xoffset")?,
+                            attributes.number("yoffset")?,
+                        ),
+                        advance: attributes.number("xadvance")?,
+                        page: attributes.number("page")? as usize,
+                    },
+                );
+            }
+            "kerning" => {
+                let first = std::char::from_u32(attributes.number("first")? as u32);
+                let second = std::char::from_u32(attributes.number("second")? as u32);
+                if let (Some(first), Some(second)) = (first, second) {
+                    kerning.insert((first, second), attributes.number("amount")?);
+                }
+            }
+            "distanceField" => {
+                let range = attributes.number("distanceRange")?;
+                distance_field = match attributes.string("fieldType")? {
+                    "sdf" | "psdf" => Some(DistanceField::Sdf { range }),
+                    "msdf" | "mtsdf" => Some(DistanceField::Msdf { range }),
+                    other => {
+                        return Err(bmfont_error(format!(
+                            "line {}: unknown field type {}",
+                            index + 1,
+                            other
+                        )))
+                    }
+                };
+            }
+            // info, chars, kernings: nothing needed from them
+            _ => {}
+        }
+    }
+    let (line_height, base) =
+        common.ok_or_else(|| bmfont_error("missing common line".to_owned()))?;
+    let pages = (0..pages.len())
+        .map(|id| {
+            pages
+                .remove(&id)
+                .ok_or_else(|| bmfont_error(format!("page {} is missing", id)))
+        })
+        .collect::<PaddleResult<Vec<_>>>()?;
+    if let Some((c, glyph)) = glyphs.iter().find(|(_, g)| g.page >= pages.len()) {
+        return Err(bmfont_error(format!(
+            "glyph {:?} refers to undefined page {}",
+            c, glyph.page
+        )));
+    }
+    Ok(BitmapFont {
+        line_height,
+        base,
+        pages,
+        glyphs,
+        kerning,
+        distance_field,
+    })
+}
+
+struct Attributes {
+    line: usize,
+    values: HashMap<String, String>,
+}
+
+impl Attributes {
+    fn string(&self, key: &str) -> PaddleResult<&str> {
+        self.values
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| bmfont_error(format!("line {}: {} is missing", self.line, key)))
+    }
+    fn number(&self, key: &str) -> PaddleResult<f32> {
+        let value = self.string(key)?;
+        value.parse().map_err(|_| {
+            bmfont_error(format!(
+                "line {}: {} is not a number: {}",
+                self.line, key, value
+            ))
+        })
+    }
+}
+
+/// Split at spaces, except inside quotes. Quotes are removed.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn split_pair(token: &str) -> Option<(String, String)> {
+    let (key, value) = token.split_once('=')?;
+    Some((key.to_owned(), value.to_owned()))
+}
+
+fn bmfont_error(msg: String) -> ErrorMessage {
+    ErrorMessage::technical(format!("Invalid BMFont file: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = r#"info face="Pixel Sans" size=16 bold=0 italic=0 charset="" unicode=1
+common lineHeight=18 base=14 scaleW=128 scaleH=64 pages=1 packed=0
+page id=0 file="pixel sans.png"
+chars count=2
+char id=65   x=0     y=0     width=8     height=12    xoffset=0     yoffset=2     xadvance=9     page=0  chnl=15
+char id=86   x=8     y=0     width=8     height=12    xoffset=-1    yoffset=2     xadvance=8     page=0  chnl=15
+kernings count=1
+kerning first=65  second=86  amount=-1
+"#;
+
+    #[test]
+    fn parse_text_format() {
+        let font = parse_bmfont(FONT, "fonts/pixel.fnt").unwrap();
+        assert_eq!(font.line_height, 18.0);
+        assert_eq!(font.base, 14.0);
+        assert_eq!(
+            font.pages,
+            vec![ImageDesc::from_path("fonts/pixel sans.png")]
+        );
+        let v = &font.glyphs[&'V'];
+        assert_eq!(v.region, Rectangle::new((8, 0), (8, 12)));
+        assert_eq!(v.offset, Vector::new(-1, 2));
+        assert_eq!(v.advance, 8.0);
+        assert_eq!(font.kerning[&('A', 'V')], -1.0);
+        assert_eq!(font.line_width("AV"), 16.0);
+        assert_eq!(font.distance_field, None);
+
+        let msdf = format!("{}distanceField fieldType=msdf distanceRange=4\n", FONT);
+        let font = parse_bmfont(&msdf, "fonts/pixel.fnt").unwrap();
+        assert_eq!(
+            font.distance_field,
+            Some(DistanceField::Msdf { range: 4.0 })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse_bmfont("info size=16", "f.fnt").is_err());
+        let missing_page = FONT.replace("page id=0 file=\"pixel sans.png\"", "");
+        assert!(parse_bmfont(&missing_page, "f.fnt").is_err());
+        let bad_number = FONT.replace("xadvance=9", "xadvance=nine");
+        assert!(parse_bmfont(&bad_number, "f.fnt").is_err());
+    }
+}
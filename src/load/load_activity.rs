@@ -3,8 +3,10 @@ use std::collections::HashMap;
 use nuts::DomainState;
 
 use crate::{
-    AssetLibrary, ComplexShape, Domain, FinishedLoading, FinishedLoadingMsg, Image, ImageDesc,
-    LoadScheduler, LoadSchedulerId, LoadedData, LoadingDoneMsg, ShapeDesc, UpdatedProgressMsg,
+    AnimationDesc, AssetLibrary, BitmapFont, BitmapFontDesc, ComplexShape, Domain, FinishedLoading,
+    FinishedLoadingMsg, Image, ImageDesc, LoadScheduler, LoadSchedulerId, LoadedData,
+    LoadingDoneMsg, ShapeDesc, SpriteAnimation, SvgDesc, SvgDrawing, Tilemap, TilemapDesc,
+    UpdatedProgressMsg,
 };
 
 /// Internal activity to keep track of currently loading downloads and reacting to the corresponding events.
@@ -24,6 +26,27 @@ pub(crate) struct LoadedShapeAsset {
     pub shape: ComplexShape,
 }
 
+pub(crate) struct LoadedAnimationAsset {
+    pub desc: AnimationDesc,
+    pub animation: SpriteAnimation,
+}
+
+pub(crate) struct LoadedTilemapAsset {
+    pub desc: TilemapDesc,
+    pub map: Tilemap,
+}
+
+pub(crate) struct LoadedBitmapFontAsset {
+    pub desc: BitmapFontDesc,
+    pub font: BitmapFont,
+}
+
+pub(crate) struct LoadedSvgAsset {
+    pub desc: SvgDesc,
+    pub drawing: SvgDrawing,
+    pub shapes: Vec<(ShapeDesc, ComplexShape)>,
+}
+
 impl LoadActivity {
     pub(crate) fn init() {
         let activity = LoadActivity {
@@ -34,6 +57,10 @@ impl LoadActivity {
         aid.private_channel(LoadActivity::update_progress);
         aid.private_domained_channel(LoadActivity::image_to_asset_library);
         aid.private_domained_channel(LoadActivity::shape_to_asset_library);
+        aid.private_domained_channel(LoadActivity::svg_to_asset_library);
+        aid.private_domained_channel(LoadActivity::animation_to_asset_library);
+        aid.private_domained_channel(LoadActivity::tilemap_to_asset_library);
+        aid.private_domained_channel(LoadActivity::font_to_asset_library);
         aid.subscribe(LoadActivity::after_progress);
     }
     fn add_scheduler(&mut self, msg: LoadScheduler) {
@@ -45,6 +72,35 @@ impl LoadActivity {
     fn shape_to_asset_library(&mut self, domain: &mut DomainState, shape_asset: LoadedShapeAsset) {
         AssetLibrary::from_domain(domain).add_shape(shape_asset.desc, shape_asset.shape);
     }
+    fn svg_to_asset_library(&mut self, domain: &mut DomainState, svg_asset: LoadedSvgAsset) {
+        let assets = AssetLibrary::from_domain(domain);
+        for (desc, shape) in svg_asset.shapes {
+            assets.add_shape(desc, shape);
+        }
+        assets.add_svg(svg_asset.desc, svg_asset.drawing);
+    }
+    fn animation_to_asset_library(
+        &mut self,
+        domain: &mut DomainState,
+        animation_asset: LoadedAnimationAsset,
+    ) {
+        AssetLibrary::from_domain(domain)
+            .add_animation(animation_asset.desc, animation_asset.animation);
+    }
+    fn tilemap_to_asset_library(
+        &mut self,
+        domain: &mut DomainState,
+        tilemap_asset: LoadedTilemapAsset,
+    ) {
+        AssetLibrary::from_domain(domain).add_tilemap(tilemap_asset.desc, tilemap_asset.map);
+    }
+    fn font_to_asset_library(
+        &mut self,
+        domain: &mut DomainState,
+        font_asset: LoadedBitmapFontAsset,
+    ) {
+        AssetLibrary::from_domain(domain).add_font(font_asset.desc, font_asset.font);
+    }
     fn update_progress(&mut self, msg: FinishedLoadingMsg) {
         let mut maybe_lm = self.loading_bundles.get_mut(&msg.id);
         let lm = maybe_lm
@@ -0,0 +1,525 @@
+//! Import of vector graphics from SVG files.
+//!
+//! The browser parses the XML, paddle then interprets paths and basic shapes (`rect`, `circle`, `ellipse`, `line`, `polyline`, `polygon`),
+//! including group transforms and solid fill and stroke colors. Each filled or stroked element is tessellated into a `ComplexShape`.
+//! Gradients, patterns, text, clipping and masks are not supported and the corresponding elements or paints are skipped.
+
+mod svg_parsing;
+
+pub(crate) use self::svg_parsing::parse_color;
+use self::svg_parsing::*;
+use crate::load::intern;
+use crate::{
+    quicksilver_compat::Color, ComplexShape, ErrorMessage, FillRule, JsError, LineCap, LineJoin,
+    PaddleResult, Rectangle, ShapeDesc, StrokeOptions, Tessellate, Vector,
+};
+use lyon::math::{point, vector, Box2D, Transform};
+use lyon::path::{builder::BorderRadii, Path, Polygon, Winding};
+use web_sys::Element;
+
+/// A loaded SVG drawing, as stored in the asset library.
+///
+/// Parts are in document order and should be drawn in that order. Their shapes are positioned in the coordinate system of the view box.
+#[derive(Clone, Debug)]
+pub struct SvgDrawing {
+    view_box: Rectangle,
+    parts: Vec<SvgPart>,
+}
+
+/// A single filled or stroked element of an SVG drawing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgPart {
+    pub shape: ShapeDesc,
+    pub color: Color,
+}
+
+impl SvgDrawing {
+    /// The area covered by the drawing, in SVG user units
+    pub fn view_box(&self) -> Rectangle {
+        self.view_box
+    }
+    pub fn parts(&self) -> &[SvgPart] {
+        &self.parts
+    }
+}
+
+/// An SVG document after tessellation, before its shapes have been added to the asset library
+pub(crate) struct TessellatedSvg {
+    pub view_box: Rectangle,
+    /// Name suffix, shape and color of each part
+    pub parts: Vec<(String, ComplexShape, Color)>,
+    /// Errors of elements that failed to tessellate and have been left out
+    pub skipped: Vec<ErrorMessage>,
+}
+
+impl TessellatedSvg {
+    /// Parse and tessellate an SVG document, using the browser's XML parser.
+    pub fn from_text(text: &str) -> PaddleResult<Self> {
+        let parser = web_sys::DomParser::new().map_err(JsError::from_js_value)?;
+        let document = parser
+            .parse_from_string(text, web_sys::SupportedType::ImageSvgXml)
+            .map_err(JsError::from_js_value)?;
+        let root = document
+            .document_element()
+            .ok_or_else(|| ErrorMessage::technical("SVG document is empty".to_owned()))?;
+        Self::from_node(&SvgNode::from_element(&root))
+    }
+
+    pub fn from_node(root: &SvgNode) -> PaddleResult<Self> {
+        if root.name != "svg" {
+            return Err(ErrorMessage::technical(format!(
+                "Expected <svg> root element but found <{}>",
+                root.name
+            )));
+        }
+        let mut elements = vec![];
+        collect_elements(root, &InheritedStyle::default(), &mut elements)?;
+
+        let mut parts = vec![];
+        let mut skipped = vec![];
+        for (index, element) in elements.into_iter().enumerate() {
+            let name = element.id.unwrap_or_else(|| index.to_string());
+            // A failing element is left out, the rest of the drawing is still usable
+            if let Some((color, fill_rule)) = element.fill {
+                match ComplexShape::fill(&element.path, fill_rule) {
+                    Ok(shape) => parts.push((name.clone(), shape, color)),
+                    Err(e) => skipped.push(skipped_element(&name, "fill", e)),
+                }
+            }
+            if let Some((color, options)) = element.stroke {
+                match ComplexShape::stroke(&element.path, &options) {
+                    Ok(shape) => parts.push((format!("{}:stroke", name), shape, color)),
+                    Err(e) => skipped.push(skipped_element(&name, "stroke", e)),
+                }
+            }
+        }
+
+        let view_box = match root.attribute("viewBox") {
+            Some(view_box) => {
+                let (pos, size) = parse_view_box(view_box)?;
+                Rectangle::new(pos, size)
+            }
+            None => match (root.length("width"), root.length("height")) {
+                (Some(w), Some(h)) => Rectangle::new_sized((w, h)),
+                _ => parts
+                    .iter()
+                    .map(|(_, shape, _)| shape.bounding_box())
+                    .reduce(|a, b| {
+                        let min = a.pos.min(b.pos);
+                        let max = (a.pos + a.size).max(b.pos + b.size);
+                        Rectangle::new(min, max - min)
+                    })
+                    .ok_or_else(|| {
+                        ErrorMessage::technical("SVG has neither size nor content".to_owned())
+                    })?,
+            },
+        };
+        Ok(Self {
+            view_box,
+            parts,
+            skipped,
+        })
+    }
+
+    /// Splits the drawing into shapes for the asset library and the drawing that references them.
+    ///
+    /// Shapes are named `{prefix}#{id}`, using the index of the element if it has no id. Stroke shapes get the suffix `:stroke`.
+    pub fn into_asset(self, prefix: &str) -> (SvgDrawing, Vec<(ShapeDesc, ComplexShape)>) {
+        let mut shapes = vec![];
+        let mut parts = vec![];
+        for (name, shape, color) in self.parts {
+            // Shape descriptors are static, reloading the same drawing reuses the interned names
+            let desc = ShapeDesc::named(intern(&format!("{}#{}", prefix, name)));
+            shapes.push((desc, shape));
+            parts.push(SvgPart { shape: desc, color });
+        }
+        let drawing = SvgDrawing {
+            view_box: self.view_box,
+            parts,
+        };
+        (drawing, shapes)
+    }
+}
+
+fn skipped_element(name: &str, operation: &str, e: ErrorMessage) -> ErrorMessage {
+    ErrorMessage::technical(format!(
+        "Skipped {} of SVG element {}: {}",
+        operation, name, e.text
+    ))
+}
+
+/// Simplified SVG element tree, converted from the browser DOM
+pub(crate) struct SvgNode {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<SvgNode>,
+}
+
+impl SvgNode {
+    fn from_element(element: &Element) -> Self {
+        let attributes = element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .filter_map(|name| {
+                let value = element.get_attribute(&name)?;
+                Some((name, value))
+            })
+            .collect();
+        let html_children = element.children();
+        let children = (0..html_children.length())
+            .filter_map(|i| html_children.item(i))
+            .map(|child| SvgNode::from_element(&child))
+            .collect();
+        Self {
+            name: element.local_name(),
+            attributes,
+            children,
+        }
+    }
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// A presentation property, which can be set as attribute or inside the style attribute. The style takes precedence.
+    fn property(&self, name: &str) -> Option<&str> {
+        self.attribute("style")
+            .and_then(|style| style_property(style, name))
+            .or_else(|| self.attribute(name))
+    }
+    fn length(&self, name: &str) -> Option<f32> {
+        self.attribute(name).and_then(parse_length)
+    }
+    fn length_or_zero(&self, name: &str) -> f32 {
+        self.length(name).unwrap_or(0.0)
+    }
+}
+
+/// Properties that are passed down from groups to their children
+#[derive(Clone)]
+struct InheritedStyle {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+    stroke_width: f32,
+    opacity: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    fill_rule: FillRule,
+    line_join: LineJoin,
+    line_cap: LineCap,
+    dashes: Vec<f32>,
+    transform: Transform,
+}
+
+impl Default for InheritedStyle {
+    fn default() -> Self {
+        Self {
+            fill: Some(Color::BLACK),
+            stroke: None,
+            stroke_width: 1.0,
+            opacity: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            dashes: vec![],
+            transform: Transform::identity(),
+        }
+    }
+}
+
+impl InheritedStyle {
+    fn apply(&self, node: &SvgNode) -> PaddleResult<Self> {
+        let mut style = self.clone();
+        if let Some(fill) = node.property("fill") {
+            style.fill = parse_color(fill);
+        }
+        if let Some(stroke) = node.property("stroke") {
+            style.stroke = parse_color(stroke);
+        }
+        if let Some(width) = node.property("stroke-width").and_then(parse_length) {
+            style.stroke_width = width;
+        }
+        // Group opacity is approximated by applying it to each child
+        if let Some(opacity) = node.property("opacity").and_then(parse_length) {
+            style.opacity *= opacity;
+        }
+        if let Some(opacity) = node.property("fill-opacity").and_then(parse_length) {
+            style.fill_opacity = opacity;
+        }
+        if let Some(opacity) = node.property("stroke-opacity").and_then(parse_length) {
+            style.stroke_opacity = opacity;
+        }
+        match node.property("fill-rule") {
+            Some("evenodd") => style.fill_rule = FillRule::EvenOdd,
+            Some("nonzero") => style.fill_rule = FillRule::NonZero,
+            _ => {}
+        }
+        match node.property("stroke-linejoin") {
+            Some("miter") => style.line_join = LineJoin::Miter,
+            Some("round") => style.line_join = LineJoin::Round,
+            Some("bevel") => style.line_join = LineJoin::Bevel,
+            _ => {}
+        }
+        match node.property("stroke-linecap") {
+            Some("butt") => style.line_cap = LineCap::Butt,
+            Some("round") => style.line_cap = LineCap::Round,
+            Some("square") => style.line_cap = LineCap::Square,
+            _ => {}
+        }
+        if let Some(dashes) = node.property("stroke-dasharray") {
+            style.dashes = dashes_list(dashes).unwrap_or_default();
+        }
+        if let Some(transform) = node.attribute("transform") {
+            style.transform = parse_transform(transform)?.then(&self.transform);
+        }
+        Ok(style)
+    }
+    fn fill(&self) -> Option<(Color, FillRule)> {
+        let color = self.fill?;
+        Some((
+            color.with_alpha(color.a * self.opacity * self.fill_opacity),
+            self.fill_rule,
+        ))
+    }
+    fn stroke(&self) -> Option<(Color, StrokeOptions)> {
+        let color = self.stroke?;
+        // Strokes are tessellated after transforming the path, hence the width is scaled approximately
+        let scale = self.transform.determinant().abs().sqrt();
+        let options = StrokeOptions::new(self.stroke_width * scale)
+            .with_line_join(self.line_join)
+            .with_line_cap(self.line_cap)
+            .with_dashes(
+                &self.dashes.iter().map(|d| d * scale).collect::<Vec<_>>(),
+                0.0,
+            );
+        Some((
+            color.with_alpha(color.a * self.opacity * self.stroke_opacity),
+            options,
+        ))
+    }
+}
+
+/// Parses `stroke-dasharray`, where `none` or invalid values result in None
+fn dashes_list(dashes: &str) -> Option<Vec<f32>> {
+    dashes
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(parse_length)
+        .collect()
+}
+
+/// A renderable element, with its path already in view box coordinates
+struct SvgElement {
+    id: Option<String>,
+    path: Path,
+    fill: Option<(Color, FillRule)>,
+    stroke: Option<(Color, StrokeOptions)>,
+}
+
+fn collect_elements(
+    node: &SvgNode,
+    inherited: &InheritedStyle,
+    out: &mut Vec<SvgElement>,
+) -> PaddleResult<()> {
+    if node.property("display") == Some("none") {
+        return Ok(());
+    }
+    let style = inherited.apply(node)?;
+    let path = match node.name.as_str() {
+        "svg" | "g" | "a" => {
+            for child in &node.children {
+                collect_elements(child, &style, out)?;
+            }
+            return Ok(());
+        }
+        "path" => match node.attribute("d") {
+            Some(d) => parse_path_data(d)?,
+            None => return Ok(()),
+        },
+        "rect" => {
+            let min = point(node.length_or_zero("x"), node.length_or_zero("y"));
+            let size = vector(node.length_or_zero("width"), node.length_or_zero("height"));
+            let radius = node.length("rx").or_else(|| node.length("ry"));
+            let rect = Box2D::new(min, min + size);
+            let mut builder = Path::builder();
+            match radius {
+                Some(r) if r > 0.0 => {
+                    builder.add_rounded_rectangle(&rect, &BorderRadii::new(r), Winding::Positive)
+                }
+                _ => builder.add_rectangle(&rect, Winding::Positive),
+            }
+            builder.build()
+        }
+        "circle" => {
+            let center = point(node.length_or_zero("cx"), node.length_or_zero("cy"));
+            let mut builder = Path::builder();
+            builder.add_circle(center, node.length_or_zero("r"), Winding::Positive);
+            builder.build()
+        }
+        "ellipse" => {
+            let center = point(node.length_or_zero("cx"), node.length_or_zero("cy"));
+            let radii = vector(node.length_or_zero("rx"), node.length_or_zero("ry"));
+            let mut builder = Path::builder();
+            builder.add_ellipse(center, radii, lyon::geom::Angle::zero(), Winding::Positive);
+            builder.build()
+        }
+        "line" => {
+            let mut builder = Path::builder();
+            builder.begin(point(node.length_or_zero("x1"), node.length_or_zero("y1")));
+            builder.line_to(point(node.length_or_zero("x2"), node.length_or_zero("y2")));
+            builder.end(false);
+            builder.build()
+        }
+        "polyline" | "polygon" => {
+            let points: Vec<_> = parse_points(node.attribute("points").unwrap_or_default())?
+                .into_iter()
+                .map(Vector::into)
+                .collect();
+            let mut builder = Path::builder();
+            builder.add_polygon(Polygon {
+                points: &points,
+                closed: node.name == "polygon",
+            });
+            builder.build()
+        }
+        // Everything else is either not rendered directly (defs, symbols, metadata, ...) or not supported
+        _ => return Ok(()),
+    };
+    out.push(SvgElement {
+        id: node.attribute("id").map(str::to_owned),
+        path: path.transformed(&style.transform),
+        // Lines are never filled, only stroked
+        fill: if node.name == "line" {
+            None
+        } else {
+            style.fill()
+        },
+        stroke: style.stroke(),
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, attributes: &[(&str, &str)], children: Vec<SvgNode>) -> SvgNode {
+        SvgNode {
+            name: name.to_owned(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            children,
+        }
+    }
+
+    #[test]
+    fn shapes_and_colors() {
+        let svg = node(
+            "svg",
+            &[("viewBox", "0 0 100 50")],
+            vec![
+                node(
+                    "rect",
+                    &[
+                        ("id", "bg"),
+                        ("width", "100"),
+                        ("height", "50"),
+                        ("fill", "#00f"),
+                    ],
+                    vec![],
+                ),
+                node(
+                    "g",
+                    &[("transform", "translate(10,10)"), ("style", "fill:red")],
+                    vec![node("circle", &[("r", "5"), ("stroke", "black")], vec![])],
+                ),
+                node("line", &[("x2", "10"), ("stroke", "white")], vec![]),
+                node("defs", &[], vec![node("rect", &[("width", "1")], vec![])]),
+            ],
+        );
+        let tessellated = TessellatedSvg::from_node(&svg).unwrap();
+        assert_eq!(tessellated.view_box, Rectangle::new((0, 0), (100, 50)));
+        let parts: Vec<_> = tessellated
+            .parts
+            .iter()
+            .map(|(name, _, color)| (name.as_str(), *color))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("bg", Color::BLUE),
+                ("1", Color::RED),
+                ("1:stroke", Color::BLACK),
+                ("2:stroke", Color::WHITE),
+            ]
+        );
+        // The group transform moves the circle
+        let circle = tessellated.parts[1].1.bounding_box();
+        assert!((circle.pos.x - 5.0).abs() < 0.01);
+        assert!((circle.size.x - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn opacity_and_none() {
+        let svg = node(
+            "svg",
+            &[("width", "20px"), ("height", "10")],
+            vec![
+                node(
+                    "path",
+                    &[
+                        ("d", "M0 0 H10 V10 Z"),
+                        ("opacity", "0.5"),
+                        ("fill-opacity", "0.5"),
+                    ],
+                    vec![],
+                ),
+                node("path", &[("d", "M0 0 H10 V10 Z"), ("fill", "none")], vec![]),
+            ],
+        );
+        let tessellated = TessellatedSvg::from_node(&svg).unwrap();
+        assert_eq!(tessellated.view_box, Rectangle::new_sized((20, 10)));
+        assert_eq!(tessellated.parts.len(), 1);
+        assert_eq!(tessellated.parts[0].2, Color::BLACK.with_alpha(0.25));
+    }
+
+    #[test]
+    fn failing_elements_skipped() {
+        let svg = node(
+            "svg",
+            &[("viewBox", "0 0 10 10")],
+            vec![
+                node(
+                    "path",
+                    &[("id", "dot"), ("d", "M1 1"), ("stroke", "red")],
+                    vec![],
+                ),
+                node("rect", &[("width", "10"), ("height", "10")], vec![]),
+            ],
+        );
+        let tessellated = TessellatedSvg::from_node(&svg).unwrap();
+        assert_eq!(tessellated.parts.len(), 1);
+        assert_eq!(tessellated.parts[0].0, "1");
+        assert_eq!(tessellated.skipped.len(), 2);
+        assert!(tessellated.skipped[0]
+            .text
+            .contains("fill of SVG element dot"));
+        assert!(tessellated.skipped[1]
+            .text
+            .contains("stroke of SVG element dot"));
+    }
+
+    #[test]
+    fn invalid_documents() {
+        assert!(TessellatedSvg::from_node(&node("html", &[], vec![])).is_err());
+        assert!(TessellatedSvg::from_node(&node("svg", &[], vec![])).is_err());
+        let broken = node("svg", &[], vec![node("path", &[("d", "M0 0 L")], vec![])]);
+        assert!(TessellatedSvg::from_node(&broken).is_err());
+    }
+}
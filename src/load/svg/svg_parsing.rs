@@ -0,0 +1,419 @@
+//! Parsers for the attribute micro-syntaxes of SVG: path data, point lists, transforms, colors and inline styles.
+
+use crate::{quicksilver_compat::Color, ErrorMessage, PaddleResult, Vector};
+use lyon::geom::{Angle, ArcFlags};
+use lyon::math::{point, vector, Transform};
+use lyon::path::{traits::SvgPathBuilder, Path};
+
+/// Parses the `d` attribute of a `<path>` element
+pub(crate) fn parse_path_data(d: &str) -> PaddleResult<Path> {
+    let mut tokens = Tokens::new(d);
+    let mut builder = Path::svg_builder();
+    let mut command: Option<u8> = None;
+    loop {
+        tokens.skip_separators();
+        if tokens.at_end() {
+            break;
+        }
+        if let Some(c) = tokens.command() {
+            command = Some(c);
+        }
+        let c = command.ok_or_else(|| tokens.error("expected a command"))?;
+        match c {
+            b'M' => {
+                builder.move_to(tokens.point()?);
+                // Additional coordinate pairs are implicit line commands
+                command = Some(b'L');
+            }
+            b'm' => {
+                builder.relative_move_to(tokens.vector()?);
+                command = Some(b'l');
+            }
+            b'L' => {
+                builder.line_to(tokens.point()?);
+            }
+            b'l' => {
+                builder.relative_line_to(tokens.vector()?);
+            }
+            b'H' => {
+                builder.horizontal_line_to(tokens.number()?);
+            }
+            b'h' => {
+                builder.relative_horizontal_line_to(tokens.number()?);
+            }
+            b'V' => {
+                builder.vertical_line_to(tokens.number()?);
+            }
+            b'v' => {
+                builder.relative_vertical_line_to(tokens.number()?);
+            }
+            b'C' => {
+                builder.cubic_bezier_to(tokens.point()?, tokens.point()?, tokens.point()?);
+            }
+            b'c' => {
+                builder.relative_cubic_bezier_to(
+                    tokens.vector()?,
+                    tokens.vector()?,
+                    tokens.vector()?,
+                );
+            }
+            b'S' => {
+                builder.smooth_cubic_bezier_to(tokens.point()?, tokens.point()?);
+            }
+            b's' => {
+                builder.smooth_relative_cubic_bezier_to(tokens.vector()?, tokens.vector()?);
+            }
+            b'Q' => {
+                builder.quadratic_bezier_to(tokens.point()?, tokens.point()?);
+            }
+            b'q' => {
+                builder.relative_quadratic_bezier_to(tokens.vector()?, tokens.vector()?);
+            }
+            b'T' => {
+                builder.smooth_quadratic_bezier_to(tokens.point()?);
+            }
+            b't' => {
+                builder.smooth_relative_quadratic_bezier_to(tokens.vector()?);
+            }
+            b'A' | b'a' => {
+                let radii = tokens.vector()?;
+                let x_rotation = Angle::degrees(tokens.number()?);
+                let flags = ArcFlags {
+                    large_arc: tokens.flag()?,
+                    sweep: tokens.flag()?,
+                };
+                if c == b'A' {
+                    builder.arc_to(radii, x_rotation, flags, tokens.point()?);
+                } else {
+                    builder.relative_arc_to(radii, x_rotation, flags, tokens.vector()?);
+                }
+            }
+            b'Z' | b'z' => {
+                builder.close();
+                // Numbers are not allowed to follow a close command
+                command = None;
+            }
+            _ => return Err(tokens.error("unknown command")),
+        }
+    }
+    Ok(builder.build())
+}
+
+/// Parses the `points` attribute of `<polyline>` and `<polygon>` elements
+pub(crate) fn parse_points(points: &str) -> PaddleResult<Vec<Vector>> {
+    let mut tokens = Tokens::new(points);
+    let mut out = vec![];
+    loop {
+        tokens.skip_separators();
+        if tokens.at_end() {
+            return Ok(out);
+        }
+        let p = tokens.point()?;
+        out.push(Vector::new(p.x, p.y));
+    }
+}
+
+/// Parses a length attribute, ignoring a `px` unit. Other units are not supported.
+pub(crate) fn parse_length(length: &str) -> Option<f32> {
+    length.trim().trim_end_matches("px").trim().parse().ok()
+}
+
+/// Parses a `viewBox` attribute
+pub(crate) fn parse_view_box(view_box: &str) -> PaddleResult<(Vector, Vector)> {
+    let mut tokens = Tokens::new(view_box);
+    let min = tokens.point()?;
+    let size = tokens.point()?;
+    Ok((Vector::new(min.x, min.y), Vector::new(size.x, size.y)))
+}
+
+/// Parses a `transform` attribute into a single transformation.
+pub(crate) fn parse_transform(transform: &str) -> PaddleResult<Transform> {
+    let mut total = Transform::identity();
+    let mut rest = transform.trim();
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or_else(|| transform_error(transform))?;
+        let close = rest.find(')').ok_or_else(|| transform_error(transform))?;
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let mut tokens = Tokens::new(&rest[open + 1..close]);
+        let mut args = vec![];
+        loop {
+            tokens.skip_separators();
+            if tokens.at_end() {
+                break;
+            }
+            args.push(tokens.number()?);
+        }
+        let t = match (name, args.as_slice()) {
+            ("matrix", [a, b, c, d, e, f]) => Transform::new(*a, *b, *c, *d, *e, *f),
+            ("translate", [x]) => Transform::translation(*x, 0.0),
+            ("translate", [x, y]) => Transform::translation(*x, *y),
+            ("scale", [s]) => Transform::scale(*s, *s),
+            ("scale", [x, y]) => Transform::scale(*x, *y),
+            ("rotate", [a]) => Transform::rotation(Angle::degrees(*a)),
+            ("rotate", [a, cx, cy]) => Transform::translation(-cx, -cy)
+                .then_rotate(Angle::degrees(*a))
+                .then_translate(vector(*cx, *cy)),
+            ("skewX", [a]) => Transform::new(1.0, 0.0, a.to_radians().tan(), 1.0, 0.0, 0.0),
+            ("skewY", [a]) => Transform::new(1.0, a.to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+            _ => return Err(transform_error(transform)),
+        };
+        // The right-most transformation is applied first
+        total = t.then(&total);
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(total)
+}
+
+fn transform_error(transform: &str) -> ErrorMessage {
+    ErrorMessage::technical(format!("Unsupported SVG transform: {}", transform))
+}
+
+/// Parses a color value as used in `fill` and `stroke`.
+///
+/// Returns None for `none`, `transparent` and unsupported values, such as references to gradients.
+pub(crate) fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let valid =
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit());
+        return if valid {
+            Some(Color::from_hex(hex))
+        } else {
+            None
+        };
+    }
+    if let Some(args) = value
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels: Vec<f32> = args
+            .split(',')
+            .filter_map(|c| {
+                let c = c.trim();
+                match c.strip_suffix('%') {
+                    Some(percent) => percent.trim().parse::<f32>().ok().map(|p| p / 100.0),
+                    None => c.parse::<f32>().ok().map(|v| v / 255.0),
+                }
+            })
+            .collect();
+        return match channels.as_slice() {
+            [r, g, b] => Some(Color::new(*r, *g, *b)),
+            _ => None,
+        };
+    }
+    let named = match value.to_ascii_lowercase().as_str() {
+        "black" => Color::BLACK,
+        "white" => Color::WHITE,
+        "red" => Color::RED,
+        "lime" => Color::GREEN,
+        "green" => Color::from_rgba(0, 128, 0, 1.0),
+        "blue" => Color::BLUE,
+        "yellow" => Color::YELLOW,
+        "cyan" | "aqua" => Color::CYAN,
+        "magenta" | "fuchsia" => Color::from_rgba(255, 0, 255, 1.0),
+        "orange" => Color::from_rgba(255, 165, 0, 1.0),
+        "purple" => Color::from_rgba(128, 0, 128, 1.0),
+        "gray" | "grey" => Color::from_rgba(128, 128, 128, 1.0),
+        _ => return None,
+    };
+    Some(named)
+}
+
+/// Looks up a property in an inline `style` attribute, such as `fill:#ff0000;stroke:none`
+pub(crate) fn style_property<'a>(style: &'a str, name: &str) -> Option<&'a str> {
+    style.split(';').find_map(|declaration| {
+        let (key, value) = declaration.split_once(':')?;
+        if key.trim() == name {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// Minimal scanner over SVG number lists
+struct Tokens<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+    fn bytes(&self) -> &[u8] {
+        self.input.as_bytes()
+    }
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+    fn peek(&self) -> Option<u8> {
+        self.bytes().get(self.pos).copied()
+    }
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || c == b',' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    /// Consumes the next character if it is a command letter
+    fn command(&mut self) -> Option<u8> {
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() && c != b'e' && c != b'E' => {
+                self.pos += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+    fn number(&mut self) -> PaddleResult<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        if let Some(b'+' | b'-') = self.peek() {
+            self.pos += 1;
+        }
+        let mut seen_dot = false;
+        let mut seen_exp = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => {}
+                // A second dot starts the next number, e.g. "0.5.5"
+                b'.' if !seen_dot && !seen_exp => seen_dot = true,
+                b'e' | b'E' if !seen_exp && self.pos > start => {
+                    seen_exp = true;
+                    if let Some(b'+' | b'-') = self.bytes().get(self.pos + 1) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        self.input[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("expected a number"))
+    }
+    /// Arc flags are single digits that may be written without separators
+    fn flag(&mut self) -> PaddleResult<bool> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("expected a flag")),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+    fn point(&mut self) -> PaddleResult<lyon::math::Point> {
+        Ok(point(self.number()?, self.number()?))
+    }
+    fn vector(&mut self) -> PaddleResult<lyon::math::Vector> {
+        Ok(vector(self.number()?, self.number()?))
+    }
+    fn error(&self, expected: &str) -> ErrorMessage {
+        ErrorMessage::technical(format!(
+            "Invalid SVG data at position {} of \"{}\": {}",
+            self.pos, self.input, expected
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon::path::PathEvent;
+
+    fn end_points(path: &Path) -> Vec<(f32, f32)> {
+        path.iter()
+            .filter_map(|e| match e {
+                PathEvent::Begin { at } => Some((at.x, at.y)),
+                PathEvent::Line { to, .. }
+                | PathEvent::Quadratic { to, .. }
+                | PathEvent::Cubic { to, .. } => Some((to.x, to.y)),
+                PathEvent::End { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn path_commands() {
+        let path = parse_path_data("M10 10 h5 v-5 L0,0 Z").unwrap();
+        assert_eq!(
+            end_points(&path),
+            vec![(10.0, 10.0), (15.0, 10.0), (15.0, 5.0), (0.0, 0.0)]
+        );
+        // Implicit line commands and compact number syntax
+        let path = parse_path_data("m1-1 2.5.5-1e1,0").unwrap();
+        assert_eq!(
+            end_points(&path),
+            vec![(1.0, -1.0), (3.5, -0.5), (-6.5, -0.5)]
+        );
+        let path = parse_path_data("M0 0C1 1 2 1 3 0s2-1 3 0Q7 1 8 0t2 0").unwrap();
+        assert_eq!(
+            end_points(&path),
+            vec![(0.0, 0.0), (3.0, 0.0), (6.0, 0.0), (8.0, 0.0), (10.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn path_arcs() {
+        // Compact flags as written by some editors
+        let path = parse_path_data("M0 0a5 5 0 0010 0").unwrap();
+        let last = *end_points(&path).last().unwrap();
+        assert!((last.0 - 10.0).abs() < 0.001 && last.1.abs() < 0.001);
+    }
+
+    #[test]
+    fn path_errors() {
+        assert!(parse_path_data("10 10").is_err());
+        assert!(parse_path_data("M0 0 X").is_err());
+        assert!(parse_path_data("M0 0 Z 1 1").is_err());
+        assert!(parse_path_data("M0").is_err());
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(
+            parse_points(" 0,0 10 0, 10,-5 ").unwrap(),
+            vec![Vector::new(0, 0), Vector::new(10, 0), Vector::new(10, -5)]
+        );
+        assert!(parse_points("0,0 10").is_err());
+    }
+
+    #[test]
+    fn transforms() {
+        let t = parse_transform("translate(10, 20) scale(2)").unwrap();
+        assert_eq!(t.transform_point(point(1.0, 1.0)), point(12.0, 22.0));
+        let t = parse_transform("matrix(1 0 0 1 5 6)").unwrap();
+        assert_eq!(t.transform_point(point(0.0, 0.0)), point(5.0, 6.0));
+        let t = parse_transform("rotate(90 1 1)").unwrap();
+        let p = t.transform_point(point(2.0, 1.0));
+        assert!((p.x - 1.0).abs() < 0.0001 && (p.y - 2.0).abs() < 0.0001);
+        assert!(parse_transform("perspective(1)").is_err());
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#f00"), Some(Color::RED));
+        assert_eq!(parse_color(" #0000FF "), Some(Color::BLUE));
+        assert_eq!(parse_color("rgb(0, 255, 0)"), Some(Color::GREEN));
+        assert_eq!(parse_color("rgb(100%,100%,100%)"), Some(Color::WHITE));
+        assert_eq!(parse_color("Black"), Some(Color::BLACK));
+        assert_eq!(parse_color("none"), None);
+        assert_eq!(parse_color("url(#gradient)"), None);
+        assert_eq!(parse_color("#12"), None);
+    }
+
+    #[test]
+    fn styles() {
+        let style = "fill:#ff0000; stroke : none;stroke-width:2px";
+        assert_eq!(style_property(style, "fill"), Some("#ff0000"));
+        assert_eq!(style_property(style, "stroke"), Some("none"));
+        assert_eq!(style_property(style, "stroke-width"), Some("2px"));
+        assert_eq!(style_property(style, "opacity"), None);
+        assert_eq!(parse_length("2px"), Some(2.0));
+    }
+}
@@ -0,0 +1,423 @@
+//! Import of maps created with the [Tiled](https://www.mapeditor.org/) map editor, in its JSON format.
+//!
+//! The browser parses the JSON, paddle then reads orthogonal maps with tile layers, object layers and (nested) group layers.
+//! Tilesets must be embedded in the map and based on a single image. Infinite maps and compressed layer data are not supported.
+
+use std::collections::HashMap;
+
+use crate::load::resolve_path;
+use crate::{
+    ErrorMessage, ImageDesc, JsError, MapObject, ObjectLayer, PaddleResult, PropertyValue,
+    Rectangle, TileLayer, Tilemap, Tileset, Vector,
+};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Plain Rust copy of a parsed JSON document
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parse JSON text with the browser's `JSON.parse`
+    pub fn parse(text: &str) -> PaddleResult<Self> {
+        let value = js_sys::JSON::parse(text).map_err(JsError::from_js_value)?;
+        Ok(Self::from_js(&value))
+    }
+    fn from_js(value: &JsValue) -> Self {
+        if let Some(b) = value.as_bool() {
+            JsonValue::Bool(b)
+        } else if let Some(n) = value.as_f64() {
+            JsonValue::Number(n)
+        } else if let Some(s) = value.as_string() {
+            JsonValue::String(s)
+        } else if js_sys::Array::is_array(value) {
+            let array = js_sys::Array::from(value);
+            JsonValue::Array(array.iter().map(|v| Self::from_js(&v)).collect())
+        } else if value.is_object() {
+            let entries = js_sys::Object::entries(value.unchecked_ref());
+            JsonValue::Object(
+                entries
+                    .iter()
+                    .map(|entry| {
+                        let entry = js_sys::Array::from(&entry);
+                        (
+                            entry.get(0).as_string().unwrap_or_default(),
+                            Self::from_js(&entry.get(1)),
+                        )
+                    })
+                    .collect(),
+            )
+        } else {
+            JsonValue::Null
+        }
+    }
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    fn as_array(&self) -> &[JsonValue] {
+        match self {
+            JsonValue::Array(values) => values,
+            _ => &[],
+        }
+    }
+    fn number(&self, key: &str) -> PaddleResult<f64> {
+        self.get(key)
+            .and_then(JsonValue::as_f64)
+            .ok_or_else(|| tiled_error(format!("missing number \"{}\"", key)))
+    }
+    fn number_or(&self, key: &str, default: f64) -> f64 {
+        self.get(key).and_then(JsonValue::as_f64).unwrap_or(default)
+    }
+    fn string_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.get(key).and_then(JsonValue::as_str).unwrap_or(default)
+    }
+}
+
+/// Layer attributes that are inherited by the children of group layers
+#[derive(Clone, Copy)]
+struct LayerContext {
+    offset: Vector,
+    visible: bool,
+    opacity: f32,
+}
+
+/// Read a map from its JSON document. Image paths of tilesets are relative to the path of the map.
+pub(crate) fn parse_tilemap(json: &JsonValue, map_path: &str) -> PaddleResult<Tilemap> {
+    let orientation = json.string_or("orientation", "orthogonal");
+    if orientation != "orthogonal" {
+        return Err(tiled_error(format!(
+            "{} maps are not supported",
+            orientation
+        )));
+    }
+    if json.get("infinite").and_then(JsonValue::as_bool) == Some(true) {
+        return Err(tiled_error("infinite maps are not supported".to_owned()));
+    }
+    let mut map = Tilemap {
+        width: json.number("width")? as usize,
+        height: json.number("height")? as usize,
+        tile_size: (
+            json.number("tilewidth")? as f32,
+            json.number("tileheight")? as f32,
+        )
+            .into(),
+        tilesets: vec![],
+        layers: vec![],
+        object_layers: vec![],
+    };
+    for tileset in json.get("tilesets").map(JsonValue::as_array).unwrap_or(&[]) {
+        map.tilesets.push(parse_tileset(tileset, map_path)?);
+    }
+    map.tilesets.sort_by_key(|ts| ts.first_gid);
+    let root = LayerContext {
+        offset: Vector::ZERO,
+        visible: true,
+        opacity: 1.0,
+    };
+    for layer in json.get("layers").map(JsonValue::as_array).unwrap_or(&[]) {
+        parse_layer(layer, root, &mut map)?;
+    }
+    Ok(map)
+}
+
+fn parse_tileset(json: &JsonValue, map_path: &str) -> PaddleResult<Tileset> {
+    if json.get("source").is_some() {
+        return Err(tiled_error(
+            "external tilesets are not supported, embed them in the map".to_owned(),
+        ));
+    }
+    let name = json.string_or("name", "").to_owned();
+    let image = json
+        .get("image")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| tiled_error(format!("tileset {} is not based on a single image", name)))?;
+    Ok(Tileset {
+        first_gid: json.number("firstgid")? as u32,
+        image: ImageDesc::from_path(resolve_path(map_path, image)),
+        tile_size: (
+            json.number("tilewidth")? as f32,
+            json.number("tileheight")? as f32,
+        )
+            .into(),
+        columns: json.number("columns")? as u32,
+        tile_count: json.number("tilecount")? as u32,
+        margin: json.number_or("margin", 0.0) as f32,
+        spacing: json.number_or("spacing", 0.0) as f32,
+        name,
+    })
+}
+
+fn parse_layer(json: &JsonValue, parent: LayerContext, map: &mut Tilemap) -> PaddleResult<()> {
+    let name = json.string_or("name", "").to_owned();
+    let context = LayerContext {
+        offset: parent.offset
+            + (
+                json.number_or("offsetx", 0.0) as f32,
+                json.number_or("offsety", 0.0) as f32,
+            )
+                .into(),
+        visible: parent.visible && json.get("visible").and_then(JsonValue::as_bool) != Some(false),
+        opacity: parent.opacity * json.number_or("opacity", 1.0) as f32,
+    };
+    match json.string_or("type", "") {
+        "tilelayer" => {
+            let encoding = json.string_or("encoding", "csv");
+            if encoding != "csv" {
+                return Err(tiled_error(format!(
+                    "layer {} uses {} encoding, only CSV is supported",
+                    name, encoding
+                )));
+            }
+            let tiles = json
+                .get("data")
+                .map(JsonValue::as_array)
+                .unwrap_or(&[])
+                .iter()
+                .map(|gid| gid.as_f64().unwrap_or(0.0) as u32)
+                .collect();
+            map.layers.push(TileLayer {
+                name,
+                tiles,
+                width: json.number("width")? as usize,
+                height: json.number("height")? as usize,
+                offset: context.offset,
+                visible: context.visible,
+                opacity: context.opacity,
+            });
+        }
+        "objectgroup" => {
+            let objects = json
+                .get("objects")
+                .map(JsonValue::as_array)
+                .unwrap_or(&[])
+                .iter()
+                .map(|object| parse_object(object, context.offset))
+                .collect::<PaddleResult<_>>()?;
+            map.object_layers.push(ObjectLayer { name, objects });
+        }
+        "group" => {
+            for child in json.get("layers").map(JsonValue::as_array).unwrap_or(&[]) {
+                parse_layer(child, context, map)?;
+            }
+        }
+        // Image layers and unknown layer types
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_object(json: &JsonValue, offset: Vector) -> PaddleResult<MapObject> {
+    let width = json.number_or("width", 0.0) as f32;
+    let height = json.number_or("height", 0.0) as f32;
+    let mut y = json.number("y")? as f32;
+    // Tile objects are positioned by their bottom left corner
+    if json.get("gid").is_some() {
+        y -= height;
+    }
+    let pos = Vector::new(json.number("x")? as f32, y) + offset;
+    let class = match json.get("class").and_then(JsonValue::as_str) {
+        Some(class) => class,
+        None => json.string_or("type", ""),
+    };
+    Ok(MapObject {
+        id: json.number_or("id", 0.0) as u32,
+        name: json.string_or("name", "").to_owned(),
+        class: class.to_owned(),
+        area: Rectangle::new(pos, (width, height)),
+        properties: parse_properties(json),
+    })
+}
+
+fn parse_properties(json: &JsonValue) -> HashMap<String, PropertyValue> {
+    let mut properties = HashMap::new();
+    for property in json
+        .get("properties")
+        .map(JsonValue::as_array)
+        .unwrap_or(&[])
+    {
+        let name = property.string_or("name", "").to_owned();
+        let value = match (property.string_or("type", "string"), property.get("value")) {
+            ("bool", Some(JsonValue::Bool(b))) => PropertyValue::Bool(*b),
+            ("int", Some(JsonValue::Number(n))) => PropertyValue::Int(*n as i64),
+            ("float", Some(JsonValue::Number(n))) => PropertyValue::Float(*n),
+            (_, Some(JsonValue::String(s))) => PropertyValue::String(s.clone()),
+            (_, Some(JsonValue::Number(n))) => PropertyValue::Float(*n),
+            (_, Some(JsonValue::Bool(b))) => PropertyValue::Bool(*b),
+            // Class properties and missing values
+            _ => continue,
+        };
+        properties.insert(name, value);
+    }
+    properties
+}
+
+fn tiled_error(msg: String) -> ErrorMessage {
+    ErrorMessage::technical(format!("Invalid Tiled map: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obj(entries: &[(&str, JsonValue)]) -> JsonValue {
+        JsonValue::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+    fn num(n: f64) -> JsonValue {
+        JsonValue::Number(n)
+    }
+    fn string(s: &str) -> JsonValue {
+        JsonValue::String(s.to_owned())
+    }
+
+    fn tileset() -> JsonValue {
+        obj(&[
+            ("firstgid", num(1.0)),
+            ("name", string("terrain")),
+            ("image", string("../images/terrain.png")),
+            ("tilewidth", num(16.0)),
+            ("tileheight", num(16.0)),
+            ("columns", num(4.0)),
+            ("tilecount", num(16.0)),
+        ])
+    }
+
+    fn map(layers: Vec<JsonValue>) -> JsonValue {
+        obj(&[
+            ("width", num(2.0)),
+            ("height", num(2.0)),
+            ("tilewidth", num(16.0)),
+            ("tileheight", num(16.0)),
+            ("orientation", string("orthogonal")),
+            ("tilesets", JsonValue::Array(vec![tileset()])),
+            ("layers", JsonValue::Array(layers)),
+        ])
+    }
+
+    #[test]
+    fn tile_layers_in_groups() {
+        let ground = obj(&[
+            ("type", string("tilelayer")),
+            ("name", string("ground")),
+            ("width", num(2.0)),
+            ("height", num(2.0)),
+            ("offsetx", num(4.0)),
+            ("opacity", num(0.5)),
+            (
+                "data",
+                JsonValue::Array(vec![num(1.0), num(0.0), num(3.0), num(2.0)]),
+            ),
+        ]);
+        let group = obj(&[
+            ("type", string("group")),
+            ("offsetx", num(10.0)),
+            ("visible", JsonValue::Bool(false)),
+            ("layers", JsonValue::Array(vec![ground])),
+        ]);
+        let tilemap = parse_tilemap(&map(vec![group]), "maps/level1.json").unwrap();
+        assert_eq!(tilemap.size_in_tiles(), (2, 2));
+        assert_eq!(
+            tilemap.tilesets()[0].image(),
+            ImageDesc::from_path("maps/../images/terrain.png")
+        );
+        let layer = tilemap.layer("ground").unwrap();
+        assert_eq!(layer.tiles, vec![1, 0, 3, 2]);
+        assert_eq!(layer.offset, Vector::new(14.0, 0.0));
+        assert!(!layer.is_visible());
+        assert_eq!(layer.opacity(), 0.5);
+    }
+
+    #[test]
+    fn object_layers() {
+        let properties = JsonValue::Array(vec![
+            obj(&[
+                ("name", string("damage")),
+                ("type", string("int")),
+                ("value", num(3.0)),
+            ]),
+            obj(&[
+                ("name", string("once")),
+                ("type", string("bool")),
+                ("value", JsonValue::Bool(true)),
+            ]),
+        ]);
+        let trap = obj(&[
+            ("id", num(7.0)),
+            ("name", string("spikes")),
+            ("type", string("trap")),
+            ("x", num(8.0)),
+            ("y", num(32.0)),
+            ("width", num(16.0)),
+            ("height", num(8.0)),
+            ("gid", num(5.0)),
+            ("properties", properties),
+        ]);
+        let layer = obj(&[
+            ("type", string("objectgroup")),
+            ("name", string("triggers")),
+            ("objects", JsonValue::Array(vec![trap])),
+        ]);
+        let tilemap = parse_tilemap(&map(vec![layer]), "level.json").unwrap();
+        let objects = tilemap.object_layer("triggers").unwrap().objects();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].class, "trap");
+        assert_eq!(objects[0].area, Rectangle::new((8, 24), (16, 8)));
+        assert_eq!(objects[0].properties["damage"], PropertyValue::Int(3));
+        assert_eq!(objects[0].properties["once"], PropertyValue::Bool(true));
+    }
+
+    #[test]
+    fn unsupported_maps() {
+        let mut infinite = map(vec![]);
+        if let JsonValue::Object(entries) = &mut infinite {
+            entries.push(("infinite".to_owned(), JsonValue::Bool(true)));
+        }
+        assert!(parse_tilemap(&infinite, "map.json").is_err());
+
+        let external = obj(&[
+            ("width", num(1.0)),
+            ("height", num(1.0)),
+            ("tilewidth", num(16.0)),
+            ("tileheight", num(16.0)),
+            (
+                "tilesets",
+                JsonValue::Array(vec![obj(&[
+                    ("firstgid", num(1.0)),
+                    ("source", string("terrain.tsj")),
+                ])]),
+            ),
+        ]);
+        assert!(parse_tilemap(&external, "map.json").is_err());
+    }
+}
@@ -11,8 +11,8 @@ use std::collections::HashMap;
 
 use crate::quicksilver_compat::Shape;
 use crate::{
-    ComplexShape, DisplayPaint, FitStrategy, FloatingText, PaddleResult, PointerEventType,
-    Rectangle, Transform,
+    ComplexShape, DisplayPaint, FitStrategy, FloatingText, NineSlice, NutsCheck, PaddleResult,
+    PointerEventType, Rectangle, RichText, TextOverflow, Transform,
 };
 
 /// A logical element for display and interaction
@@ -24,8 +24,12 @@ pub struct UiElement {
     shape: ComplexShape,
     /// How to color the triangles.
     paint: Box<dyn DisplayPaint>,
+    /// Replaces shape and paint when set, for backgrounds that keep their borders when resized.
+    nine_slice: Option<NineSlice>,
     /// Zero or one text to display on the element.
     text: Option<RefCell<FloatingText>>,
+    /// Styled content of the text, applied on the next draw when icons can be looked up.
+    rich_text: Option<RichText>,
     /// Registered interactive events and what to do on them.
     triggers: HashMap<PointerEventType, Box<dyn Fn()>>,
     z: i16,
@@ -37,8 +41,10 @@ impl UiElement {
             area,
             paint: Box::new(paint),
             shape: ComplexShape::from_shape(area),
+            nine_slice: None,
             triggers: Default::default(),
             text: None,
+            rich_text: None,
             z: 0,
         }
     }
@@ -48,6 +54,18 @@ impl UiElement {
         Ok(self)
     }
 
+    /// Show text with styled spans and inline icons, see `RichText` for the markup.
+    pub fn with_rich_text(mut self, text: RichText) -> PaddleResult<Self> {
+        self.set_rich_text(Some(text))?;
+        Ok(self)
+    }
+
+    /// Shrink or shorten text that does not fit into the element, e.g. localized labels on buttons.
+    pub fn with_text_overflow(mut self, overflow: TextOverflow) -> PaddleResult<Self> {
+        self.set_text_overflow(overflow)?;
+        Ok(self)
+    }
+
     pub fn with_z(mut self, z: i16) -> Self {
         self.set_z(z);
         self
@@ -84,12 +102,27 @@ impl UiElement {
         self
     }
 
+    /// Draw a nine-slice image over the area of the element, instead of its shape and paint.
+    pub fn with_nine_slice(mut self, nine_slice: NineSlice) -> Self {
+        self.nine_slice = Some(nine_slice);
+        self
+    }
+
     pub fn draw(&self, canvas: &mut crate::DisplayArea) {
-        canvas.draw_ex(&self.shape, &self.paint, Transform::IDENTITY, self.z);
+        match &self.nine_slice {
+            Some(nine_slice) => canvas.draw_nine_slice(&self.area, nine_slice, self.z),
+            None => canvas.draw_ex(&self.shape, &self.paint, Transform::IDENTITY, self.z),
+        }
         if let Some(text) = self.text.as_ref() {
             text.borrow_mut()
                 .update_position(&canvas.frame_to_display_area(self.area), self.z)
                 .unwrap();
+            if let Some(rich_text) = &self.rich_text {
+                text.borrow_mut()
+                    .update_rich_text(rich_text, canvas.full().assets())
+                    .nuts_check();
+            }
+            text.borrow_mut().draw();
         }
     }
 
@@ -121,6 +154,7 @@ impl UiElement {
     }
 
     pub fn set_text(&mut self, text: Option<String>) -> PaddleResult<()> {
+        self.rich_text = None;
         if let Some(text) = text {
             if let Some(t) = &mut self.text {
                 t.borrow_mut().update_text(&text);
@@ -136,6 +170,18 @@ impl UiElement {
         Ok(())
     }
 
+    /// Set styled text, which is shown after the next draw of the element.
+    pub fn set_rich_text(&mut self, text: Option<RichText>) -> PaddleResult<()> {
+        if text.is_some() && self.text.is_none() {
+            self.text = Some(RefCell::new(FloatingText::new(&self.area, String::new())?));
+        }
+        if text.is_none() {
+            self.text = None;
+        }
+        self.rich_text = text;
+        Ok(())
+    }
+
     pub fn set_alignment(&mut self, fit: FitStrategy) -> PaddleResult<()> {
         let text = self
             .text
@@ -145,6 +191,15 @@ impl UiElement {
         Ok(())
     }
 
+    pub fn set_text_overflow(&mut self, overflow: TextOverflow) -> PaddleResult<()> {
+        let text = self
+            .text
+            .as_ref()
+            .ok_or_else(|| crate::ErrorMessage::technical("No text to be fitted".to_owned()))?;
+        text.borrow_mut().update_overflow(overflow)?;
+        Ok(())
+    }
+
     pub fn set_z(&mut self, z: i16) {
         self.z = z;
     }